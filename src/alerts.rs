@@ -0,0 +1,107 @@
+use std::fmt;
+
+/// Used when --bell or --flash is given without any --alert
+pub const DEFAULT_RULE: &str = "memory>95";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Cpu,
+    Memory,
+    Swap,
+}
+
+impl Metric {
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::Memory => "memory",
+            Metric::Swap => "swap",
+        }
+    }
+}
+
+/// Fires when `metric` (a percentage) goes above `threshold`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertRule {
+    pub metric: Metric,
+    pub threshold: f32,
+}
+
+impl AlertRule {
+    /// "memory>95" or "cpu > 90%"
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (metric, threshold) = rule
+            .split_once('>')
+            .ok_or_else(|| format!("alert must look like METRIC>PERCENT, got '{}'", rule))?;
+        let metric = match metric.trim() {
+            "cpu" => Metric::Cpu,
+            "memory" | "mem" => Metric::Memory,
+            "swap" => Metric::Swap,
+            other => return Err(format!("unknown alert metric '{}'", other)),
+        };
+        let threshold = threshold
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| format!("invalid alert threshold '{}'", threshold))?;
+        Ok(Self { metric, threshold })
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} > {}%", self.metric.name(), self.threshold)
+    }
+}
+
+/// Current value of every metric rules can refer to, in percent
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Readings {
+    pub cpu: f32,
+    pub memory: f32,
+    pub swap: f32,
+}
+
+impl Readings {
+    fn get(&self, metric: Metric) -> f32 {
+        match metric {
+            Metric::Cpu => self.cpu,
+            Metric::Memory => self.memory,
+            Metric::Swap => self.swap,
+        }
+    }
+}
+
+/// Tracks which rules are above their threshold, so an alert only fires
+/// once when it starts and again only after it cleared
+#[derive(Debug, Default)]
+pub struct AlertMonitor {
+    rules: Vec<(AlertRule, bool)>,
+}
+
+impl AlertMonitor {
+    pub fn new(rules: &[AlertRule]) -> Self {
+        Self {
+            rules: rules.iter().map(|rule| (*rule, false)).collect(),
+        }
+    }
+
+    /// Descriptions of the alerts that started firing with these readings
+    pub fn check(&mut self, readings: Readings) -> Vec<String> {
+        let mut fired = Vec::new();
+        for (rule, firing) in &mut self.rules {
+            let value = readings.get(rule.metric);
+            let above = value > rule.threshold;
+            if above && !*firing {
+                fired.push(format!(
+                    "Alert: {} at {:.1}% (> {}%)",
+                    rule.metric.name(),
+                    value,
+                    rule.threshold
+                ));
+            }
+            *firing = above;
+        }
+        fired
+    }
+}
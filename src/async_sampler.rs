@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::scheduler::Scheduler;
+
+/// Drive the samplers from the shared tick scheduler on a tokio runtime owned
+/// by a background thread. The samplers themselves do blocking /proc and
+/// sysinfo calls, so each run is handed to the blocking pool.
+pub fn spawn(state: Arc<Mutex<crate::tui::AppState>>) {
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_time()
+            .thread_name("m-as-sampler")
+            .build()
+            .expect("failed to start the sampler runtime");
+
+        runtime.block_on(async move {
+            let mut scheduler = Scheduler::new();
+            let mut running = HashMap::new();
+            loop {
+                let due = {
+                    let mut state = state.lock().unwrap();
+                    let due = scheduler.due(&state);
+                    if !due.is_empty() {
+                        state.sampled_at = scheduler.taken_at();
+                    }
+                    due
+                };
+                for (name, base, sample) in due {
+                    // Still busy with an earlier tick, this run is skipped rather than queued
+                    if running
+                        .get(name)
+                        .is_some_and(|task: &tokio::task::JoinHandle<()>| !task.is_finished())
+                    {
+                        continue;
+                    }
+                    let state = Arc::clone(&state);
+                    let task = tokio::task::spawn_blocking(move || {
+                        state.lock().unwrap().run_collector(name, base, sample);
+                    });
+                    running.insert(name, task);
+                }
+                tokio::time::sleep(scheduler.advance()).await;
+            }
+        });
+    });
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::history;
+
+const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+/// Ten minutes at the 5s battery refresh
+const HISTORY_CAPACITY: usize = 120;
+/// Charge readings the estimate is fitted over, older ones don't reflect the current load
+pub const ESTIMATE_WINDOW: Duration = Duration::from_secs(300);
+/// Below this the slope is mostly noise from the coarse charge counters, use the power draw
+const MIN_ESTIMATE_SPAN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, e.g. by a charge threshold
+    NotCharging,
+    Unknown,
+}
+
+impl BatteryState {
+    fn parse(status: &str) -> Self {
+        match status.trim() {
+            "Charging" => BatteryState::Charging,
+            "Discharging" => BatteryState::Discharging,
+            "Full" => BatteryState::Full,
+            "Not charging" => BatteryState::NotCharging,
+            _ => BatteryState::Unknown,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BatteryState::Charging => "charging",
+            BatteryState::Discharging => "discharging",
+            BatteryState::Full => "full",
+            BatteryState::NotCharging => "not charging",
+            BatteryState::Unknown => "unknown",
+        }
+    }
+}
+
+/// One `type=Battery` entry of /sys/class/power_supply
+#[derive(Debug, Clone)]
+pub struct Battery {
+    /// "BAT0"
+    pub name: String,
+    pub state: BatteryState,
+    pub energy_wh: f64,
+    pub full_wh: f64,
+    /// What the firmware reports right now, None when it reports neither power nor current
+    pub power_w: Option<f64>,
+}
+
+impl Battery {
+    pub fn percent(&self) -> f64 {
+        self.energy_wh / self.full_wh.max(f64::EPSILON) * 100.0
+    }
+
+    /// Batteries report either energy (µWh, µW) or charge (µAh, µA) plus the voltage
+    fn read(dir: &Path) -> Option<Self> {
+        let read = |file: &str| fs::read_to_string(dir.join(file)).ok();
+        let number = |file: &str| read(file)?.trim().parse::<f64>().ok();
+        if read("type")?.trim() != "Battery" || number("present") == Some(0.0) {
+            return None;
+        }
+        let volts = number("voltage_now").map(|uv| uv / 1e6);
+        let wh = |energy: &str, charge: &str| {
+            number(energy)
+                .map(|uwh| uwh / 1e6)
+                .or_else(|| Some(number(charge)? / 1e6 * volts?))
+        };
+        Some(Battery {
+            name: dir.file_name()?.to_string_lossy().into_owned(),
+            state: BatteryState::parse(&read("status").unwrap_or_default()),
+            energy_wh: wh("energy_now", "charge_now")?,
+            full_wh: wh("energy_full", "charge_full")?,
+            power_w: number("power_now")
+                .map(|uw| uw / 1e6)
+                .or_else(|| Some(number("current_now")? / 1e6 * volts?))
+                .map(f64::abs),
+        })
+    }
+}
+
+/// How long until the batteries are empty, or full while charging
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Estimate {
+    Empty(Duration),
+    Full(Duration),
+}
+
+/// Batteries with their combined power draw history and a runtime estimate
+/// from the recent discharge slope, which evens out the jumpy instant power.
+#[derive(Debug, Default)]
+pub struct BatteryMonitor {
+    pub batteries: Vec<Battery>,
+    /// Combined draw in W, one point per update
+    pub power_history: VecDeque<f64>,
+    /// Estimated hours left, one point per update while discharging
+    pub runtime_history: VecDeque<f64>,
+    pub estimate: Option<Estimate>,
+    /// (time, combined Wh) since the batteries last changed state
+    readings: VecDeque<(Instant, f64)>,
+    charging: Option<bool>,
+}
+
+impl BatteryMonitor {
+    pub fn new() -> Self {
+        let mut monitor = Self::default();
+        monitor.update();
+        monitor
+    }
+
+    pub fn update(&mut self) {
+        self.batteries = fs::read_dir(POWER_SUPPLY_ROOT)
+            .map(|entries| {
+                let mut batteries: Vec<Battery> = entries
+                    .flatten()
+                    .filter_map(|entry| Battery::read(&entry.path()))
+                    .collect();
+                batteries.sort_by(|a, b| a.name.cmp(&b.name));
+                batteries
+            })
+            .unwrap_or_default();
+        if self.batteries.is_empty() {
+            self.estimate = None;
+            return;
+        }
+
+        let energy: f64 = self.batteries.iter().map(|b| b.energy_wh).sum();
+        let full: f64 = self.batteries.iter().map(|b| b.full_wh).sum();
+        let power: Option<f64> = self.batteries.iter().map(|b| b.power_w).sum();
+        history::push_capped(
+            &mut self.power_history,
+            power.unwrap_or(0.0),
+            HISTORY_CAPACITY,
+        );
+
+        let discharging = self
+            .batteries
+            .iter()
+            .any(|b| b.state == BatteryState::Discharging);
+        let charging = self
+            .batteries
+            .iter()
+            .any(|b| b.state == BatteryState::Charging);
+        // Plugging in or out makes the old slope meaningless
+        if self.charging != Some(charging) {
+            self.readings.clear();
+            self.runtime_history.clear();
+            self.charging = Some(charging);
+        }
+        let now = Instant::now();
+        self.readings.push_back((now, energy));
+        while self
+            .readings
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > ESTIMATE_WINDOW)
+        {
+            self.readings.pop_front();
+        }
+
+        // Wh per hour, negative while discharging
+        let span = self
+            .readings
+            .front()
+            .map_or(Duration::ZERO, |(at, _)| now.duration_since(*at));
+        let slope = if span >= MIN_ESTIMATE_SPAN {
+            history::slope(self.readings.iter().map(|(at, wh)| {
+                (
+                    at.duration_since(self.readings[0].0).as_secs_f64() / 3600.0,
+                    *wh,
+                )
+            }))
+        } else {
+            None
+        }
+        .or_else(|| power.map(|w| if discharging { -w } else { w }));
+
+        let hours = |wh: f64, rate: f64| Duration::from_secs_f64((wh / rate * 3600.0).min(1e7));
+        self.estimate = match slope {
+            Some(slope) if discharging && slope < 0.0 => {
+                Some(Estimate::Empty(hours(energy, -slope)))
+            }
+            Some(slope) if charging && slope > 0.0 => {
+                Some(Estimate::Full(hours((full - energy).max(0.0), slope)))
+            }
+            _ => None,
+        };
+        if let Some(Estimate::Empty(left)) = self.estimate {
+            history::push_capped(
+                &mut self.runtime_history,
+                left.as_secs_f64() / 3600.0,
+                HISTORY_CAPACITY,
+            );
+        }
+    }
+}
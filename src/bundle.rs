@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::capabilities::Capabilities;
+use crate::cli::Args;
+use crate::cpu::CpuInfo;
+use crate::memory::MemoryInfo;
+use crate::process;
+use crate::snapshot::Snapshot;
+
+/// How many snapshots go into history.jsonl, taken `--interval` apart
+const HISTORY_SAMPLES: usize = 5;
+
+/// Collect a short history and write everything support needs about
+/// "the machine is slow" into a single .tar.gz
+pub fn run(args: &Args, output: Option<PathBuf>) -> Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let output =
+        output.unwrap_or_else(|| PathBuf::from(format!("m-as-bundle-{}.tar.gz", timestamp)));
+
+    eprintln!(
+        "Collecting {} samples {}ms apart...",
+        HISTORY_SAMPLES,
+        args.interval.as_millis()
+    );
+    let history = collect_history(args.interval)?;
+    let latest = history.last().cloned().unwrap_or_default();
+
+    let encoder = GzEncoder::new(File::create(&output)?, Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+    let root = format!("m-as-bundle-{}", timestamp);
+
+    append(&mut archive, &root, "snapshot.json", latest.as_bytes())?;
+    append(
+        &mut archive,
+        &root,
+        "history.jsonl",
+        history.join("\n").as_bytes(),
+    )?;
+    append(
+        &mut archive,
+        &root,
+        "config.txt",
+        format!("{:#?}\n", args).as_bytes(),
+    )?;
+    append(
+        &mut archive,
+        &root,
+        "platform.txt",
+        platform_report().as_bytes(),
+    )?;
+
+    archive.into_inner()?.finish()?;
+    eprintln!("Wrote {}", output.display());
+    Ok(())
+}
+
+fn collect_history(interval: Duration) -> Result<Vec<String>> {
+    let mut cpu_info = CpuInfo::new_sampled();
+    let mut memory_info = MemoryInfo::new();
+    let mut history = Vec::with_capacity(HISTORY_SAMPLES);
+
+    for i in 0..HISTORY_SAMPLES {
+        if i > 0 {
+            thread::sleep(interval);
+        }
+        cpu_info.update();
+        memory_info.update();
+        let mut processes = process::get_all_processes();
+        process::sort_by_memory(&mut processes);
+        let snapshot = Snapshot::capture(&cpu_info, &memory_info, &processes);
+        history.push(serde_json::to_string(&snapshot)?);
+    }
+    Ok(history)
+}
+
+fn append(
+    archive: &mut tar::Builder<GzEncoder<File>>,
+    root: &str,
+    name: &str,
+    contents: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+    );
+    header.set_cksum();
+    archive.append_data(&mut header, Path::new(root).join(name), contents)?;
+    Ok(())
+}
+
+/// What this machine lets m-as see, so missing data in a bundle can be explained
+fn platform_report() -> String {
+    let check = |available: bool| if available { "yes" } else { "no" };
+    let readable = |path: &str| File::open(path).is_ok();
+
+    let mut report = String::new();
+    report.push_str(&format!(
+        "os: {}\n",
+        sysinfo::System::long_os_version().unwrap_or_else(|| "unknown".to_string())
+    ));
+    report.push_str(&format!(
+        "kernel: {}\n",
+        sysinfo::System::kernel_version().unwrap_or_else(|| "unknown".to_string())
+    ));
+    report.push_str(&format!("arch: {}\n", std::env::consts::ARCH));
+    report.push_str(&format!("m-as version: {}\n\n", env!("CARGO_PKG_VERSION")));
+
+    report.push_str(&format!(
+        "/proc/stat readable: {}\n",
+        check(readable("/proc/stat"))
+    ));
+    report.push_str(&format!(
+        "/proc/diskstats readable: {}\n",
+        check(readable("/proc/diskstats"))
+    ));
+    report.push_str(&format!(
+        "utmp readable: {}\n",
+        check(readable("/var/run/utmp"))
+    ));
+    report.push_str(&format!(
+        "auth log readable: {}\n",
+        check(readable("/var/log/auth.log") || readable("/var/log/secure"))
+    ));
+    report.push_str(&format!(
+        "cgroup v2: {}\n",
+        check(Path::new("/sys/fs/cgroup/cgroup.controllers").exists())
+    ));
+    report.push_str(&format!(
+        "systemctl: {}\n",
+        check(
+            Command::new("systemctl")
+                .arg("--version")
+                .output()
+                .is_ok_and(|o| o.status.success())
+        )
+    ));
+
+    let capabilities = Capabilities::detect();
+    if capabilities.denied().is_empty() {
+        report.push_str("\npermission denied: nothing\n");
+    } else {
+        report.push_str("\npermission denied:\n");
+        for capability in capabilities.denied() {
+            report.push_str(&format!("  {}\n", capability));
+        }
+    }
+    report
+}
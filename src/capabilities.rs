@@ -0,0 +1,182 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use crate::cpufreq;
+
+// Bit numbers from linux/capability.h
+const CAP_KILL: u32 = 5;
+const CAP_SYS_PTRACE: u32 = 19;
+const CAP_SYS_ADMIN: u32 = 21;
+const CAP_SYS_NICE: u32 = 23;
+const CAP_SYS_RESOURCE: u32 = 24;
+
+const AUTH_LOGS: [&str; 2] = ["/var/log/auth.log", "/var/log/secure"];
+
+/// Data or actions that depend on privileges the monitor may not have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// /proc mounted with hidepid hides everybody else's processes
+    OtherProcesses,
+    /// Open file counts of other users' processes
+    OpenFiles,
+    /// Failed login counts in the Users tab
+    AuthLog,
+    /// Lowering nice values, or renicing other users' processes
+    Renice,
+    /// Signalling other users' processes
+    SignalOthers,
+    /// Switching the CPU frequency governor
+    CpuFreq,
+    /// Kernel stacks in the wait trace, /proc/<pid>/stack is root only
+    KernelStacks,
+    /// Lowering oom_score_adj to protect a process from the OOM killer
+    OomProtect,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 8] = [
+        Capability::OtherProcesses,
+        Capability::OpenFiles,
+        Capability::AuthLog,
+        Capability::Renice,
+        Capability::SignalOthers,
+        Capability::CpuFreq,
+        Capability::KernelStacks,
+        Capability::OomProtect,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Capability::OtherProcesses => "other users' processes",
+            Capability::OpenFiles => "open files of other users' processes",
+            Capability::AuthLog => "failed logins from the auth log",
+            Capability::Renice => "raising priorities and renicing other users' processes",
+            Capability::SignalOthers => "killing other users' processes",
+            Capability::CpuFreq => "switching the CPU governor",
+            Capability::KernelStacks => "kernel stacks of waiting processes",
+            Capability::OomProtect => "protecting processes from the OOM killer",
+        }
+    }
+
+    /// What would make it available
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Capability::OtherProcesses => "/proc is mounted with hidepid, run as root",
+            Capability::OpenFiles => "run as root or with CAP_SYS_PTRACE",
+            Capability::AuthLog => "add your user to the adm group",
+            Capability::Renice => "run as root or with CAP_SYS_NICE",
+            Capability::SignalOthers => "run as root or with CAP_KILL",
+            Capability::CpuFreq => "run as root",
+            Capability::KernelStacks => "run as root or with CAP_SYS_ADMIN",
+            Capability::OomProtect => "run as root or with CAP_SYS_RESOURCE",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.description(), self.hint())
+    }
+}
+
+/// Which capabilities the monitor is missing, detected once at startup
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    denied: Vec<Capability>,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        let root = is_root();
+        let effective = effective_capabilities();
+        let has = |bit: u32| root || effective & (1 << bit) != 0;
+
+        let denied = Capability::ALL
+            .into_iter()
+            .filter(|capability| match capability {
+                Capability::OtherProcesses => !root && proc_hides_pids(),
+                Capability::OpenFiles => !has(CAP_SYS_PTRACE),
+                // Without any auth log there is nothing a permission would fix
+                Capability::AuthLog => {
+                    let existing: Vec<&str> = AUTH_LOGS
+                        .into_iter()
+                        .filter(|path| Path::new(path).exists())
+                        .collect();
+                    !existing.is_empty() && !existing.iter().any(|path| File::open(path).is_ok())
+                }
+                Capability::Renice => !has(CAP_SYS_NICE),
+                Capability::SignalOthers => !has(CAP_KILL),
+                // Like the auth log, without cpufreq there is nothing to switch
+                Capability::CpuFreq => {
+                    let policies = cpufreq::read_policies();
+                    !policies.is_empty() && !cpufreq::can_switch(&policies)
+                }
+                Capability::KernelStacks => !has(CAP_SYS_ADMIN),
+                Capability::OomProtect => !has(CAP_SYS_RESOURCE),
+            })
+            .collect();
+        Self { denied }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        !self.denied.contains(&capability)
+    }
+
+    pub fn denied(&self) -> &[Capability] {
+        &self.denied
+    }
+}
+
+/// The error with a hint on how to get the missing permission, when that's what it is
+pub fn explain(error: &io::Error, capability: Capability) -> String {
+    if error.kind() == io::ErrorKind::PermissionDenied {
+        format!("{}, {}", error, capability.hint())
+    } else {
+        error.to_string()
+    }
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and can't fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+/// The CapEff mask from /proc/self/status, 0 where there is none
+fn effective_capabilities() -> u64 {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("CapEff:"))
+                .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+        })
+        .unwrap_or(0)
+}
+
+/// Whether /proc is mounted with hidepid=1/2 (or invisible/noaccess)
+fn proc_hides_pids() -> bool {
+    let Ok(mounts) = fs::read_to_string("/proc/mounts") else {
+        return false;
+    };
+    mounts.lines().any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        fields.get(1) == Some(&"/proc")
+            && fields.get(2) == Some(&"proc")
+            && fields.get(3).is_some_and(|options| {
+                options.split(',').any(|option| {
+                    option
+                        .strip_prefix("hidepid=")
+                        .is_some_and(|value| !matches!(value, "0" | "off"))
+                })
+            })
+    })
+}
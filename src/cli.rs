@@ -0,0 +1,605 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+
+use crate::alerts::{self, AlertRule};
+use crate::columns::{self, Column};
+use crate::disk::MountFilter;
+use crate::influx::{self, Endpoint};
+use crate::memory::UsageThresholds;
+use crate::probes::Target;
+use crate::process::{DEFAULT_LEAK_SLOPE_MB_PER_MIN, DEFAULT_LEAK_WINDOW};
+use crate::remote::Address;
+use crate::selfmon::DEFAULT_COLLECTOR_BUDGET;
+use crate::units::{Prefix, RateUnit, Units};
+
+const USAGE: &str = "\
+Usage: møas [COMMAND] [OPTIONS]
+
+Commands:
+  bundle               Write a support bundle (snapshot, recent history, config and
+                       platform report) to a .tar.gz, path set with --output
+  top                  Print a table of the busiest processes once and exit, -n <N> rows
+                       (default: 10) sorted by --sort cpu|mem (default: cpu)
+  watch <PID|NAME>     Print the CPU, memory, I/O and children of one process every --interval,
+                       exit with status 1 when it is gone
+  run -- <COMMAND>...  Start COMMAND, follow it and its children in the TUI (output in the
+                       Log tab) and print its peak memory, CPU time and I/O when it exits
+
+Options:
+  --stream <FORMAT>    Print one snapshot per line instead of the TUI (formats: json)
+  --plain              Print a short summary in plain sentences instead of the TUI, for
+                       screen readers and logs (every 5s unless --interval is given)
+  --interval <DURATION>
+                       Sampling interval, e.g. 500ms or 2s, plain numbers are milliseconds
+                       (default: 1s). Used by --stream, --plain (default: 5s), --serve, --ssh,
+                       watch and between bundle history samples
+  --columns <LIST>     Process fields in stream output, e.g. pid,name,cpu,rss,user,cmdline
+                       (also: time, swap, mem_delta, cpu_delta, status, ppid, pgid, sid, fds, origin;
+                       rss/swap are bytes, time is CPU time in ms, mem_delta is MB since the
+                       previous line)
+  --output <PATH>      Record the stream to a file instead of stdout
+  --rotate-size <SIZE> Rotate the recording once it reaches SIZE (e.g. 100M, 1G)
+  --rotate-every <DUR> Rotate the recording after DUR (e.g. 3600s)
+  --keep <N>           Number of rotated recordings to keep (default: 5)
+  --fsync <DUR>        fsync the recording every DUR so a crash loses at most that much
+  --memory-thresholds <WARN,CRIT>
+                       Memory/swap gauge color thresholds in percent (default: 70,90)
+  --mount-include <GLOB>
+                       Only show disks whose mount point, filesystem or device matches,
+                       can be given several times
+  --mount-exclude <GLOB>
+                       Hide matching disks in addition to the defaults (/snap/*, tmpfs,
+                       devtmpfs, squashfs, loop*), can be given several times
+  --all-mounts         Start with every mount shown (toggle with m)
+  --bits               Show throughput in bits per second instead of bytes
+  --si                 Use powers of 1000 (kB, MB) instead of 1024 (KiB, MiB)
+  --alert <RULE>       Alert when a metric goes above a percentage, e.g. memory>95 (metrics:
+                       cpu, memory, swap), can be given several times
+  --bell               Ring the terminal bell when an alert fires
+  --flash              Flash the screen border when an alert fires
+                       (both default to --alert memory>95)
+  --ping <HOST>        Ping HOST every 5s and chart the latency in the Network tab,
+                       can be given several times
+  --resolve <NAME>     Resolve NAME every 5s and chart the lookup time, can be given
+                       several times
+  --sampler-budget <PERCENT>
+                       Share of its interval a collector may spend sampling before it is
+                       slowed down, e.g. on servers with thousands of processes (default: 15)
+  --freeze-delay <DUR> Keep the process rows in place for DUR after the last navigation key so
+                       the selection doesn't jump, e.g. 3s or off (default: 2s, u resumes)
+  --smoothing <ALPHA>  Weight of each new sample in the smoothed charts, between 0 and 1
+                       (default: 0.3, toggle smoothing with S)
+  --leak-window <DUR>  How far back the leak detector looks at memory growth (default: 300s)
+  --leak-slope <MB>    Growth in MB per minute from which a process whose memory only went
+                       up is listed as a leak suspect (default: 1)
+  --accessible         High contrast selection and focus, no sparklines, fewer redraws
+  --monochrome         Like --accessible without any colors, also set by NO_COLOR
+  --show-overhead      Show the monitor's own CPU and memory usage in a status line
+  --serve-readonly     Read-only TUI for SSH forced commands: kill/service actions and exports are disabled
+  --process-log <PATH> Append process start/exit events to PATH
+  --history-db <PATH>  Record CPU, memory and swap usage every 10s to a SQLite database,
+                       browsed in the History tab (also recorded in --stream mode)
+  --influx <URL>       Push metrics in InfluxDB line protocol to URL, e.g.
+                       http://localhost:8086/write?db=m_as or
+                       http://localhost:8086/api/v2/write?org=ORG&bucket=BUCKET, with the
+                       token from $INFLUX_TOKEN (also pushed in --stream mode)
+  --influx-interval <DUR>
+                       How often to push to --influx (default: 10s)
+  --mirror             Render the data of an already running instance instead of sampling
+  --socket <PATH>      Socket used for mirroring (default: $XDG_RUNTIME_DIR/m-as.sock)
+  --serve <ADDR>       Sample without a TUI and send snapshots to every client connecting to
+                       ADDR: unix:PATH, HOST:PORT, systemd for socket activation, or stdio
+                       for a single client on stdout. The interval is set with --interval
+  --connect <ADDR>     Render the data of a --serve instance at unix:PATH or HOST:PORT,
+                       actions are disabled since the processes are remote
+  --ssh <DEST>         Run `møas --serve stdio` on DEST (user@host) through ssh and render its
+                       data, needs key based login and m-as installed there
+  --ssh-command <CMD>  m-as command on the remote host (default: møas)
+  -h, --help           Print this help";
+
+/// How long the process rows stay in place after the last navigation key
+pub const DEFAULT_FREEZE_DELAY: Duration = Duration::from_secs(2);
+
+/// How often --plain prints a summary without --interval
+pub const PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Rows printed by `top` without -n
+pub const DEFAULT_TOP_COUNT: usize = 10;
+
+/// EMA weight of a new sample, about a 5 sample memory
+pub const DEFAULT_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopSort {
+    Cpu,
+    Memory,
+}
+
+/// Where and how a stream recording is written
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    pub path: PathBuf,
+    pub max_size: Option<u64>,
+    pub max_age: Option<Duration>,
+    pub keep: usize,
+    pub fsync_interval: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Support bundle, written to the given path or a timestamped default
+    Bundle { output: Option<PathBuf> },
+    /// One table of the top processes
+    Top { count: usize, sort: TopSort },
+    /// Follow one process, given as PID or unique name
+    Watch { target: String },
+    /// Start a command and follow it, everything after `run` (and `--`)
+    Run { command: Vec<String> },
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub command: Option<Command>,
+    pub stream: Option<StreamFormat>,
+    pub interval: Duration,
+    /// Set when `--output` was given
+    pub record: Option<RecordOptions>,
+    /// Process fields to stream, everything when None
+    pub columns: Option<Vec<Column>>,
+    pub memory_thresholds: UsageThresholds,
+    /// Which mounts the disk panel shows
+    pub mount_filter: MountFilter,
+    pub show_overhead: bool,
+    /// Share of the interval each collector may spend, 0.15 is 15%
+    pub sampler_budget: f64,
+    /// Bits vs bytes and SI vs binary prefixes for every panel
+    pub units: Units,
+    pub alerts: Vec<AlertRule>,
+    pub bell: bool,
+    pub flash: bool,
+    /// Hosts to ping and names to resolve periodically
+    pub probes: Vec<Target>,
+    /// None turns holding the row order while navigating off
+    pub freeze_delay: Option<Duration>,
+    /// EMA weight used while chart smoothing is on
+    pub smoothing: f64,
+    pub leak_window: Duration,
+    pub leak_slope: f64,
+    /// High contrast, reduced motion rendering
+    pub accessible: bool,
+    pub monochrome: bool,
+    /// Text summaries instead of the TUI
+    pub plain: bool,
+    /// Shared monitoring access, no action may change the system
+    pub read_only: bool,
+    pub process_log: Option<PathBuf>,
+    /// SQLite file the usage history is recorded to
+    pub history_db: Option<PathBuf>,
+    pub influx: Option<Endpoint>,
+    pub influx_interval: Duration,
+    /// Attach to another instance instead of collecting data
+    pub mirror: bool,
+    pub socket: Option<PathBuf>,
+    /// Headless mode streaming snapshots to remote clients
+    pub serve: Option<Address>,
+    /// Render a `--serve` instance's data instead of collecting
+    pub connect: Option<Address>,
+    /// ssh destination to start a server on and render
+    pub ssh: Option<String>,
+    pub ssh_command: String,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut parsed = Args {
+            command: None,
+            stream: None,
+            interval: Duration::from_millis(1000),
+            record: None,
+            columns: None,
+            memory_thresholds: UsageThresholds::default(),
+            mount_filter: MountFilter::default(),
+            show_overhead: false,
+            sampler_budget: DEFAULT_COLLECTOR_BUDGET,
+            units: Units::default(),
+            alerts: Vec::new(),
+            bell: false,
+            flash: false,
+            probes: Vec::new(),
+            freeze_delay: Some(DEFAULT_FREEZE_DELAY),
+            smoothing: DEFAULT_SMOOTHING,
+            leak_window: DEFAULT_LEAK_WINDOW,
+            leak_slope: DEFAULT_LEAK_SLOPE_MB_PER_MIN,
+            accessible: false,
+            monochrome: false,
+            plain: false,
+            read_only: false,
+            process_log: None,
+            history_db: None,
+            influx: None,
+            influx_interval: influx::DEFAULT_INTERVAL,
+            mirror: false,
+            socket: None,
+            serve: None,
+            connect: None,
+            ssh: None,
+            ssh_command: "møas".to_string(),
+        };
+        let mut output = None;
+        let mut max_size = None;
+        let mut max_age = None;
+        let mut keep = 5;
+        let mut fsync_interval = None;
+        let mut interval_given = false;
+        let mut top_count = None;
+        let mut top_sort = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "bundle" if parsed.command.is_none() => {
+                    parsed.command = Some(Command::Bundle { output: None });
+                }
+                "top" if parsed.command.is_none() => {
+                    parsed.command = Some(Command::Top {
+                        count: DEFAULT_TOP_COUNT,
+                        sort: TopSort::Cpu,
+                    });
+                }
+                "watch" if parsed.command.is_none() => {
+                    let target = args
+                        .next()
+                        .ok_or_else(|| eyre!("watch needs a PID or process name"))?;
+                    parsed.command = Some(Command::Watch { target });
+                }
+                "run" if parsed.command.is_none() => {
+                    let mut command: Vec<String> = args.by_ref().collect();
+                    if command.first().is_some_and(|arg| arg == "--") {
+                        command.remove(0);
+                    }
+                    if command.is_empty() {
+                        bail!("run needs a command, e.g. møas run -- make -j8");
+                    }
+                    parsed.command = Some(Command::Run { command });
+                }
+                "-n" => {
+                    let value = next_value(&mut args, &arg)?;
+                    top_count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|&count: &usize| count > 0)
+                            .ok_or_else(|| eyre!("invalid count '{}'", value))?,
+                    );
+                }
+                "--sort" => {
+                    let value = next_value(&mut args, &arg)?;
+                    top_sort = Some(match value.as_str() {
+                        "cpu" => TopSort::Cpu,
+                        "mem" | "memory" => TopSort::Memory,
+                        other => bail!("unknown sort '{}', use cpu or mem", other),
+                    });
+                }
+                "--stream" => {
+                    let format = next_value(&mut args, &arg)?;
+                    parsed.stream = Some(match format.as_str() {
+                        "json" => StreamFormat::Json,
+                        other => bail!("unknown stream format '{}'", other),
+                    });
+                }
+                "--interval" => {
+                    parsed.interval = parse_interval(&next_value(&mut args, &arg)?)?;
+                    interval_given = true;
+                }
+                "--columns" => {
+                    let list = next_value(&mut args, &arg)?;
+                    parsed.columns = Some(columns::parse_list(&list).map_err(|e| eyre!(e))?);
+                }
+                "--output" => output = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+                "--rotate-size" => max_size = Some(parse_size(&next_value(&mut args, &arg)?)?),
+                "--rotate-every" => {
+                    max_age = Some(parse_interval(&next_value(&mut args, &arg)?)?);
+                }
+                "--keep" => {
+                    let value = next_value(&mut args, &arg)?;
+                    keep = value
+                        .parse()
+                        .map_err(|_| eyre!("invalid count '{}'", value))?;
+                }
+                "--fsync" => {
+                    fsync_interval = Some(parse_interval(&next_value(&mut args, &arg)?)?);
+                }
+                "--memory-thresholds" => {
+                    parsed.memory_thresholds = parse_thresholds(&next_value(&mut args, &arg)?)?;
+                }
+                "--mount-include" => {
+                    parsed
+                        .mount_filter
+                        .include
+                        .push(next_value(&mut args, &arg)?);
+                }
+                "--mount-exclude" => {
+                    parsed
+                        .mount_filter
+                        .exclude
+                        .push(next_value(&mut args, &arg)?);
+                }
+                "--all-mounts" => parsed.mount_filter.show_all = true,
+                "--show-overhead" => parsed.show_overhead = true,
+                "--sampler-budget" => {
+                    parsed.sampler_budget = parse_budget(&next_value(&mut args, &arg)?)?;
+                }
+                "--bits" => parsed.units.rate_unit = RateUnit::Bits,
+                "--si" => parsed.units.prefix = Prefix::Si,
+                "--alert" => {
+                    let rule = next_value(&mut args, &arg)?;
+                    parsed
+                        .alerts
+                        .push(AlertRule::parse(&rule).map_err(|e| eyre!(e))?);
+                }
+                "--bell" => parsed.bell = true,
+                "--flash" => parsed.flash = true,
+                "--ping" => parsed
+                    .probes
+                    .push(Target::Ping(next_value(&mut args, &arg)?)),
+                "--resolve" => {
+                    parsed
+                        .probes
+                        .push(Target::Resolve(next_value(&mut args, &arg)?));
+                }
+                "--freeze-delay" => {
+                    parsed.freeze_delay = match next_value(&mut args, &arg)?.as_str() {
+                        "off" | "0" => None,
+                        value => Some(parse_interval(value)?),
+                    };
+                }
+                "--smoothing" => {
+                    parsed.smoothing = parse_smoothing(&next_value(&mut args, &arg)?)?;
+                }
+                "--leak-window" => {
+                    parsed.leak_window = parse_interval(&next_value(&mut args, &arg)?)?;
+                }
+                "--leak-slope" => {
+                    let value = next_value(&mut args, &arg)?;
+                    parsed.leak_slope = value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|slope: &f64| *slope > 0.0)
+                        .ok_or_else(|| eyre!("invalid leak slope '{}'", value))?;
+                }
+                "--accessible" => parsed.accessible = true,
+                "--monochrome" => parsed.monochrome = true,
+                "--plain" => parsed.plain = true,
+                "--serve-readonly" => parsed.read_only = true,
+                "--process-log" => {
+                    parsed.process_log = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--history-db" => {
+                    parsed.history_db = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--influx" => {
+                    let url = next_value(&mut args, &arg)?;
+                    parsed.influx =
+                        Some(Endpoint::parse(&url).map_err(|e| eyre!("--influx: {}", e))?);
+                }
+                "--influx-interval" => {
+                    parsed.influx_interval = parse_interval(&next_value(&mut args, &arg)?)?;
+                }
+                "--mirror" => parsed.mirror = true,
+                "--socket" => parsed.socket = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+                "--serve" => {
+                    let address = next_value(&mut args, &arg)?;
+                    parsed.serve = Some(Address::parse(&address).map_err(|e| eyre!(e))?);
+                }
+                "--connect" => {
+                    let address = next_value(&mut args, &arg)?;
+                    parsed.connect = Some(Address::parse(&address).map_err(|e| eyre!(e))?);
+                }
+                "--ssh" => parsed.ssh = Some(next_value(&mut args, &arg)?),
+                "--ssh-command" => parsed.ssh_command = next_value(&mut args, &arg)?,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                other => bail!("unknown argument '{}'\n\n{}", other, USAGE),
+            }
+        }
+
+        if let Some(Command::Bundle {
+            output: bundle_output,
+        }) = &mut parsed.command
+        {
+            *bundle_output = output.take();
+        }
+        match &mut parsed.command {
+            Some(Command::Top { count, sort }) => {
+                *count = top_count.unwrap_or(*count);
+                *sort = top_sort.unwrap_or(*sort);
+            }
+            _ if top_count.is_some() || top_sort.is_some() => {
+                bail!("-n and --sort only work with the top command");
+            }
+            _ => {}
+        }
+
+        if (parsed.bell || parsed.flash) && parsed.alerts.is_empty() {
+            parsed
+                .alerts
+                .push(AlertRule::parse(alerts::DEFAULT_RULE).map_err(|e| eyre!(e))?);
+        }
+
+        if parsed.plain {
+            if parsed.stream.is_some()
+                || parsed.serve.is_some()
+                || parsed.mirror
+                || parsed.connect.is_some()
+                || parsed.ssh.is_some()
+            {
+                bail!("--plain can't be combined with --stream, --serve, --mirror, --connect or --ssh");
+            }
+            // A summary every second is too much to listen to
+            if !interval_given {
+                parsed.interval = PLAIN_INTERVAL;
+            }
+        }
+
+        if matches!(parsed.command, Some(Command::Run { .. }))
+            && (parsed.stream.is_some()
+                || parsed.serve.is_some()
+                || parsed.mirror
+                || parsed.connect.is_some()
+                || parsed.ssh.is_some()
+                || parsed.plain)
+        {
+            bail!("run can't be combined with --stream, --serve, --mirror, --connect, --ssh or --plain");
+        }
+        if parsed.serve.is_some() && (parsed.stream.is_some() || parsed.mirror) {
+            bail!("--serve can't be combined with --stream or --mirror");
+        }
+        if parsed.ssh.is_some()
+            && (parsed.connect.is_some() || parsed.serve.is_some() || parsed.mirror)
+        {
+            bail!("--ssh can't be combined with --connect, --serve or --mirror");
+        }
+        // PIDs in the list belong to the other machine
+        if parsed.ssh.is_some() {
+            parsed.read_only = true;
+        }
+        match &parsed.connect {
+            Some(_) if parsed.serve.is_some() || parsed.mirror => {
+                bail!("--connect can't be combined with --serve or --mirror");
+            }
+            Some(Address::Systemd | Address::Stdio) => {
+                bail!("--connect needs unix:PATH or HOST:PORT")
+            }
+            // PIDs in the list belong to the other machine
+            Some(_) => parsed.read_only = true,
+            None => {}
+        }
+
+        if parsed.columns.is_some() && parsed.stream.is_none() {
+            bail!("--columns only works together with --stream");
+        }
+
+        if let Some(path) = output {
+            if parsed.stream.is_none() {
+                bail!("--output only works together with --stream");
+            }
+            parsed.record = Some(RecordOptions {
+                path,
+                max_size,
+                max_age,
+                keep,
+                fsync_interval,
+            });
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Accepts plain milliseconds ("500") or a suffixed value ("500ms", "2s")
+pub fn parse_interval(value: &str) -> Result<Duration> {
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        (secs, 1000)
+    } else {
+        (value, 1)
+    };
+
+    let millis: u64 = number
+        .trim()
+        .parse()
+        .map_err(|_| eyre!("invalid interval '{}'", value))?;
+    if millis == 0 {
+        bail!("interval must be greater than zero");
+    }
+    millis
+        .checked_mul(scale)
+        .map(Duration::from_millis)
+        .ok_or_else(|| eyre!("interval '{}' is too large", value))
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next().ok_or_else(|| eyre!("{} needs a value", flag))
+}
+
+/// Accepts plain bytes ("1048576") or a binary suffix ("512K", "100M", "1G")
+pub fn parse_size(value: &str) -> Result<u64> {
+    let upper = value.trim().to_ascii_uppercase();
+    let (number, scale) = match upper.chars().last() {
+        Some('K') => (&upper[..upper.len() - 1], 1024),
+        Some('M') => (&upper[..upper.len() - 1], 1024 * 1024),
+        Some('G') => (&upper[..upper.len() - 1], 1024 * 1024 * 1024),
+        _ => (upper.as_str(), 1),
+    };
+
+    let size: u64 = number
+        .parse()
+        .map_err(|_| eyre!("invalid size '{}'", value))?;
+    if size == 0 {
+        bail!("size must be greater than zero");
+    }
+    size.checked_mul(scale)
+        .ok_or_else(|| eyre!("size '{}' is too large", value))
+}
+
+/// "15" or "15%" -> 0.15
+pub fn parse_budget(value: &str) -> Result<f64> {
+    let percent: f64 = value
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| eyre!("invalid budget '{}'", value))?;
+    if !(percent > 0.0 && percent <= 100.0) {
+        bail!("budget must be between 0 and 100 percent");
+    }
+    Ok(percent / 100.0)
+}
+
+/// "0.3" -> 0.3, has to be above 0 and at most 1
+pub fn parse_smoothing(value: &str) -> Result<f64> {
+    let alpha: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| eyre!("invalid smoothing factor '{}'", value))?;
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        bail!("smoothing factor must be above 0 and at most 1");
+    }
+    Ok(alpha)
+}
+
+/// "70,90" -> warning at 70%, critical at 90%
+pub fn parse_thresholds(value: &str) -> Result<UsageThresholds> {
+    let (warning, critical) = value
+        .split_once(',')
+        .ok_or_else(|| eyre!("thresholds must look like WARN,CRIT, got '{}'", value))?;
+    let parse = |v: &str| -> Result<f32> {
+        v.trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| eyre!("invalid threshold '{}'", v))
+    };
+    let thresholds = UsageThresholds {
+        warning: parse(warning)?,
+        critical: parse(critical)?,
+    };
+    if thresholds.warning > thresholds.critical {
+        bail!("warning threshold must not be above the critical one");
+    }
+    Ok(thresholds)
+}
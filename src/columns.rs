@@ -0,0 +1,384 @@
+use ratatui::layout::Constraint;
+use ratatui::style::Color;
+use serde_json::{json, Value};
+
+use crate::process::{self, Process};
+use crate::units;
+
+/// Every process field that can be shown, shared by the TUI table and `--columns`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Pid,
+    Name,
+    Cpu,
+    /// Lifetime CPU time, htop's TIME+
+    Time,
+    Rss,
+    /// Memory and CPU change since the previous refresh
+    MemDelta,
+    CpuDelta,
+    Swap,
+    /// Average power, macOS only
+    Energy,
+    Status,
+    Parent,
+    Pgid,
+    Sid,
+    Fds,
+    /// Page faults and context switches per second
+    MinorFaults,
+    MajorFaults,
+    VoluntarySwitches,
+    InvoluntarySwitches,
+    /// Kubernetes namespace/pod
+    Pod,
+    /// Working directory and executable path, the latter marked when deleted on disk
+    Cwd,
+    Exe,
+    Origin,
+    User,
+    Cmdline,
+}
+
+impl Column {
+    pub const ALL: [Column; 24] = [
+        Column::Pid,
+        Column::Name,
+        Column::Cpu,
+        Column::Time,
+        Column::Rss,
+        Column::MemDelta,
+        Column::CpuDelta,
+        Column::Swap,
+        Column::Energy,
+        Column::Status,
+        Column::Parent,
+        Column::Pgid,
+        Column::Sid,
+        Column::Fds,
+        Column::MinorFaults,
+        Column::MajorFaults,
+        Column::VoluntarySwitches,
+        Column::InvoluntarySwitches,
+        Column::Pod,
+        Column::Cwd,
+        Column::Exe,
+        Column::Origin,
+        Column::User,
+        Column::Cmdline,
+    ];
+
+    /// Shown together with `E`, they need the per-process counters read
+    pub const EVENTS: [Column; 4] = [
+        Column::MinorFaults,
+        Column::MajorFaults,
+        Column::VoluntarySwitches,
+        Column::InvoluntarySwitches,
+    ];
+
+    /// Name used on the command line and as the JSON key
+    pub fn key(&self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::Name => "name",
+            Column::Cpu => "cpu",
+            Column::Time => "time",
+            Column::Rss => "rss",
+            Column::MemDelta => "mem_delta",
+            Column::CpuDelta => "cpu_delta",
+            Column::Swap => "swap",
+            Column::Energy => "energy",
+            Column::Status => "status",
+            Column::Parent => "ppid",
+            Column::Pgid => "pgid",
+            Column::Sid => "sid",
+            Column::Fds => "fds",
+            Column::MinorFaults => "minflt",
+            Column::MajorFaults => "majflt",
+            Column::VoluntarySwitches => "vcsw",
+            Column::InvoluntarySwitches => "ivcsw",
+            Column::Pod => "pod",
+            Column::Cwd => "cwd",
+            Column::Exe => "exe",
+            Column::Origin => "origin",
+            Column::User => "user",
+            Column::Cmdline => "cmdline",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Name => "NAME",
+            Column::Cpu => "CPU%",
+            Column::Time => "TIME+",
+            Column::Rss => "MEMORY",
+            Column::MemDelta => "ΔMEM",
+            Column::CpuDelta => "ΔCPU",
+            Column::Swap => "SWAP",
+            Column::Energy => "ENERGY",
+            Column::Status => "STATUS",
+            Column::Parent => "PARENT",
+            Column::Pgid => "PGID",
+            Column::Sid => "SID",
+            Column::Fds => "FDS",
+            Column::MinorFaults => "MINF/s",
+            Column::MajorFaults => "MAJF/s",
+            Column::VoluntarySwitches => "VCSW/s",
+            Column::InvoluntarySwitches => "ICSW/s",
+            Column::Pod => "POD",
+            Column::Cwd => "CWD",
+            Column::Exe => "EXE",
+            Column::Origin => "ORIGIN",
+            Column::User => "USER",
+            Column::Cmdline => "COMMAND",
+        }
+    }
+
+    pub fn width(&self) -> Constraint {
+        match self {
+            Column::Pid | Column::Cpu | Column::Parent | Column::Pgid | Column::Sid => {
+                Constraint::Length(6)
+            }
+            Column::Name => Constraint::Length(15),
+            Column::Rss | Column::Swap | Column::Energy | Column::Status => Constraint::Length(8),
+            Column::Time | Column::MemDelta => Constraint::Length(9),
+            Column::CpuDelta => Constraint::Length(7),
+            Column::Fds => Constraint::Length(5),
+            Column::MinorFaults
+            | Column::MajorFaults
+            | Column::VoluntarySwitches
+            | Column::InvoluntarySwitches => Constraint::Length(6),
+            Column::User => Constraint::Length(10),
+            Column::Pod | Column::Cwd | Column::Exe | Column::Origin | Column::Cmdline => {
+                Constraint::Min(10)
+            }
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Column::Pid => Color::Yellow,
+            Column::Name => Color::Green,
+            Column::Cpu => Color::Red,
+            Column::Time => Color::LightRed,
+            Column::Rss => Color::Blue,
+            Column::MemDelta | Column::CpuDelta => Color::Gray,
+            Column::Swap => Color::LightMagenta,
+            Column::Energy => Color::LightYellow,
+            Column::Status => Color::Cyan,
+            Column::Parent => Color::Magenta,
+            Column::Pgid | Column::Sid => Color::LightYellow,
+            Column::Fds => Color::LightBlue,
+            Column::MinorFaults => Color::Gray,
+            Column::MajorFaults => Color::LightRed,
+            Column::VoluntarySwitches => Color::Cyan,
+            Column::InvoluntarySwitches => Color::Yellow,
+            Column::Pod => Color::LightCyan,
+            Column::Cwd => Color::LightBlue,
+            Column::Exe => Color::White,
+            Column::Origin => Color::LightGreen,
+            Column::User => Color::Green,
+            Column::Cmdline => Color::Gray,
+        }
+    }
+
+    /// Like `color`, but delta cells are red when going up and green when going
+    /// down, and deleted executables are red
+    pub fn cell_color(&self, process: &Process) -> Color {
+        let change = match self {
+            Column::Exe if process.exe_deleted => return Color::Red,
+            Column::MemDelta => process.memory_delta_mb,
+            Column::CpuDelta => process.cpu_delta.map(f64::from),
+            _ => return self.color(),
+        };
+        match change {
+            Some(change) if change >= DELTA_EPSILON => Color::Red,
+            Some(change) if change <= -DELTA_EPSILON => Color::Green,
+            _ => self.color(),
+        }
+    }
+
+    /// Table cell text
+    pub fn text(&self, process: &Process) -> String {
+        let or_dash = |id: Option<sysinfo::Pid>| id.map_or("-".to_string(), |id| id.to_string());
+        match self {
+            Column::Pid => process.pid.to_string(),
+            Column::Name => {
+                if process.name.chars().count() > 15 {
+                    format!("{}...", process.name.chars().take(12).collect::<String>())
+                } else {
+                    process.name.clone()
+                }
+            }
+            Column::Cpu => format!("{:.1}%", process.cpu_usage),
+            Column::Time => format_cpu_time(process.cpu_time_ms),
+            Column::Rss => units::format_mb(process.memory_mb),
+            Column::MemDelta => format_delta(process.memory_delta_mb, "M"),
+            Column::CpuDelta => format_delta(process.cpu_delta.map(f64::from), "%"),
+            Column::Swap => process
+                .swap_bytes
+                .map_or("-".to_string(), units::format_size),
+            Column::Energy => process.power_mw.map_or("-".to_string(), format_power),
+            Column::Status => status_label(process).to_string(),
+            Column::Parent => process
+                .parent_pid
+                .map_or("None".to_string(), |pid| pid.to_string()),
+            Column::Pgid => or_dash(process.pgid),
+            Column::Sid => or_dash(process.sid),
+            Column::Fds => process
+                .fd_count
+                .map_or("-".to_string(), |count| count.to_string()),
+            Column::MinorFaults
+            | Column::MajorFaults
+            | Column::VoluntarySwitches
+            | Column::InvoluntarySwitches => self
+                .event_rate(process)
+                .map_or("-".to_string(), format_rate),
+            Column::Pod => process.pod.clone().unwrap_or_default(),
+            Column::Cwd => process.cwd.clone().unwrap_or_default(),
+            Column::Exe => exe_text(process),
+            Column::Origin => process
+                .sandbox
+                .as_ref()
+                .map_or(String::new(), |sandbox| sandbox.to_string()),
+            Column::User => process.user.clone().unwrap_or_default(),
+            Column::Cmdline => process.cmdline.clone(),
+        }
+    }
+
+    fn event_rate(&self, process: &Process) -> Option<f64> {
+        let rates = process.event_rates?;
+        match self {
+            Column::MinorFaults => Some(rates.minor_faults),
+            Column::MajorFaults => Some(rates.major_faults),
+            Column::VoluntarySwitches => Some(rates.voluntary_switches),
+            Column::InvoluntarySwitches => Some(rates.involuntary_switches),
+            _ => None,
+        }
+    }
+
+    /// Machine readable value, untruncated and in base units (bytes for rss)
+    pub fn json(&self, process: &Process) -> Value {
+        let id = |id: Option<sysinfo::Pid>| json!(id.map(|id| id.as_u32()));
+        match self {
+            Column::Pid => json!(process.pid.as_u32()),
+            Column::Name => json!(process.name),
+            Column::Cpu => json!(process.cpu_usage),
+            Column::Time => json!(process.cpu_time_ms),
+            Column::Rss => json!((process.memory_mb * 1024.0 * 1024.0) as u64),
+            Column::MemDelta => json!(process.memory_delta_mb),
+            Column::CpuDelta => json!(process.cpu_delta),
+            Column::Swap => json!(process.swap_bytes),
+            Column::Energy => json!(process.power_mw),
+            Column::Status => json!(process::status_name(process.status)),
+            Column::Parent => id(process.parent_pid),
+            Column::Pgid => id(process.pgid),
+            Column::Sid => id(process.sid),
+            Column::Fds => json!(process.fd_count),
+            Column::MinorFaults
+            | Column::MajorFaults
+            | Column::VoluntarySwitches
+            | Column::InvoluntarySwitches => json!(self.event_rate(process)),
+            Column::Pod => json!(process.pod),
+            Column::Cwd => json!(process.cwd),
+            Column::Exe => json!(process.exe.as_ref().map(|_| exe_text(process))),
+            Column::Origin => json!(process.sandbox.as_ref().map(|s| s.to_string())),
+            Column::User => json!(process.user),
+            Column::Cmdline => json!(process.cmdline),
+        }
+    }
+}
+
+/// The path with "(deleted)" after it when the file on disk is gone or replaced
+fn exe_text(process: &Process) -> String {
+    match &process.exe {
+        Some(exe) if process.exe_deleted => format!("{} (deleted)", exe),
+        Some(exe) => exe.clone(),
+        None => String::new(),
+    }
+}
+
+/// Changes smaller than this are shown as unchanged, they're rounding noise
+const DELTA_EPSILON: f64 = 0.05;
+
+/// "85mW", "1.2W"
+fn format_power(milliwatts: f64) -> String {
+    if milliwatts >= 1000.0 {
+        format!("{:.1}W", milliwatts / 1000.0)
+    } else {
+        format!("{:.0}mW", milliwatts)
+    }
+}
+
+/// "0", "850", "12.3k" events per second
+fn format_rate(per_second: f64) -> String {
+    if per_second >= 1000.0 {
+        format!("{:.1}k", per_second / 1000.0)
+    } else {
+        format!("{:.0}", per_second)
+    }
+}
+
+/// "▲1.2M", "▼0.4%", or "0" for no (or no known) change. The arrows carry
+/// the direction without relying on color.
+fn format_delta(change: Option<f64>, unit: &str) -> String {
+    match change {
+        Some(change) if change >= DELTA_EPSILON => format!("▲{:.1}{}", change, unit),
+        Some(change) if change <= -DELTA_EPSILON => format!("▼{:.1}{}", -change, unit),
+        Some(_) => "0".to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// htop's TIME+ format: "1:23.45" (minutes:seconds.hundredths), "2h05:09" past an hour
+pub fn format_cpu_time(ms: u64) -> String {
+    let hours = ms / 3_600_000;
+    let minutes = ms / 60_000 % 60;
+    let seconds = ms / 1000 % 60;
+    if hours > 0 {
+        format!("{}h{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}.{:02}", minutes, seconds, ms % 1000 / 10)
+    }
+}
+
+fn status_label(process: &Process) -> &'static str {
+    use sysinfo::ProcessStatus;
+    match process.status {
+        ProcessStatus::Run => "Running",
+        ProcessStatus::Sleep => "Sleeping",
+        ProcessStatus::Idle => "Idle",
+        ProcessStatus::Zombie => "Zombie",
+        ProcessStatus::Dead => "Dead",
+        ProcessStatus::Stop => "Stopped",
+        ProcessStatus::UninterruptibleDiskSleep => "DiskSlp",
+        _ => "Unknown",
+    }
+}
+
+/// "pid,name,cpu" -> columns, rejecting unknown names
+pub fn parse_list(list: &str) -> Result<Vec<Column>, String> {
+    list.split(',')
+        .map(|key| {
+            let key = key.trim();
+            Column::ALL
+                .into_iter()
+                .find(|column| column.key() == key)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = Column::ALL.iter().map(|c| c.key()).collect();
+                    format!("unknown column '{}' (known: {})", key, known.join(", "))
+                })
+        })
+        .collect()
+}
+
+/// One process as a JSON object with just the requested fields
+pub fn to_json(columns: &[Column], process: &Process) -> Value {
+    Value::Object(
+        columns
+            .iter()
+            .map(|column| (column.key().to_string(), column.json(process)))
+            .collect(),
+    )
+}
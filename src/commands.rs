@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sysinfo::Pid;
+
+use crate::cli;
+use crate::process::{Owned, SortKey};
+
+/// Commands and their arguments, shown as help in the command palette
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("kill", "PID"),
+    ("sort", "KEY[,THEN] (cpu|memory|growth|delta|time|swap)"),
+    ("filter", "[TEXT|re:REGEX]"),
+    ("fuzzy", "[TEXT]"),
+    ("preset", "[NAME]"),
+    ("theme", "auto|dark|light|contrast|mono"),
+    ("interval", "DURATION"),
+    ("who-owns", "PORT|PATH"),
+    ("quit", ""),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Dark or light, whichever matches the terminal background
+    #[default]
+    Auto,
+    Dark,
+    Light,
+    /// The accessible high contrast, reduced motion look
+    Contrast,
+    /// Like contrast, without any colors
+    Mono,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// SIGTERM a single process
+    Kill(Pid),
+    /// Primary key and an optional one for ties
+    Sort(SortKey, Option<SortKey>),
+    /// Only show processes whose name or command line contains the text, None clears it
+    Filter(Option<String>),
+    /// `:filter re:PATTERN`, a case-insensitive regex over name, command line and PID
+    Regex(String),
+    /// Like `Filter`, but the characters only have to appear in order, best matches first
+    Fuzzy(Option<String>),
+    /// Switch to a `[filters]` preset from the config file, None turns it off
+    Preset(Option<String>),
+    Theme(Theme),
+    /// How often the process list is refreshed
+    Interval(Duration),
+    /// Select the process that has a port bound or a file open
+    WhoOwns(Owned),
+    Quit,
+}
+
+/// Parse a command palette line such as "kill 1234" or "sort cpu"
+pub fn parse(line: &str) -> Result<Command, String> {
+    let (name, argument) = split(line);
+    match name {
+        "kill" => argument
+            .parse::<u32>()
+            .map(|pid| Command::Kill(Pid::from_u32(pid)))
+            .map_err(|_| format!("kill needs a PID, got '{}'", argument)),
+        "sort" => {
+            let parse_key = |name: &str| {
+                SortKey::parse(name.trim()).ok_or_else(|| {
+                    format!(
+                        "unknown sort key '{}' (known: {})",
+                        name.trim(),
+                        SortKey::names().join(", ")
+                    )
+                })
+            };
+            match argument.split_once(',') {
+                Some((key, then)) => Ok(Command::Sort(parse_key(key)?, Some(parse_key(then)?))),
+                None => Ok(Command::Sort(parse_key(argument)?, None)),
+            }
+        }
+        "filter" => match argument.strip_prefix("re:") {
+            Some("") => Ok(Command::Filter(None)),
+            Some(pattern) => compile_filter(pattern).map(|_| Command::Regex(pattern.to_string())),
+            None => Ok(Command::Filter(
+                (!argument.is_empty()).then(|| argument.to_string()),
+            )),
+        },
+        "fuzzy" => Ok(Command::Fuzzy(
+            (!argument.is_empty()).then(|| argument.to_string()),
+        )),
+        "preset" => Ok(Command::Preset(match argument {
+            "" | "off" | "none" => None,
+            name => Some(name.to_string()),
+        })),
+        "theme" => match argument {
+            "auto" | "default" => Ok(Command::Theme(Theme::Auto)),
+            "dark" => Ok(Command::Theme(Theme::Dark)),
+            "light" => Ok(Command::Theme(Theme::Light)),
+            "contrast" | "accessible" => Ok(Command::Theme(Theme::Contrast)),
+            "mono" | "monochrome" => Ok(Command::Theme(Theme::Mono)),
+            other => Err(format!("unknown theme '{}'", other)),
+        },
+        "interval" => cli::parse_interval(argument)
+            .map(Command::Interval)
+            .map_err(|e| e.to_string()),
+        "who-owns" => match argument.trim_start_matches(':') {
+            "" => Err("who-owns needs a port or a path".to_string()),
+            port if port.bytes().all(|b| b.is_ascii_digit()) => port
+                .parse()
+                .map(|port| Command::WhoOwns(Owned::Port(port)))
+                .map_err(|_| format!("invalid port '{}'", port)),
+            _ => Ok(Command::WhoOwns(Owned::Path(argument.into()))),
+        },
+        "q" | "quit" => Ok(Command::Quit),
+        "" => Err("empty command".to_string()),
+        other => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+fn split(line: &str) -> (&str, &str) {
+    let line = line.trim().trim_start_matches(':');
+    line.split_once(char::is_whitespace)
+        .map_or((line, ""), |(name, rest)| (name, rest.trim()))
+}
+
+/// The regex behind `:filter re:`, errors cut down to the one line that fits the palette
+pub fn compile_filter(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| {
+            let message = e.to_string();
+            let reason = message
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or(&message)
+                .trim()
+                .trim_start_matches("error: ");
+            format!("invalid regex: {}", reason)
+        })
+}
+
+/// Problems worth showing while the line is still being typed, only broken regexes
+/// since everything else is just incomplete
+pub fn live_error(line: &str) -> Option<String> {
+    match split(line) {
+        ("filter", argument) => compile_filter(argument.strip_prefix("re:")?).err(),
+        _ => None,
+    }
+}
+
+/// Commands starting with what has been typed so far, for the palette's hint line
+pub fn completions(line: &str) -> Vec<&'static (&'static str, &'static str)> {
+    let typed = line.trim_start();
+    COMMANDS
+        .iter()
+        .filter(|(name, _)| {
+            // Once an argument is being typed only the command itself is relevant
+            match typed.split_once(char::is_whitespace) {
+                Some((command, _)) => *name == command,
+                None => name.starts_with(typed),
+            }
+        })
+        .collect()
+}
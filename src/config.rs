@@ -0,0 +1,109 @@
+//! Optional settings file, `$XDG_CONFIG_HOME/m-as/config.toml`:
+//!
+//! ```toml
+//! [filters]
+//! web = "nginx|php-fpm"
+//! dev = "cargo|rustc|node"
+//!
+//! [intervals]
+//! disks = "10s"
+//! services = "30s"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::cli;
+use crate::scheduler;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Named regexes over process name and command line, cycled with `p`
+    pub filters: BTreeMap<String, String>,
+    /// Sampler periods by sampler name, "500ms" or "10s"
+    pub intervals: BTreeMap<String, String>,
+}
+
+/// A compiled `[filters]` entry
+#[derive(Debug, Clone)]
+pub struct FilterPreset {
+    pub name: String,
+    pub pattern: Regex,
+}
+
+impl FilterPreset {
+    pub fn matches(&self, name: &str, cmdline: &str) -> bool {
+        self.pattern.is_match(name) || self.pattern.is_match(cmdline)
+    }
+}
+
+impl Config {
+    /// Presets in name order, an invalid regex fails the whole file so it isn't missed
+    pub fn filter_presets(&self) -> Result<Vec<FilterPreset>, String> {
+        self.filters
+            .iter()
+            .map(|(name, pattern)| {
+                Regex::new(pattern)
+                    .map(|pattern| FilterPreset {
+                        name: name.clone(),
+                        pattern,
+                    })
+                    .map_err(|e| format!("filter preset '{}': {}", name, e))
+            })
+            .collect()
+    }
+
+    /// Parsed `[intervals]`, names are checked against the scheduler's samplers
+    pub fn sampler_intervals(&self) -> Result<Vec<(String, Duration)>, String> {
+        let known: Vec<&str> = scheduler::samplers()
+            .into_iter()
+            .map(|(name, ..)| name)
+            .collect();
+        self.intervals
+            .iter()
+            .map(|(name, value)| {
+                if scheduler::RUNTIME_INTERVALS.contains(&name.as_str()) {
+                    return Err(format!(
+                        "interval '{}' is set in the TUI (:interval, chart options)",
+                        name
+                    ));
+                }
+                if !known.contains(&name.as_str()) {
+                    return Err(format!(
+                        "unknown sampler '{}', one of: {}",
+                        name,
+                        known.join(", ")
+                    ));
+                }
+                cli::parse_interval(value)
+                    .map(|interval| (name.clone(), interval))
+                    .map_err(|e| format!("interval '{}': {}", name, e))
+            })
+            .collect()
+    }
+}
+
+/// $XDG_CONFIG_HOME/m-as/config.toml, falling back to ~/.config
+pub fn default_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("m-as").join("config.toml"))
+}
+
+/// Defaults when there is no file, unlike the UI state a broken one is an error
+pub fn load(path: &Path) -> Result<Config, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
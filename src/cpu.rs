@@ -1,9 +1,25 @@
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use sysinfo::System;
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+use crate::cpufreq::{self, Policy};
+use crate::history::{self, ChartConfig};
+use crate::platform::{self, CoreKind, CpuTopology};
+use crate::sampler::Sampler;
+use crate::snapshot::CpuSnapshot;
+
+// user nice system idle iowait irq softirq steal, the fields before guest time
+const PROC_STAT_FIELDS: usize = 8;
+
+/// Usage needs two readings at least this far apart, closer ones come out as noise
+pub const MIN_SAMPLE_INTERVAL: Duration = sysinfo::MINIMUM_CPU_UPDATE_INTERVAL;
 
 #[allow(dead_code)]
 pub fn main() {
-    let mut cpu_info = CpuInfo::new();
+    let mut cpu_info = CpuInfo::new_sampled();
 
     loop {
         cpu_info.update();
@@ -18,72 +34,233 @@ pub fn main() {
 #[derive(Debug, Clone)]
 pub struct CpuCore {
     pub name: String,
+    /// Performance or efficiency core, None on CPUs with only one kind
+    pub kind: Option<CoreKind>,
     pub usage: f32,
     pub history: VecDeque<f32>, // For graphing historical usage
 }
 
 impl CpuCore {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, capacity: usize) -> Self {
         Self {
             name,
+            kind: None,
             usage: 0.0,
-            history: VecDeque::with_capacity(60), // Store 60 data points (1 minute at 1 update/sec)
+            history: VecDeque::with_capacity(capacity),
         }
     }
 }
 
+/// Share of CPU time spent in each state since the previous update.
+/// sysinfo only reports busy vs idle, this comes straight from /proc/stat.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuBreakdown {
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub idle: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    /// Time the hypervisor ran something else while this VM wanted the CPU
+    pub steal: f32,
+}
+
+impl CpuBreakdown {
+    fn from_delta(delta: [u64; PROC_STAT_FIELDS]) -> Self {
+        let total = delta.iter().sum::<u64>().max(1) as f32;
+        let percent = |i: usize| delta[i] as f32 / total * 100.0;
+        Self {
+            user: percent(0),
+            nice: percent(1),
+            system: percent(2),
+            idle: percent(3),
+            iowait: percent(4),
+            irq: percent(5),
+            softirq: percent(6),
+            steal: percent(7),
+        }
+    }
+
+    /// Busy states as (short label, percent), in the order they're stacked
+    pub fn busy_parts(&self) -> [(&'static str, f32); 6] {
+        [
+            ("us", self.user),
+            ("ni", self.nice),
+            ("sy", self.system),
+            ("irq", self.irq + self.softirq),
+            ("io", self.iowait),
+            ("st", self.steal),
+        ]
+    }
+}
+
+/// What the CPU is, read once at startup for the hardware popup
+#[derive(Debug, Clone, Default)]
+pub struct HardwareInfo {
+    pub model: String,
+    pub vendor: String,
+    /// Current frequency of the first core when read
+    pub frequency_mhz: u64,
+    /// None where sysinfo can't tell cores from hardware threads
+    pub physical_cores: Option<usize>,
+    pub threads: usize,
+    /// Sockets, caches and microcode, Linux only
+    pub topology: Option<CpuTopology>,
+}
+
 #[derive(Debug)]
 pub struct CpuInfo {
     pub global_usage: f32,
     pub cores: Vec<CpuCore>,
     pub history: VecDeque<f32>,
+    pub chart_config: ChartConfig,
+    /// cpufreq policies with their governors, empty without cpufreq (VMs, containers, other platforms)
+    pub policies: Vec<Policy>,
+    /// None until two /proc/stat readings exist, and on other platforms
+    pub breakdown: Option<CpuBreakdown>,
+    last_stat: Option<[u64; PROC_STAT_FIELDS]>,
+    last_refresh: Instant,
     system: System,
 }
 
 impl CpuInfo {
-    /// Create a new CpuInfo struct with default value
+    /// Take the first reading without waiting, usage is known from the next `update`
     pub fn new() -> Self {
-        let system = System::new_all();
-        // Wait a bit to get accurate initial readings
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        let system = System::new_with_specifics(
+            RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
+        );
+        let last_stat = read_proc_stat();
 
+        let chart_config = ChartConfig::default();
+        let kinds = platform::core_kinds(system.cpus().len()).unwrap_or_default();
         let cores = system
             .cpus()
             .iter()
             .enumerate()
-            .map(|(i, _)| CpuCore::new(format!("Core {}", i + 1)))
+            .map(|(i, _)| CpuCore {
+                kind: kinds.get(i).copied(),
+                ..CpuCore::new(format!("Core {}", i + 1), chart_config.capacity())
+            })
             .collect();
 
         Self {
             global_usage: 0.0,
             cores,
-            history: VecDeque::with_capacity(60),
+            history: VecDeque::with_capacity(chart_config.capacity()),
+            chart_config,
+            policies: cpufreq::read_policies(),
+            breakdown: None,
+            last_stat,
+            last_refresh: Instant::now(),
             system,
         }
     }
 
-    /// Update the CPU information
+    pub fn hardware(&self) -> HardwareInfo {
+        let cpus = self.system.cpus();
+        let first = cpus.first();
+        HardwareInfo {
+            model: first.map_or_else(String::new, |cpu| cpu.brand().trim().to_string()),
+            vendor: first.map_or_else(String::new, |cpu| cpu.vendor_id().to_string()),
+            frequency_mhz: first.map_or(0, |cpu| cpu.frequency()),
+            physical_cores: System::physical_core_count(),
+            threads: cpus.len(),
+            topology: platform::cpu_topology(),
+        }
+    }
+
+    /// Like `new`, but waits `MIN_SAMPLE_INTERVAL` so the first `update` already
+    /// has a usage. For the one-shot and streaming modes, not under a lock.
+    pub fn new_sampled() -> Self {
+        let cpu_info = Self::new();
+        std::thread::sleep(MIN_SAMPLE_INTERVAL);
+        cpu_info
+    }
+
+    /// Update the CPU information. A call sooner than `MIN_SAMPLE_INTERVAL`
+    /// after the last one is skipped, the reading would only be noise.
     pub fn update(&mut self) {
-        // Refresh CPU information
+        if self.last_refresh.elapsed() < MIN_SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_refresh = Instant::now();
         self.system.refresh_cpu_all();
 
         // Update global usage
+        let capacity = self.chart_config.capacity();
         self.global_usage = self.system.global_cpu_usage();
-        self.history.push_back(self.global_usage);
-        if self.history.len() > 60 {
-            self.history.pop_front();
-        }
+        history::push_capped(&mut self.history, self.global_usage, capacity);
 
         // Update each core's usage
         for (i, cpu) in self.system.cpus().iter().enumerate() {
             if let Some(core) = self.cores.get_mut(i) {
                 core.usage = cpu.cpu_usage();
-                core.history.push_back(core.usage);
-                if core.history.len() > 60 {
-                    core.history.pop_front();
-                }
+                history::push_capped(&mut core.history, core.usage, capacity);
+            }
+        }
+
+        let stat = read_proc_stat();
+        if let (Some(stat), Some(last)) = (stat, self.last_stat) {
+            let mut delta = [0; PROC_STAT_FIELDS];
+            for (i, value) in delta.iter_mut().enumerate() {
+                *value = stat[i].saturating_sub(last[i]);
             }
+            self.breakdown = Some(CpuBreakdown::from_delta(delta));
         }
+        self.last_stat = stat;
+
+        self.policies = cpufreq::read_policies();
+    }
+
+    /// Take readings collected elsewhere (e.g. a mirrored instance) instead of sampling
+    pub fn apply_snapshot(&mut self, snapshot: &CpuSnapshot) {
+        let capacity = self.chart_config.capacity();
+        self.global_usage = snapshot.global_usage;
+        self.breakdown = snapshot.breakdown;
+        history::push_capped(&mut self.history, self.global_usage, capacity);
+
+        if self.cores.len() != snapshot.cores.len() {
+            self.cores = (0..snapshot.cores.len())
+                .map(|i| CpuCore::new(format!("Core {}", i + 1), capacity))
+                .collect();
+        }
+        for (core, &usage) in self.cores.iter_mut().zip(&snapshot.cores) {
+            core.usage = usage;
+            history::push_capped(&mut core.history, usage, capacity);
+        }
+    }
+
+    /// Switch sample rate/window, resampling the existing history so the chart doesn't reset
+    pub fn set_chart_config(&mut self, config: ChartConfig) {
+        let old = self.chart_config;
+        let capacity = config.capacity();
+        self.history = history::resample(
+            &self.history,
+            old.sample_interval,
+            config.sample_interval,
+            capacity,
+        );
+        for core in &mut self.cores {
+            core.history = history::resample(
+                &core.history,
+                old.sample_interval,
+                config.sample_interval,
+                capacity,
+            );
+        }
+        self.chart_config = config;
+    }
+
+    /// Switch the policy at `policy`, or every policy when None, to `governor`. Needs root.
+    pub fn set_governor(&mut self, policy: Option<usize>, governor: &str) -> io::Result<()> {
+        let result = match policy {
+            Some(index) => cpufreq::set_governor(self.policies.get(index), governor),
+            None => cpufreq::set_governor(&self.policies, governor),
+        };
+        // Some policies may have switched even on failure
+        self.policies = cpufreq::read_policies();
+        result
     }
 
     pub fn _core_graph_data(&self, _core_index: usize) -> Option<Vec<(f64, f64)>> {
@@ -96,3 +273,46 @@ impl CpuInfo {
         })
     }
 }
+
+impl Default for CpuInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for CpuInfo {
+    type Snapshot = CpuSnapshot;
+
+    fn new() -> Self {
+        CpuInfo::new()
+    }
+
+    fn update(&mut self) {
+        CpuInfo::update(self);
+    }
+
+    fn snapshot(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            global_usage: self.global_usage,
+            cores: self.cores.iter().map(|core| core.usage).collect(),
+            breakdown: self.breakdown,
+        }
+    }
+}
+
+/// Cumulative jiffies per state from the aggregate "cpu" line of /proc/stat
+fn read_proc_stat() -> Option<[u64; PROC_STAT_FIELDS]> {
+    let contents = fs::read_to_string("/proc/stat").ok()?;
+    let mut fields = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("cpu "))?
+        .split_whitespace()
+        .map(|field| field.parse::<u64>().ok());
+
+    let mut times = [0; PROC_STAT_FIELDS];
+    for time in &mut times {
+        // Old kernels stop before steal, those states are just 0
+        *time = fields.next().flatten().unwrap_or(0);
+    }
+    Some(times)
+}
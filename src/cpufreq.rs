@@ -0,0 +1,179 @@
+//! CPU frequency scaling through the Linux cpufreq sysfs interface.
+//!
+//! The kernel groups CPUs that share a clock into policies
+//! (`/sys/devices/system/cpu/cpufreq/policyN`), the governor is set per policy.
+//! On hybrid CPUs and multi-socket machines those can differ.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const CPUFREQ_ROOT: &str = "/sys/devices/system/cpu/cpufreq";
+
+/// One cpufreq policy and the CPUs it drives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    /// Directory name, e.g. "policy0"
+    pub name: String,
+    /// Zero based CPU ids, the ones currently online
+    pub cpus: Vec<usize>,
+    pub governor: String,
+    /// e.g. ["performance", "powersave", "schedutil"]
+    pub available_governors: Vec<String>,
+    /// Current frequency, None when the driver doesn't report it
+    pub cur_khz: Option<u64>,
+}
+
+impl Policy {
+    fn read(path: &Path) -> Option<Self> {
+        let read = |file: &str| {
+            fs::read_to_string(path.join(file))
+                .ok()
+                .map(|value| value.trim().to_string())
+        };
+        Some(Self {
+            name: path.file_name()?.to_str()?.to_string(),
+            cpus: read("affected_cpus")
+                .map(|cpus| {
+                    cpus.split_whitespace()
+                        .filter_map(|id| id.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            governor: read("scaling_governor")?,
+            available_governors: read("scaling_available_governors")
+                .map(|list| list.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            cur_khz: read("scaling_cur_freq").and_then(|khz| khz.parse().ok()),
+        })
+    }
+
+    fn governor_path(&self) -> PathBuf {
+        Path::new(CPUFREQ_ROOT)
+            .join(&self.name)
+            .join("scaling_governor")
+    }
+
+    /// "CPU 1-4" style label, one based like the core list
+    pub fn cpu_label(&self) -> String {
+        match (self.cpus.first(), self.cpus.last()) {
+            (Some(first), Some(last)) if first == last => format!("CPU {}", first + 1),
+            (Some(first), Some(last)) if last - first + 1 == self.cpus.len() => {
+                format!("CPU {}-{}", first + 1, last + 1)
+            }
+            _ => format!("{} CPUs", self.cpus.len()),
+        }
+    }
+}
+
+/// All policies sorted by number, empty without cpufreq (VMs, containers, other platforms)
+pub fn read_policies() -> Vec<Policy> {
+    let Ok(entries) = fs::read_dir(CPUFREQ_ROOT) else {
+        return Vec::new();
+    };
+    let mut policies: Vec<(usize, Policy)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let number = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("policy")?
+                .parse()
+                .ok()?;
+            Some((number, Policy::read(&entry.path())?))
+        })
+        .collect();
+    policies.sort_by_key(|(number, _)| *number);
+    policies.into_iter().map(|(_, policy)| policy).collect()
+}
+
+/// The governor of every policy, or None when they differ or there are none
+pub fn common_governor(policies: &[Policy]) -> Option<&str> {
+    let first = policies.first()?;
+    policies
+        .iter()
+        .all(|policy| policy.governor == first.governor)
+        .then_some(first.governor.as_str())
+}
+
+/// Governors every policy offers, in the order of the first one
+pub fn available_governors(policies: &[Policy]) -> Vec<String> {
+    let Some(first) = policies.first() else {
+        return Vec::new();
+    };
+    first
+        .available_governors
+        .iter()
+        .filter(|governor| {
+            policies
+                .iter()
+                .all(|policy| policy.available_governors.contains(governor))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Governor of the policy driving `cpu`
+pub fn governor_of(policies: &[Policy], cpu: usize) -> Option<&str> {
+    policies
+        .iter()
+        .find(|policy| policy.cpus.contains(&cpu))
+        .map(|policy| policy.governor.as_str())
+}
+
+/// Abbreviation for the core list, unknown governors are cut to 5 characters
+pub fn short_governor(governor: &str) -> &str {
+    match governor {
+        "performance" => "perf",
+        "powersave" => "save",
+        "schedutil" => "sched",
+        "ondemand" => "ondem",
+        "conservative" => "cons",
+        "userspace" => "user",
+        other => other.get(..5).unwrap_or(other),
+    }
+}
+
+/// Whether the governor files can be written, false without cpufreq
+pub fn can_switch(policies: &[Policy]) -> bool {
+    policies
+        .first()
+        .is_some_and(|policy| writable(&policy.governor_path()))
+}
+
+#[cfg(unix)]
+fn writable(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: path is a valid NUL terminated string for the duration of the call
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn writable(_path: &Path) -> bool {
+    false
+}
+
+/// Switch the given policies to `governor`, needs root. Stops at the first
+/// failure, policies before it keep the new governor.
+pub fn set_governor<'a>(
+    policies: impl IntoIterator<Item = &'a Policy>,
+    governor: &str,
+) -> io::Result<()> {
+    let mut written = 0;
+    for policy in policies {
+        fs::write(policy.governor_path(), governor)?;
+        written += 1;
+    }
+    if written == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "CPU frequency scaling is not available",
+        ));
+    }
+    Ok(())
+}
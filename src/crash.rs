@@ -0,0 +1,124 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::job;
+use crate::logging;
+use crate::snapshot::Snapshot;
+
+/// Snapshots kept for the crash log, taken `SNAPSHOT_INTERVAL` apart
+const KEPT_SNAPSHOTS: usize = 5;
+/// Converting every process for each 250ms refresh would cost more than it's worth
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Recent {
+    last: Option<Instant>,
+    snapshots: VecDeque<Snapshot>,
+}
+
+// Outside of AppState, whose lock the panicking thread may hold
+static RECENT: Mutex<Recent> = Mutex::new(Recent {
+    last: None,
+    snapshots: VecDeque::new(),
+});
+
+/// Keep a snapshot for the crash log, `capture` only runs when one is due
+pub fn record(capture: impl FnOnce() -> Snapshot) {
+    let Ok(mut recent) = RECENT.try_lock() else {
+        return;
+    };
+    if recent
+        .last
+        .is_some_and(|last| last.elapsed() < SNAPSHOT_INTERVAL)
+    {
+        return;
+    }
+    recent.last = Some(Instant::now());
+    recent.snapshots.push_back(capture());
+    if recent.snapshots.len() > KEPT_SNAPSHOTS {
+        recent.snapshots.pop_front();
+    }
+}
+
+/// On a panic in any thread: restore the terminal, write a crash log, let the
+/// previous hook (color-eyre's report) print, stop a `møas run` command and
+/// exit. A panicked sampler would otherwise leave the UI running on a terminal
+/// that is no longer raw.
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        ratatui::restore();
+        let path = write_log(&panic_message(info));
+        previous(info);
+        if let Some(path) = path {
+            eprintln!("Crash log written to {}", path.display());
+        }
+        // Exiting skips the teardown in job::run
+        job::stop_running();
+        std::process::exit(101);
+    }));
+}
+
+/// Crash log for an error the UI loop returned, the report itself is printed by main
+pub fn report_error(error: &color_eyre::Report) {
+    if let Some(path) = write_log(&format!("Error: {:?}", error)) {
+        eprintln!("Crash log written to {}", path.display());
+    }
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let location = info
+        .location()
+        .map(|l| format!(" at {}:{}", l.file(), l.line()))
+        .unwrap_or_default();
+    let thread = std::thread::current();
+    format!(
+        "Panic in thread '{}'{}: {}\n\n{}",
+        thread.name().unwrap_or("unnamed"),
+        location,
+        payload,
+        Backtrace::force_capture()
+    )
+}
+
+/// `crash-<unix time>.log` next to the regular log, None when it can't be written
+fn write_log(message: &str) -> Option<PathBuf> {
+    let dir = logging::default_path()?.parent()?.to_path_buf();
+    fs::create_dir_all(&dir).ok()?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = dir.join(format!("crash-{}.log", timestamp));
+
+    let mut contents = format!(
+        "m-as {} crashed\n\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        message
+    );
+    // try_lock: the panic may have happened while recording
+    if let Ok(recent) = RECENT.try_lock() {
+        let _ = writeln!(
+            contents,
+            "\nLast {} snapshots, oldest first:",
+            recent.snapshots.len()
+        );
+        for snapshot in &recent.snapshots {
+            if let Ok(json) = serde_json::to_string(snapshot) {
+                let _ = writeln!(contents, "{}", json);
+            }
+        }
+    }
+    fs::write(&path, contents).ok()?;
+    Some(path)
+}
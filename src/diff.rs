@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use sysinfo::Pid;
+
+use crate::process::{self, Process};
+
+#[allow(dead_code)]
+pub fn main() {
+    let baseline = Baseline::mark(&process::get_all_processes());
+    std::thread::sleep(Duration::from_secs(5));
+
+    for delta in baseline.diff(&process::get_all_processes()).iter().take(20) {
+        println!(
+            "{:>8} {:<20} {:<8} {:>+10.1}MB {:>8}ms",
+            delta.pid,
+            delta.name,
+            delta.change.label(),
+            delta.memory_delta_mb,
+            delta.cpu_time_ms
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    New,
+    Exited,
+    /// Alive both at the mark and now
+    Running,
+}
+
+impl Change {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Change::New => "new",
+            Change::Exited => "exited",
+            Change::Running => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessDelta {
+    pub pid: Pid,
+    pub name: String,
+    pub change: Change,
+    pub memory_delta_mb: f64,
+    /// CPU time consumed since the mark, the whole lifetime for new processes
+    pub cpu_time_ms: u64,
+}
+
+/// Process table as it was when the user marked a baseline
+#[derive(Debug)]
+pub struct Baseline {
+    pub marked_at: Instant,
+    processes: HashMap<Pid, Process>,
+}
+
+impl Baseline {
+    pub fn mark(processes: &[Process]) -> Self {
+        Self {
+            marked_at: Instant::now(),
+            processes: processes.iter().map(|p| (p.pid, p.clone())).collect(),
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        self.marked_at.elapsed()
+    }
+
+    /// Changes between the mark and `current`, biggest memory growth first
+    pub fn diff(&self, current: &[Process]) -> Vec<ProcessDelta> {
+        let mut deltas = Vec::new();
+        let mut seen = HashMap::with_capacity(current.len());
+
+        for process in current {
+            // A reused PID with a different name is a different process
+            let before = self
+                .processes
+                .get(&process.pid)
+                .filter(|before| before.name == process.name);
+            seen.insert(process.pid, before.is_some());
+            deltas.push(match before {
+                Some(before) => ProcessDelta {
+                    pid: process.pid,
+                    name: process.name.clone(),
+                    change: Change::Running,
+                    memory_delta_mb: process.memory_mb - before.memory_mb,
+                    cpu_time_ms: process.cpu_time_ms.saturating_sub(before.cpu_time_ms),
+                },
+                None => ProcessDelta {
+                    pid: process.pid,
+                    name: process.name.clone(),
+                    change: Change::New,
+                    memory_delta_mb: process.memory_mb,
+                    cpu_time_ms: process.cpu_time_ms,
+                },
+            });
+        }
+
+        for before in self.processes.values() {
+            if seen.get(&before.pid) != Some(&true) {
+                deltas.push(ProcessDelta {
+                    pid: before.pid,
+                    name: before.name.clone(),
+                    change: Change::Exited,
+                    memory_delta_mb: -before.memory_mb,
+                    cpu_time_ms: 0,
+                });
+            }
+        }
+
+        deltas.sort_by(|a, b| b.memory_delta_mb.total_cmp(&a.memory_delta_mb));
+        deltas
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::time::Instant;
+
+use sysinfo::Disks;
+
+use crate::units;
+
+const HISTORY_CAPACITY: usize = 60;
+// /proc/diskstats always counts in 512 byte sectors regardless of the device
+const SECTOR_SIZE: u64 = 512;
+/// Inode usage above this is flagged even when there's plenty of free space
+pub const INODE_WARNING_PERCENT: f32 = 90.0;
+/// Noise mounts hidden unless everything is shown: snap packages, memory
+/// backed filesystems and loop devices
+pub const DEFAULT_EXCLUDES: &[&str] = &["/snap/*", "tmpfs", "devtmpfs", "squashfs", "loop*"];
+
+#[allow(dead_code)]
+pub fn main() {
+    let mut disk_info = DiskInfo::new(MountFilter::default());
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    disk_info.update();
+
+    for disk in disk_info.visible() {
+        println!(
+            "{:<20} {:<10} {:>5.1}% inodes {} read {} write {}",
+            disk.mount_point,
+            disk.device,
+            disk.usage_percent(),
+            disk.inode_usage_percent()
+                .map_or("N/A".to_string(), |p| format!("{:.1}%", p)),
+            units::format_rate(disk.read_rate),
+            units::format_rate(disk.write_rate)
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Disk {
+    pub device: String,
+    pub mount_point: String,
+    pub file_system: String,
+    pub total_space: u64,
+    pub available_space: u64,
+    /// None for filesystems without a fixed inode table (btrfs, some network mounts)
+    pub total_inodes: Option<u64>,
+    pub free_inodes: u64,
+    /// Bytes per second since the previous update
+    pub read_rate: u64,
+    pub write_rate: u64,
+    pub read_history: VecDeque<u64>,
+    pub write_history: VecDeque<u64>,
+}
+
+impl Disk {
+    pub fn usage_percent(&self) -> f32 {
+        if self.total_space == 0 {
+            return 0.0;
+        }
+        let used = self.total_space.saturating_sub(self.available_space);
+        (used as f32 / self.total_space as f32) * 100.0
+    }
+
+    pub fn inode_usage_percent(&self) -> Option<f32> {
+        let total = self.total_inodes?;
+        let used = total.saturating_sub(self.free_inodes);
+        Some((used as f32 / total as f32) * 100.0)
+    }
+
+    pub fn inodes_near_exhaustion(&self) -> bool {
+        self.inode_usage_percent()
+            .is_some_and(|percent| percent >= INODE_WARNING_PERCENT)
+    }
+}
+
+/// Which mounts the disk panel shows. Patterns are globs matched against the
+/// mount point, the filesystem type and the device name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountFilter {
+    /// When set, only mounts matching one of these are shown, even if excluded
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Ignore both lists
+    pub show_all: bool,
+}
+
+impl Default for MountFilter {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: DEFAULT_EXCLUDES.iter().map(|p| p.to_string()).collect(),
+            show_all: false,
+        }
+    }
+}
+
+impl MountFilter {
+    pub fn matches(&self, disk: &Disk) -> bool {
+        if self.show_all {
+            return true;
+        }
+        let matches_any = |patterns: &[String]| {
+            patterns.iter().any(|pattern| {
+                glob_match(pattern, &disk.mount_point)
+                    || glob_match(pattern, &disk.file_system)
+                    || glob_match(pattern, &disk.device)
+            })
+        };
+        if !self.include.is_empty() {
+            return matches_any(&self.include);
+        }
+        !matches_any(&self.exclude)
+    }
+}
+
+/// Shell style glob where `*` matches any run of characters (including '/')
+/// and `?` a single one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last '*' was and the text position it's currently matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[derive(Debug)]
+pub struct DiskInfo {
+    pub disks: Vec<Disk>,
+    pub filter: MountFilter,
+    system_disks: Disks,
+    // Cumulative (read, written) bytes per device from the previous update
+    last_counters: HashMap<String, (u64, u64)>,
+    last_update: Instant,
+}
+
+impl DiskInfo {
+    pub fn new(filter: MountFilter) -> Self {
+        let system_disks = Disks::new_with_refreshed_list();
+        let mut disk_info = Self {
+            disks: Vec::new(),
+            filter,
+            system_disks,
+            last_counters: read_io_counters(),
+            last_update: Instant::now(),
+        };
+        disk_info.refresh_capacity();
+        disk_info
+    }
+
+    /// The disks the mount filter lets through
+    pub fn visible(&self) -> impl Iterator<Item = &Disk> {
+        self.disks.iter().filter(|disk| self.filter.matches(disk))
+    }
+
+    /// Number of mounts the filter hides
+    pub fn hidden_count(&self) -> usize {
+        self.disks.len() - self.visible().count()
+    }
+
+    pub fn update(&mut self) {
+        self.system_disks.refresh(true);
+        self.refresh_capacity();
+
+        let now = Instant::now();
+        let elapsed = now
+            .duration_since(self.last_update)
+            .as_secs_f64()
+            .max(0.001);
+        let counters = read_io_counters();
+
+        for (disk, system_disk) in self.disks.iter_mut().zip(self.system_disks.list()) {
+            // Prefer /proc/diskstats, fall back to what sysinfo measured since the last refresh
+            let (read_delta, write_delta) = match (
+                counters.get(&disk.device),
+                self.last_counters.get(&disk.device),
+            ) {
+                (Some(&(read, written)), Some(&(last_read, last_written))) => (
+                    read.saturating_sub(last_read),
+                    written.saturating_sub(last_written),
+                ),
+                _ => {
+                    let usage = system_disk.usage();
+                    (usage.read_bytes, usage.written_bytes)
+                }
+            };
+
+            disk.read_rate = (read_delta as f64 / elapsed) as u64;
+            disk.write_rate = (write_delta as f64 / elapsed) as u64;
+            push_rate(&mut disk.read_history, disk.read_rate);
+            push_rate(&mut disk.write_history, disk.write_rate);
+        }
+
+        self.last_counters = counters;
+        self.last_update = now;
+    }
+
+    /// Sync the disk list with sysinfo while keeping the history of known mounts
+    fn refresh_capacity(&mut self) {
+        let mut previous: HashMap<String, Disk> = self
+            .disks
+            .drain(..)
+            .map(|disk| (disk.mount_point.clone(), disk))
+            .collect();
+
+        self.disks = self
+            .system_disks
+            .list()
+            .iter()
+            .map(|system_disk| {
+                let mount_point = system_disk.mount_point().to_string_lossy().into_owned();
+                let mut disk = previous.remove(&mount_point).unwrap_or_else(|| Disk {
+                    device: device_name(&system_disk.name().to_string_lossy()),
+                    mount_point,
+                    file_system: String::new(),
+                    total_space: 0,
+                    available_space: 0,
+                    total_inodes: None,
+                    free_inodes: 0,
+                    read_rate: 0,
+                    write_rate: 0,
+                    read_history: VecDeque::with_capacity(HISTORY_CAPACITY),
+                    write_history: VecDeque::with_capacity(HISTORY_CAPACITY),
+                });
+                disk.file_system = system_disk.file_system().to_string_lossy().into_owned();
+                disk.total_space = system_disk.total_space();
+                disk.available_space = system_disk.available_space();
+                match inode_counts(&disk.mount_point) {
+                    Some((total, free)) => {
+                        disk.total_inodes = Some(total);
+                        disk.free_inodes = free;
+                    }
+                    None => disk.total_inodes = None,
+                }
+                disk
+            })
+            .collect();
+    }
+}
+
+fn push_rate(history: &mut VecDeque<u64>, rate: u64) {
+    history.push_back(rate);
+    if history.len() > HISTORY_CAPACITY {
+        history.pop_front();
+    }
+}
+
+/// (total, free) inodes of the filesystem mounted at `mount_point`
+#[cfg(unix)]
+fn inode_counts(mount_point: &str) -> Option<(u64, u64)> {
+    let path = std::ffi::CString::new(mount_point).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is a valid C string and stat is a properly sized out parameter
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 || stat.f_files == 0 {
+        return None;
+    }
+    Some((stat.f_files as u64, stat.f_ffree as u64))
+}
+
+#[cfg(not(unix))]
+fn inode_counts(_mount_point: &str) -> Option<(u64, u64)> {
+    None
+}
+
+/// "/dev/nvme0n1p2" -> "nvme0n1p2", the name used in /proc/diskstats
+fn device_name(name: &str) -> String {
+    name.rsplit('/').next().unwrap_or(name).to_string()
+}
+
+/// Cumulative (read, written) bytes per block device. Empty when /proc/diskstats
+/// isn't available, e.g. on other platforms.
+fn read_io_counters() -> HashMap<String, (u64, u64)> {
+    let Ok(contents) = fs::read_to_string("/proc/diskstats") else {
+        return HashMap::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // major minor name reads merged sectors_read ms writes merged sectors_written ...
+            let sectors_read: u64 = fields.get(5)?.parse().ok()?;
+            let sectors_written: u64 = fields.get(9)?.parse().ok()?;
+            Some((
+                fields.get(2)?.to_string(),
+                (sectors_read * SECTOR_SIZE, sectors_written * SECTOR_SIZE),
+            ))
+        })
+        .collect()
+}
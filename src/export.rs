@@ -0,0 +1,124 @@
+//! Dump of the process table as it's shown, with the filter and sort applied,
+//! for attaching to bug reports and tickets.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::columns::Column;
+use crate::process::{self, Process};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Aligned columns, the way the table looks
+    Text,
+    /// Raw values in base units, summary lines start with #
+    Csv,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+/// Write `m-as-processes-<unix time>.<ext>` to the working directory, like `bundle`
+pub fn write(
+    format: Format,
+    summary: &[(&str, String)],
+    columns: &[Column],
+    processes: &[Process],
+) -> io::Result<PathBuf> {
+    let now = SystemTime::now();
+    let timestamp = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let path = PathBuf::from(format!(
+        "m-as-processes-{}.{}",
+        timestamp,
+        format.extension()
+    ));
+
+    let mut header = vec![("taken", process::format_timestamp(now, true))];
+    header.extend(summary.iter().map(|(key, value)| (*key, value.clone())));
+    let contents = match format {
+        Format::Text => text(&header, columns, processes),
+        Format::Csv => csv(&header, columns, processes),
+    };
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+fn text(summary: &[(&str, String)], columns: &[Column], processes: &[Process]) -> String {
+    let mut out = String::new();
+    for (key, value) in summary {
+        let _ = writeln!(out, "{:<10} {}", format!("{}:", key), value);
+    }
+    out.push('\n');
+
+    let rows: Vec<Vec<String>> = processes
+        .iter()
+        .map(|process| columns.iter().map(|column| column.text(process)).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.title().chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut push_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let _ = writeln!(out, "{}", line.trim_end());
+    };
+    push_row(columns.iter().map(|column| column.title()).collect());
+    for row in &rows {
+        push_row(row.iter().map(String::as_str).collect());
+    }
+    out
+}
+
+fn csv(summary: &[(&str, String)], columns: &[Column], processes: &[Process]) -> String {
+    let mut out = String::new();
+    for (key, value) in summary {
+        let _ = writeln!(out, "# {}: {}", key, value);
+    }
+    let keys: Vec<String> = columns.iter().map(|c| c.key().to_string()).collect();
+    let _ = writeln!(out, "{}", keys.join(","));
+    for process in processes {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match column.json(process) {
+                Value::Null => String::new(),
+                Value::String(text) => csv_field(&text),
+                other => other.to_string(),
+            })
+            .collect();
+        let _ = writeln!(out, "{}", cells.join(","));
+    }
+    out
+}
+
+/// Quote fields with separators, quotes or line breaks, command lines have all of them
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
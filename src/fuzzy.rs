@@ -0,0 +1,83 @@
+//! fzf style fuzzy matching for the process filter: the query's characters
+//! have to appear in order, tight runs and word starts score higher.
+
+/// Every matched character
+const SCORE_MATCH: i64 = 16;
+/// Extra for a character right after the previous match
+const BONUS_CONSECUTIVE: i64 = 8;
+/// Extra for the start of the text or of a word ("/usr/bin/python" -> p)
+const BONUS_WORD_START: i64 = 8;
+/// Per skipped character between the first and last match
+const PENALTY_GAP: i64 = 1;
+
+/// A successful match, `positions` are char indices into the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// Case-insensitive match of `query` against `text`, None when some character is missing.
+/// Like fzf's v1 algorithm: find the first complete match, then walk back from its
+/// end to get the shortest window before scoring.
+pub fn find(query: &str, text: &str) -> Option<Match> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(Match {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().copied().map(lowercase).collect();
+
+    // Forward: where the first complete match ends
+    let mut next = 0;
+    let mut end = None;
+    for (i, c) in lower.iter().enumerate() {
+        if *c == query[next] {
+            next += 1;
+            if next == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward: the latest start that still fits the query before `end`
+    let mut positions = vec![0; query.len()];
+    let mut remaining = query.len();
+    for i in (0..=end).rev() {
+        if lower[i] == query[remaining - 1] {
+            remaining -= 1;
+            positions[remaining] = i;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    for (n, &position) in positions.iter().enumerate() {
+        score += SCORE_MATCH;
+        if n > 0 && positions[n - 1] + 1 == position {
+            score += BONUS_CONSECUTIVE;
+        }
+        if position == 0 || !chars[position - 1].is_alphanumeric() {
+            score += BONUS_WORD_START;
+        }
+    }
+    let span = positions[positions.len() - 1] - positions[0] + 1;
+    score -= (span - positions.len()) as i64 * PENALTY_GAP;
+    Some(Match { score, positions })
+}
+
+/// One char in, one char out, so positions stay valid for the original text
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
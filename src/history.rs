@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How often a chart is sampled and how much time it covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChartConfig {
+    pub sample_interval: Duration,
+    pub window: Duration,
+}
+
+/// Presets offered in the chart options popup, trading resolution for window length
+pub const CHART_PRESETS: &[ChartConfig] = &[
+    ChartConfig::new(Duration::from_millis(250), Duration::from_secs(30)),
+    ChartConfig::new(Duration::from_millis(500), Duration::from_secs(60)),
+    ChartConfig::new(Duration::from_secs(1), Duration::from_secs(60)),
+    ChartConfig::new(Duration::from_secs(1), Duration::from_secs(120)),
+    ChartConfig::new(Duration::from_secs(2), Duration::from_secs(300)),
+];
+
+impl ChartConfig {
+    pub const fn new(sample_interval: Duration, window: Duration) -> Self {
+        Self {
+            sample_interval,
+            window,
+        }
+    }
+
+    /// Number of points needed to fill the window
+    pub fn capacity(&self) -> usize {
+        (self.window.as_millis() / self.sample_interval.as_millis().max(1)).max(1) as usize
+    }
+
+    pub fn label(&self) -> String {
+        format!(
+            "{} samples over {}",
+            format_interval(self.sample_interval),
+            format_interval(self.window)
+        )
+    }
+}
+
+impl Default for ChartConfig {
+    fn default() -> Self {
+        // 1 update/sec over a minute, what the charts always used
+        CHART_PRESETS[2]
+    }
+}
+
+pub fn format_interval(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if millis.is_multiple_of(3_600_000) {
+        format!("{}h", millis / 3_600_000)
+    } else if millis.is_multiple_of(60_000) {
+        format!("{}m", millis / 60_000)
+    } else {
+        format!("{}s", millis as f64 / 1000.0)
+    }
+}
+
+/// Push a value and drop the oldest ones once the buffer is over capacity
+pub fn push_capped<T>(history: &mut VecDeque<T>, value: T, capacity: usize) {
+    history.push_back(value);
+    while history.len() > capacity {
+        history.pop_front();
+    }
+}
+
+/// Least-squares slope of (x, y) points, None with fewer than two distinct x
+pub fn slope(points: impl Iterator<Item = (f64, f64)> + Clone) -> Option<f64> {
+    let n = points.clone().count() as f64;
+    let mean_x = points.clone().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.clone().map(|(_, y)| y).sum::<f64>() / n;
+    let (covariance, variance) = points.fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+        let dx = x - mean_x;
+        (covariance + dx * (y - mean_y), variance + dx * dx)
+    });
+    (variance > f64::EPSILON).then(|| covariance / variance)
+}
+
+/// Convert a history sampled every `from` into one sampled every `to`.
+///
+/// Coarser targets average all source points falling into a bucket, finer
+/// targets repeat the nearest source point. Works backwards from the newest
+/// sample so the most recent data always lines up, and keeps at most `capacity` points.
+pub fn resample(
+    history: &VecDeque<f32>,
+    from: Duration,
+    to: Duration,
+    capacity: usize,
+) -> VecDeque<f32> {
+    if history.is_empty() || from == to {
+        let skip = history.len().saturating_sub(capacity);
+        return history.iter().skip(skip).copied().collect();
+    }
+
+    let ratio = to.as_secs_f64() / from.as_secs_f64();
+    let output_len = ((history.len() as f64 / ratio).floor() as usize).clamp(1, capacity);
+    let newest = history.len() - 1;
+
+    let mut resampled = VecDeque::with_capacity(output_len);
+    for k in 0..output_len {
+        // Indices counted back from the newest sample
+        let start = (k as f64 * ratio).floor() as usize;
+        let end = (((k + 1) as f64 * ratio).floor() as usize).max(start + 1);
+        let bucket: Vec<f32> = (start..end.min(history.len()))
+            .map(|back| history[newest - back])
+            .collect();
+        let value = if bucket.is_empty() {
+            history[newest - start.min(newest)]
+        } else {
+            bucket.iter().sum::<f32>() / bucket.len() as f32
+        };
+        resampled.push_front(value);
+    }
+    resampled
+}
+
+/// Exponential moving average of a history, oldest first. `alpha` is the weight of
+/// each new sample: 1 keeps the raw values, smaller values smooth harder.
+pub fn smooth(values: impl IntoIterator<Item = f64>, alpha: f64) -> Vec<f64> {
+    let mut average = None;
+    values
+        .into_iter()
+        .map(|value| {
+            let next = average.map_or(value, |average| alpha * value + (1.0 - alpha) * average);
+            average = Some(next);
+            next
+        })
+        .collect()
+}
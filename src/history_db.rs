@@ -0,0 +1,173 @@
+//! `--history-db`: CPU and memory totals written to SQLite every few seconds,
+//! summarized into time buckets for the History tab. One table, one row per
+//! sample, so the file can be queried with the sqlite3 shell just as well.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::snapshot::{CpuSnapshot, MemorySnapshot};
+
+/// One row per interval, about 8640 a day
+pub const RECORD_INTERVAL: Duration = Duration::from_secs(10);
+/// Rows older than this are dropped when the database is opened
+const RETENTION: Duration = Duration::from_secs(90 * 24 * 3600);
+
+/// How far back the History tab looks and how coarse its buckets are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryRange {
+    Hours6,
+    Day,
+    Week,
+    Month,
+}
+
+impl HistoryRange {
+    pub const ALL: [HistoryRange; 4] = [
+        HistoryRange::Hours6,
+        HistoryRange::Day,
+        HistoryRange::Week,
+        HistoryRange::Month,
+    ];
+
+    pub fn span(&self) -> Duration {
+        Duration::from_secs(match self {
+            HistoryRange::Hours6 => 6 * 3600,
+            HistoryRange::Day => 24 * 3600,
+            HistoryRange::Week => 7 * 24 * 3600,
+            HistoryRange::Month => 30 * 24 * 3600,
+        })
+    }
+
+    /// Around 50 buckets for every range
+    pub fn bucket(&self) -> Duration {
+        Duration::from_secs(match self {
+            HistoryRange::Hours6 => 10 * 60,
+            HistoryRange::Day => 30 * 60,
+            HistoryRange::Week => 3 * 3600,
+            HistoryRange::Month => 12 * 3600,
+        })
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistoryRange::Hours6 => "6 hours",
+            HistoryRange::Day => "24 hours",
+            HistoryRange::Week => "7 days",
+            HistoryRange::Month => "30 days",
+        }
+    }
+
+    /// The next longer range, or shorter with `forward` unset, wrapping around
+    pub fn cycle(&self, forward: bool) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|range| range == self)
+            .unwrap_or(0);
+        let len = Self::ALL.len();
+        Self::ALL[if forward {
+            (index + 1) % len
+        } else {
+            (index + len - 1) % len
+        }]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+/// Summary of the samples in one time bucket, memory in percent of the total
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub start: SystemTime,
+    pub samples: u64,
+    pub cpu: Stats,
+    pub memory: Stats,
+    pub swap: Stats,
+}
+
+pub struct HistoryDb {
+    connection: Connection,
+}
+
+impl HistoryDb {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS samples (
+                 timestamp INTEGER NOT NULL,
+                 cpu REAL NOT NULL,
+                 memory_used INTEGER NOT NULL,
+                 memory_total INTEGER NOT NULL,
+                 swap_used INTEGER NOT NULL,
+                 swap_total INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS samples_timestamp ON samples (timestamp);",
+        )?;
+        connection.execute(
+            "DELETE FROM samples WHERE timestamp < ?1",
+            params![unix_secs(SystemTime::now() - RETENTION)],
+        )?;
+        Ok(Self { connection })
+    }
+
+    pub fn record(&self, cpu: &CpuSnapshot, memory: &MemorySnapshot) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT INTO samples (timestamp, cpu, memory_used, memory_total, swap_used, swap_total)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                unix_secs(SystemTime::now()),
+                cpu.global_usage,
+                memory.used_memory as i64,
+                memory.total_memory as i64,
+                memory.used_swap as i64,
+                memory.total_swap as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Buckets of `range` that have samples, oldest first
+    pub fn summarize(&self, range: HistoryRange) -> rusqlite::Result<Vec<Bucket>> {
+        let bucket = range.bucket().as_secs() as i64;
+        let since = unix_secs(SystemTime::now() - range.span());
+        let mut statement = self.connection.prepare_cached(
+            "SELECT timestamp / ?1 * ?1 AS bucket, COUNT(*),
+                    MIN(cpu), AVG(cpu), MAX(cpu),
+                    MIN(memory), AVG(memory), MAX(memory),
+                    MIN(swap), AVG(swap), MAX(swap)
+             FROM (SELECT timestamp, cpu,
+                          100.0 * memory_used / MAX(memory_total, 1) AS memory,
+                          100.0 * swap_used / MAX(swap_total, 1) AS swap
+                   FROM samples WHERE timestamp >= ?2)
+             GROUP BY bucket ORDER BY bucket",
+        )?;
+        let buckets = statement.query_map(params![bucket, since], |row| {
+            let stats = |first: usize| -> rusqlite::Result<Stats> {
+                Ok(Stats {
+                    min: row.get(first)?,
+                    avg: row.get(first + 1)?,
+                    max: row.get(first + 2)?,
+                })
+            };
+            Ok(Bucket {
+                start: UNIX_EPOCH + Duration::from_secs(row.get::<_, i64>(0)?.max(0) as u64),
+                samples: row.get::<_, i64>(1)?.max(0) as u64,
+                cpu: stats(2)?,
+                memory: stats(5)?,
+                swap: stats(8)?,
+            })
+        })?;
+        buckets.collect()
+    }
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
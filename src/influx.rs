@@ -0,0 +1,268 @@
+//! `--influx URL`: metrics pushed in InfluxDB line protocol, for charting in
+//! Grafana without Prometheus. Works with the v1 `/write?db=` and the v2
+//! `/api/v2/write?org=&bucket=` endpoints, the token for either comes from
+//! `INFLUX_TOKEN`. Plain HTTP only, TLS is left to a local proxy.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use crate::snapshot::Snapshot;
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(3);
+/// Process names with the most CPU, each name is a series so they're capped
+const TOP_PROCESSES: usize = 10;
+
+/// Where to POST, parsed from `http://host[:port]/path?query`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    /// Path and query, with `precision=s` added since timestamps are in seconds
+    pub target: String,
+}
+
+impl Endpoint {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        if url.starts_with("https://") {
+            return Err("only http:// is supported, use a local TLS proxy for https".to_string());
+        }
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("'{}' is not an http:// URL", url))?;
+        let (authority, target) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/write"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("invalid port '{}' in {}", port, url))?,
+            ),
+            None => (authority, 8086),
+        };
+        if host.is_empty() {
+            return Err(format!("no host in {}", url));
+        }
+        let mut target = target.to_string();
+        if !target.contains("precision=") {
+            target.push(if target.contains('?') { '&' } else { '?' });
+            target.push_str("precision=s");
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            target,
+        })
+    }
+
+    /// One POST with the lines as body, errors on anything but a 2xx answer
+    pub fn push(&self, body: &str, token: Option<&str>) -> io::Result<()> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not found"))?;
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.0\r\nHost: {}:{}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n",
+            self.target,
+            self.host,
+            self.port,
+            body.len()
+        );
+        if let Some(token) = token {
+            let _ = write!(request, "Authorization: Token {}\r\n", token);
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+        stream.write_all(body.as_bytes())?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        // "HTTP/1.1 204 No Content"
+        let mut status_line = response.lines().next().unwrap_or_default().splitn(3, ' ');
+        match status_line.nth(1).and_then(|code| code.parse::<u16>().ok()) {
+            Some(code) if (200..300).contains(&code) => Ok(()),
+            Some(code) => {
+                // Influx explains rejected lines in the JSON body
+                let detail = response
+                    .split_once("\r\n\r\n")
+                    .map_or("", |(_, body)| body.trim());
+                Err(io::Error::other(format!("HTTP {} {}", code, detail)))
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an HTTP response",
+            )),
+        }
+    }
+}
+
+/// Pushes from a background thread so a slow or unreachable server doesn't
+/// hold up the sampler that produced the lines
+pub struct Exporter {
+    pub endpoint: Endpoint,
+    pub interval: Duration,
+    /// Tagged on every line
+    host: String,
+    sender: SyncSender<String>,
+    results: Receiver<Result<(), String>>,
+    pub last_error: Option<String>,
+    pub pushed: u64,
+}
+
+impl Exporter {
+    pub fn start(endpoint: Endpoint, interval: Duration) -> Self {
+        // One batch in flight, newer ones are dropped while it's stuck
+        let (sender, batches) = mpsc::sync_channel::<String>(1);
+        let (report, results) = mpsc::channel();
+        let token = std::env::var("INFLUX_TOKEN").ok();
+        let pusher = endpoint.clone();
+        thread::spawn(move || {
+            for body in batches {
+                let result = pusher
+                    .push(&body, token.as_deref())
+                    .map_err(|e| e.to_string());
+                if report.send(result).is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            endpoint,
+            interval,
+            host: sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string()),
+            sender,
+            results,
+            last_error: None,
+            pushed: 0,
+        }
+    }
+
+    pub fn send(&mut self, snapshot: &Snapshot) {
+        self.collect();
+        let body = lines(snapshot, &self.host);
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(body) {
+            tracing::debug!("influx push still running, skipped a batch");
+        }
+    }
+
+    /// Take in finished pushes, logging when they start or stop failing
+    fn collect(&mut self) {
+        for result in self.results.try_iter() {
+            match (&result, self.last_error.is_some()) {
+                (Err(e), false) => tracing::warn!(
+                    "influx push to {}:{} failing: {}",
+                    self.endpoint.host,
+                    self.endpoint.port,
+                    e
+                ),
+                (Ok(()), true) => tracing::info!("influx push works again"),
+                _ => {}
+            }
+            if result.is_ok() {
+                self.pushed += 1;
+            }
+            self.last_error = result.err();
+        }
+    }
+}
+
+/// Line protocol for one snapshot, every line tagged with the host name
+pub fn lines(snapshot: &Snapshot, host: &str) -> String {
+    let timestamp = snapshot.taken_at / 1000;
+    let host = escape_tag(host);
+    let mut out = String::new();
+    let mut line = |measurement: &str, tags: &str, fields: String| {
+        let _ = writeln!(
+            out,
+            "{},host={}{} {} {}",
+            measurement, host, tags, fields, timestamp
+        );
+    };
+
+    let cpu = &snapshot.cpu;
+    let mut fields = format!("usage={}", cpu.global_usage);
+    if let Some(breakdown) = &cpu.breakdown {
+        let _ = write!(
+            fields,
+            ",user={},system={},iowait={},steal={}",
+            breakdown.user, breakdown.system, breakdown.iowait, breakdown.steal
+        );
+    }
+    line("m_as_cpu", "", fields);
+    for (index, usage) in cpu.cores.iter().enumerate() {
+        line(
+            "m_as_cpu_core",
+            &format!(",core={}", index),
+            format!("usage={}", usage),
+        );
+    }
+
+    let memory = &snapshot.mem;
+    line(
+        "m_as_memory",
+        "",
+        format!(
+            "used={}i,total={}i,swap_used={}i,swap_total={}i",
+            memory.used_memory, memory.total_memory, memory.used_swap, memory.total_swap
+        ),
+    );
+
+    if let Some(network) = &snapshot.net {
+        line(
+            "m_as_network",
+            "",
+            format!("rx_rate={}i,tx_rate={}i", network.rx_rate, network.tx_rate),
+        );
+    }
+
+    // Summed by name, PIDs as tags would create a series per process ever started
+    let mut by_name: Vec<(&str, f32, f64, usize)> = Vec::new();
+    for process in snapshot.procs.iter().filter(|p| p.thread.is_none()) {
+        match by_name.iter_mut().find(|(name, ..)| *name == process.name) {
+            Some((_, cpu, memory, count)) => {
+                *cpu += process.cpu_usage;
+                *memory += process.memory_mb;
+                *count += 1;
+            }
+            None => by_name.push((&process.name, process.cpu_usage, process.memory_mb, 1)),
+        }
+    }
+    by_name.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.2.total_cmp(&a.2)));
+    for (name, cpu, memory_mb, count) in by_name.into_iter().take(TOP_PROCESSES) {
+        line(
+            "m_as_process",
+            &format!(",name={}", escape_tag(name)),
+            format!(
+                "cpu={},memory={}i,count={}i",
+                cpu,
+                (memory_mb * 1024.0 * 1024.0) as u64,
+                count
+            ),
+        );
+    }
+    out
+}
+
+/// Tag keys and values can't contain unescaped commas, equals signs or spaces
+fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        // A newline would end the line
+        let c = if c == '\n' { ' ' } else { c };
+        if matches!(c, ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
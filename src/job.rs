@@ -0,0 +1,242 @@
+//! `møas run -- <command>`: start a command, follow it and its children in
+//! the TUI and print what it used once it exits, like `/usr/bin/time` with
+//! live graphs.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use sysinfo::Pid;
+
+use crate::cli::Args;
+use crate::columns::format_cpu_time;
+use crate::tui;
+use crate::units;
+
+/// The command while it runs, for the panic hook. Cleared once it's reaped, so
+/// a reused PID is never signalled.
+static RUNNING: Mutex<Option<(Pid, String)>> = Mutex::new(None);
+
+/// The command being followed, as the TUI sees it
+pub struct Job {
+    pub pid: Pid,
+    /// Command line as given, for the process table title
+    pub command: String,
+    pub started: Instant,
+    done: Arc<AtomicBool>,
+    /// Output lines read so far, with whether they came from stderr
+    output: Option<Receiver<(String, bool)>>,
+}
+
+impl Job {
+    pub fn finished(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Send the command's output to the Log tab. Only called once logging is set
+    /// up, until then the lines are queued.
+    pub fn forward_output(&mut self) {
+        let Some(output) = self.output.take() else {
+            return;
+        };
+        thread::spawn(move || {
+            for (line, stderr) in output {
+                if stderr {
+                    tracing::warn!("{}", line);
+                } else {
+                    tracing::info!("{}", line);
+                }
+            }
+        });
+    }
+}
+
+/// What the command used, the totals include children it waited for
+struct Usage {
+    status: ExitStatus,
+    wall: Duration,
+    /// User and system CPU time
+    cpu: Option<(Duration, Duration)>,
+    /// Of the largest single process, in bytes
+    peak_rss: Option<u64>,
+    /// Bytes read from and written to storage
+    io: Option<(u64, u64)>,
+}
+
+pub fn run(args: Args, command: Vec<String>) -> Result<()> {
+    let mut child = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        // The terminal belongs to the TUI
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err_with(|| format!("failed to start {}", command[0]))?;
+    // Read right away, a full pipe would stop the command before the TUI is up
+    // or after it failed
+    let (sender, output) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        drain(stdout, false, sender.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        drain(stderr, true, sender);
+    }
+    let started = Instant::now();
+    let done = Arc::new(AtomicBool::new(false));
+    let job = Job {
+        pid: Pid::from_u32(child.id()),
+        command: command.join(" "),
+        started,
+        done: Arc::clone(&done),
+        output: Some(output),
+    };
+    let (pid, command) = (job.pid, job.command.clone());
+    *RUNNING.lock().unwrap() = Some((pid, command.clone()));
+    let waiter = thread::spawn(move || {
+        let usage = wait(child, started);
+        *RUNNING.lock().unwrap() = None;
+        done.store(true, Ordering::Relaxed);
+        usage
+    });
+
+    let result = tui::main(args, Some(job));
+    if result.is_err() {
+        stop_running();
+    } else if !waiter.is_finished() {
+        eprintln!(
+            "{} (PID {}) is still running, waiting for it to finish (Ctrl-C stops it)...",
+            command, pid
+        );
+    }
+    let usage = waiter
+        .join()
+        .map_err(|_| eyre!("waiting for {} failed", command))?
+        .wrap_err_with(|| format!("failed to wait for {}", command))?;
+    result?;
+
+    eprintln!("{}", summary(&command, &usage));
+    std::process::exit(exit_code(usage.status));
+}
+
+fn drain(pipe: impl Read + Send + 'static, stderr: bool, lines: Sender<(String, bool)>) {
+    thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(io::Result::ok) {
+            // Nobody forwards them once the TUI is gone, keep reading anyway
+            let _ = lines.send((line, stderr));
+        }
+    });
+}
+
+/// The TUI failed or panicked, there's nothing left to watch the command with
+pub fn stop_running() {
+    // try_lock: the panic may have happened while it was held
+    let Ok(running) = RUNNING.try_lock() else {
+        return;
+    };
+    if let Some((pid, command)) = running.as_ref() {
+        eprintln!("Stopping {} (PID {})...", command, pid);
+        terminate(*pid);
+    }
+}
+
+#[cfg(unix)]
+fn terminate(pid: Pid) {
+    // SAFETY: plain syscall, the PID is our unreaped child so it can't be reused
+    unsafe { libc::kill(pid.as_u32() as libc::pid_t, libc::SIGTERM) };
+}
+
+#[cfg(not(unix))]
+fn terminate(_pid: Pid) {}
+
+/// The command's own status, a signal becomes 128 + its number like in shells
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+/// "make -j8: exit status: 0 after 1:02.30" and one line per measurement
+fn summary(command: &str, usage: &Usage) -> String {
+    let mut lines = vec![format!(
+        "{}: {} after {}",
+        command,
+        usage.status,
+        format_cpu_time(usage.wall.as_millis() as u64)
+    )];
+    if let Some((user, system)) = usage.cpu {
+        lines.push(format!(
+            "  CPU time   {} user, {} system",
+            format_cpu_time(user.as_millis() as u64),
+            format_cpu_time(system.as_millis() as u64)
+        ));
+    }
+    if let Some(peak_rss) = usage.peak_rss {
+        lines.push(format!(
+            "  peak RSS   {} (largest process)",
+            units::format_size(peak_rss)
+        ));
+    }
+    if let Some((read, written)) = usage.io {
+        lines.push(format!(
+            "  disk I/O   {} read, {} written",
+            units::format_size(read),
+            units::format_size(written)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// wait4 rather than `Child::wait` for the resource usage that comes with it
+#[cfg(unix)]
+fn wait(child: Child, started: Instant) -> io::Result<Usage> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    // SAFETY: all zeroes is a valid rusage, wait4 overwrites it
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: the PID is our own child that nothing else reaps, both pointers are valid
+        if unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut usage) } >= 0 {
+            break;
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+    let time = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    };
+    // Kilobytes everywhere but macOS
+    let rss_scale = if cfg!(target_os = "macos") { 1 } else { 1024 };
+    Ok(Usage {
+        status: ExitStatus::from_raw(status),
+        wall: started.elapsed(),
+        cpu: Some((time(usage.ru_utime), time(usage.ru_stime))),
+        peak_rss: Some(usage.ru_maxrss as u64 * rss_scale),
+        // Counted in 512 byte blocks
+        io: Some((usage.ru_inblock as u64 * 512, usage.ru_oublock as u64 * 512)),
+    })
+}
+
+#[cfg(not(unix))]
+fn wait(mut child: Child, started: Instant) -> io::Result<Usage> {
+    Ok(Usage {
+        status: child.wait()?,
+        wall: started.elapsed(),
+        cpu: None,
+        peak_rss: None,
+        io: None,
+    })
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::time::Instant;
+
+/// A minute at the 1s kernel refresh
+const HISTORY_CAPACITY: usize = 60;
+/// Share of a limit from which it's shown as a warning
+pub const LIMIT_WARNING: f64 = 0.8;
+pub const LIMIT_CRITICAL: f64 = 0.95;
+
+/// Cumulative counters from /proc/stat
+#[derive(Debug, Clone, Copy, Default)]
+struct Counters {
+    context_switches: u64,
+    interrupts: u64,
+    forks: u64,
+}
+
+/// Context switch, interrupt and fork rates with their recent history.
+/// A spike in context switches often shows contention that CPU% hides.
+#[derive(Debug, Default)]
+pub struct KernelStats {
+    /// False where /proc/stat doesn't exist
+    pub available: bool,
+    pub context_switches: f64,
+    pub interrupts: f64,
+    pub forks: f64,
+    /// Runnable and uninterruptible (blocked on I/O) tasks right now
+    pub running: u64,
+    pub blocked: u64,
+    pub context_switch_history: VecDeque<u64>,
+    pub interrupt_history: VecDeque<u64>,
+    pub limits: SystemLimits,
+    last: Option<(Counters, Instant)>,
+}
+
+/// Usage of a system-wide limit, e.g. open files against fs.file-max
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limit {
+    pub used: u64,
+    pub max: u64,
+}
+
+impl Limit {
+    pub fn ratio(&self) -> f64 {
+        self.used as f64 / self.max.max(1) as f64
+    }
+}
+
+/// Kernel tables that make fork() or open() fail once full, None where unreadable
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemLimits {
+    /// Allocated file handles against fs.file-max
+    pub open_files: Option<Limit>,
+    /// Tasks (every thread of every process) against kernel.threads-max
+    pub threads: Option<Limit>,
+    /// Processes against kernel.pid_max. Threads take PIDs too, so `threads`
+    /// usually runs out of PID space first.
+    pub pids: Option<Limit>,
+}
+
+impl SystemLimits {
+    pub fn read() -> Self {
+        let read_u64 = |path: &str| fs::read_to_string(path).ok()?.trim().parse::<u64>().ok();
+        // "allocated free max", free has been 0 since 2.6
+        let open_files = fs::read_to_string("/proc/sys/fs/file-nr")
+            .ok()
+            .and_then(|contents| {
+                let fields: Vec<u64> = contents
+                    .split_whitespace()
+                    .filter_map(|field| field.parse().ok())
+                    .collect();
+                Some(Limit {
+                    used: fields.first()?.saturating_sub(*fields.get(1)?),
+                    max: *fields.get(2)?,
+                })
+            });
+        // The fourth field of loadavg is "running/total tasks"
+        let tasks = fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|contents| {
+                contents
+                    .split_whitespace()
+                    .nth(3)?
+                    .split_once('/')?
+                    .1
+                    .parse::<u64>()
+                    .ok()
+            });
+        let processes = fs::read_dir("/proc").ok().map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()))
+                })
+                .count() as u64
+        });
+        Self {
+            open_files,
+            threads: tasks
+                .zip(read_u64("/proc/sys/kernel/threads-max"))
+                .map(|(used, max)| Limit { used, max }),
+            pids: processes
+                .zip(read_u64("/proc/sys/kernel/pid_max"))
+                .map(|(used, max)| Limit { used, max }),
+        }
+    }
+
+    /// (name, limit) for every readable limit
+    pub fn all(&self) -> Vec<(&'static str, Limit)> {
+        [
+            ("fds", self.open_files),
+            ("threads", self.threads),
+            ("pids", self.pids),
+        ]
+        .into_iter()
+        .filter_map(|(name, limit)| Some((name, limit?)))
+        .collect()
+    }
+}
+
+impl KernelStats {
+    pub fn new() -> Self {
+        let mut stats = Self::default();
+        stats.update();
+        stats
+    }
+
+    pub fn update(&mut self) {
+        let Ok(contents) = fs::read_to_string("/proc/stat") else {
+            self.available = false;
+            return;
+        };
+        self.available = true;
+        let now = Instant::now();
+
+        let mut counters = Counters::default();
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(key), Some(value)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            // The first intr field is the total, per-IRQ counts follow
+            let value = value.parse().unwrap_or(0);
+            match key {
+                "ctxt" => counters.context_switches = value,
+                "intr" => counters.interrupts = value,
+                "processes" => counters.forks = value,
+                "procs_running" => self.running = value,
+                "procs_blocked" => self.blocked = value,
+                _ => {}
+            }
+        }
+
+        if let Some((last, at)) = self.last {
+            let secs = now.duration_since(at).as_secs_f64();
+            if secs > 0.0 {
+                let rate =
+                    |current: u64, previous: u64| current.saturating_sub(previous) as f64 / secs;
+                self.context_switches = rate(counters.context_switches, last.context_switches);
+                self.interrupts = rate(counters.interrupts, last.interrupts);
+                self.forks = rate(counters.forks, last.forks);
+                push_capped(
+                    &mut self.context_switch_history,
+                    self.context_switches as u64,
+                );
+                push_capped(&mut self.interrupt_history, self.interrupts as u64);
+            }
+        }
+        self.last = Some((counters, now));
+
+        let previous = self.limits;
+        self.limits = SystemLimits::read();
+        // Log the crossing rather than every refresh above the threshold
+        let before = previous.all();
+        for (name, limit) in self.limits.all() {
+            let was_below = before
+                .iter()
+                .find(|(previous_name, _)| *previous_name == name)
+                .is_none_or(|(_, before)| before.ratio() < LIMIT_WARNING);
+            if limit.ratio() >= LIMIT_WARNING && was_below {
+                tracing::warn!(
+                    "{} at {:.0}% of the system limit ({} of {})",
+                    name,
+                    limit.ratio() * 100.0,
+                    limit.used,
+                    limit.max
+                );
+            }
+        }
+    }
+
+    /// Whether the current context switch rate is well above the last minute's average
+    pub fn context_switch_spike(&self) -> bool {
+        let history = &self.context_switch_history;
+        if history.len() < 10 {
+            return false;
+        }
+        let average = history.iter().sum::<u64>() as f64 / history.len() as f64;
+        self.context_switches > average * 2.0
+    }
+}
+
+fn push_capped(history: &mut VecDeque<u64>, value: u64) {
+    history.push_back(value);
+    if history.len() > HISTORY_CAPACITY {
+        history.pop_front();
+    }
+}
@@ -0,0 +1,131 @@
+//! Pod names for processes in Kubernetes containers. The pod UID comes from the
+//! cgroup path, names from kubelet's log directories, which are named
+//! `<namespace>_<pod>_<uid>`, or from the kubelet read-only API where the logs
+//! aren't readable.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::process::{self, Process};
+
+const POD_LOG_ROOT: &str = "/var/log/pods";
+/// Disabled by default since 1.10 but still common on self-managed nodes
+const KUBELET_READ_ONLY: &str = "127.0.0.1:10255";
+const KUBELET_TIMEOUT: Duration = Duration::from_millis(500);
+/// Pods come and go far slower than the process refresh
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Earliest refresh for a UID that isn't known yet, so unknown pods don't cause one per tick
+const MISS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pod {
+    pub namespace: String,
+    pub name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct PodIndex {
+    pods: HashMap<String, Pod>,
+    refreshed: Option<Instant>,
+}
+
+impl PodIndex {
+    /// "namespace/name" into `process.pod` for every containerized process,
+    /// the bare UID when the pod isn't known (yet)
+    pub fn annotate(&mut self, processes: &mut [Process]) {
+        let uids: Vec<Option<String>> = processes
+            .iter()
+            .map(|p| process::container(p.pid).and_then(|c| c.pod_uid))
+            .collect();
+        let since = self.refreshed.map(|at| at.elapsed());
+        // A pod that started since the last refresh is picked up early
+        let missing = uids
+            .iter()
+            .flatten()
+            .any(|uid| !self.pods.contains_key(uid));
+        if since.is_none_or(|since| {
+            since >= REFRESH_INTERVAL || (missing && since >= MISS_REFRESH_INTERVAL)
+        }) {
+            self.refresh();
+        }
+
+        for (process, uid) in processes.iter_mut().zip(uids) {
+            let Some(uid) = uid else {
+                continue;
+            };
+            process.pod = Some(match self.pods.get(&uid) {
+                Some(pod) => format!("{}/{}", pod.namespace, pod.name),
+                None => uid,
+            });
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.refreshed = Some(Instant::now());
+        self.pods = read_pod_logs();
+        if self.pods.is_empty() {
+            self.pods = read_kubelet().unwrap_or_default();
+        }
+    }
+}
+
+fn read_pod_logs() -> HashMap<String, Pod> {
+    let Ok(entries) = fs::read_dir(POD_LOG_ROOT) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Namespaces and pod names can't contain '_', UIDs neither
+            let mut parts = name.splitn(3, '_');
+            let (namespace, pod, uid) = (parts.next()?, parts.next()?, parts.next()?);
+            Some((
+                uid.to_string(),
+                Pod {
+                    namespace: namespace.to_string(),
+                    name: pod.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// GET /pods, the API is plain HTTP without authentication
+fn read_kubelet() -> Option<HashMap<String, Pod>> {
+    let address = KUBELET_READ_ONLY.parse().ok()?;
+    let mut stream = TcpStream::connect_timeout(&address, KUBELET_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(KUBELET_TIMEOUT)).ok()?;
+    write!(
+        stream,
+        "GET /pods HTTP/1.0\r\nHost: {}\r\n\r\n",
+        KUBELET_READ_ONLY
+    )
+    .ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    let (_, body) = response.split_once("\r\n\r\n")?;
+
+    let pods: Value = serde_json::from_str(body).ok()?;
+    Some(
+        pods["items"]
+            .as_array()?
+            .iter()
+            .filter_map(|item| {
+                let metadata = &item["metadata"];
+                Some((
+                    metadata["uid"].as_str()?.to_string(),
+                    Pod {
+                        namespace: metadata["namespace"].as_str()?.to_string(),
+                        name: metadata["name"].as_str()?.to_string(),
+                    },
+                ))
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,30 @@
+//! m-as's data collection without the TUI.
+//!
+//! Every collector implements [`Sampler`]:
+//!
+//! ```no_run
+//! use m_as::cpu::CpuInfo;
+//! use m_as::Sampler;
+//!
+//! let mut cpu = CpuInfo::new();
+//! loop {
+//!     std::thread::sleep(std::time::Duration::from_secs(1));
+//!     cpu.update();
+//!     println!("{:.1}%", cpu.snapshot().global_usage);
+//! }
+//! ```
+
+pub mod battery;
+pub mod cpu;
+pub mod cpufreq;
+pub mod history;
+pub mod kernel;
+pub mod memory;
+pub mod network;
+pub mod platform;
+pub mod process;
+pub mod sampler;
+pub mod snapshot;
+pub mod units;
+
+pub use sampler::Sampler;
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+
+use crate::cli::RecordOptions;
+use crate::process::format_timestamp;
+use crate::recorder::RotatingWriter;
+
+/// Entries kept for the Log tab, the file has the rest
+const LOG_CAPACITY: usize = 500;
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+const KEEP_LOGS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: SystemTime,
+    pub level: Level,
+    /// Module the event came from, without the crate name
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<5} {}: {}",
+            format_timestamp(self.time, true),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// Recent log entries, shared between the logging layer and the TUI
+pub type LogBuffer = Arc<Mutex<VecDeque<LogEntry>>>;
+
+/// `$XDG_DATA_HOME/m-as/m-as.log`, rotated at 1 MiB
+pub fn default_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(dir.join("m-as").join("m-as.log"))
+}
+
+/// Route `tracing` events to the log file and the returned buffer. Nothing goes
+/// to stderr, it would end up in the middle of the TUI.
+pub fn init() -> LogBuffer {
+    let buffer = LogBuffer::default();
+    // Without a writable file the Log tab still works
+    let writer = default_path().and_then(|path| {
+        std::fs::create_dir_all(path.parent()?).ok()?;
+        RotatingWriter::open(RecordOptions {
+            path,
+            max_size: Some(MAX_LOG_SIZE),
+            max_age: None,
+            keep: KEEP_LOGS,
+            fsync_interval: None,
+        })
+        .ok()
+    });
+    let layer = LogLayer {
+        buffer: Arc::clone(&buffer),
+        writer: writer.map(Mutex::new),
+    };
+    // Fails only when a subscriber is already set, which keeps logging working anyway
+    let _ = tracing_subscriber::registry().with(layer).try_init();
+    buffer
+}
+
+struct LogLayer {
+    buffer: LogBuffer,
+    writer: Option<Mutex<RotatingWriter>>,
+}
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        // Dependencies log too, only our own debug output is of interest
+        let ours = metadata.target().starts_with("m_as") || metadata.target().starts_with("møas");
+        let max_level = if ours { Level::DEBUG } else { Level::INFO };
+        if *metadata.level() > max_level {
+            return;
+        }
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let target = metadata.target();
+        let entry = LogEntry {
+            time: SystemTime::now(),
+            level: *metadata.level(),
+            target: target
+                .split_once("::")
+                .map_or(target, |(_, module)| module)
+                .to_string(),
+            message: visitor.message,
+        };
+
+        if let Some(writer) = &self.writer {
+            // A full disk must not take the monitor down
+            let _ = writer.lock().unwrap().write_line(&entry.to_string());
+        }
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.push_back(entry);
+        if buffer.len() > LOG_CAPACITY {
+            buffer.pop_front();
+        }
+    }
+}
+
+/// The message followed by any other fields as `key=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{}", value));
+        } else {
+            let _ = write!(self.message, " {}={}", field.name(), value);
+        }
+    }
+}
@@ -1,13 +1,85 @@
-mod cpu;
-mod memory;
-// mod network;
-mod process;
+mod alerts;
+#[cfg(feature = "tokio")]
+mod async_sampler;
+mod bundle;
+mod capabilities;
+mod cli;
+mod columns;
+mod commands;
+mod config;
+mod crash;
+mod diff;
+mod disk;
+mod export;
+mod fuzzy;
+mod history_db;
+mod influx;
+mod job;
+mod kube;
+mod logging;
+#[cfg(unix)]
+mod mirror;
+#[cfg(not(unix))]
+#[path = "mirror_other.rs"]
+mod mirror;
+mod numa;
+mod panels;
+mod plain;
+mod probes;
+mod recorder;
+mod remote;
+mod scheduler;
+mod selfmon;
+mod services;
+mod sessions;
+#[cfg(feature = "smart")]
+mod smart;
+mod stream;
+mod theme;
+mod top;
 mod tui;
+mod ui_state;
+mod watch;
 use color_eyre::Result;
+// The collectors live in the library so other crates can embed them
+use m_as::{
+    battery, cpu, cpufreq, history, kernel, memory, network, platform, process, snapshot, units,
+};
 
 pub fn main() -> Result<()> {
     // process::main();
     // cpu::main();
     // memory::main();
-    tui::main()
+    color_eyre::install()?;
+    let args = cli::Args::parse()?;
+    units::Units::set_current(args.units);
+    if let Some(cli::Command::Bundle { output }) = args.command.clone() {
+        return bundle::run(&args, output);
+    }
+    if let Some(cli::Command::Top { count, sort }) = args.command {
+        return top::run(count, sort);
+    }
+    if let Some(cli::Command::Watch { target }) = &args.command {
+        return watch::run(target, args.interval);
+    }
+    if let Some(cli::Command::Run { command }) = args.command.clone() {
+        return job::run(args, command);
+    }
+    if let Some(address) = &args.serve {
+        return remote::serve(address, args.interval);
+    }
+    if args.plain {
+        return plain::run(args.interval, args.memory_thresholds);
+    }
+    match args.stream {
+        Some(format) => stream::run(
+            format,
+            args.interval,
+            args.record,
+            args.columns,
+            args.history_db,
+            args.influx.map(|endpoint| (endpoint, args.influx_interval)),
+        ),
+        None => tui::main(args, None),
+    }
 }
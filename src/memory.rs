@@ -1,7 +1,14 @@
 use std::collections::VecDeque;
+use std::fs;
+use std::time::Instant;
 
 use sysinfo::System;
 
+use crate::platform::{self, MemoryPressure};
+use crate::sampler::Sampler;
+use crate::snapshot::MemorySnapshot;
+use crate::units;
+
 #[derive(Debug)]
 pub struct MemoryInfo {
     system: System,
@@ -13,6 +20,140 @@ pub struct MemoryInfo {
     pub total_swap: u64,
     pub used_swap: u64,
     pub swap_history: VecDeque<f32>,
+    /// None where the platform doesn't report it (no PSI, Windows)
+    pub pressure: Option<MemoryPressure>,
+    /// None until two /proc/vmstat readings exist, and on other platforms
+    pub paging: Option<PagingRates>,
+    pub paging_history: PagingHistory,
+    last_vmstat: Option<(VmstatCounters, Instant)>,
+}
+
+/// Swap and paging traffic in bytes per second. Swap in/out that keeps going
+/// is what makes a box slow, no matter how much swap is in use.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PagingRates {
+    pub swap_in: f64,
+    pub swap_out: f64,
+    /// Reads from and writes to block devices, file pages and swap together
+    pub page_in: f64,
+    pub page_out: f64,
+}
+
+/// Last minute of `PagingRates`, one entry per update
+#[derive(Debug, Clone, Default)]
+pub struct PagingHistory {
+    pub swap_in: VecDeque<u64>,
+    pub swap_out: VecDeque<u64>,
+    pub page_in: VecDeque<u64>,
+    pub page_out: VecDeque<u64>,
+}
+
+impl PagingHistory {
+    fn push(&mut self, rates: &PagingRates) {
+        for (history, rate) in [
+            (&mut self.swap_in, rates.swap_in),
+            (&mut self.swap_out, rates.swap_out),
+            (&mut self.page_in, rates.page_in),
+            (&mut self.page_out, rates.page_out),
+        ] {
+            history.push_back(rate as u64);
+            if history.len() > 60 {
+                history.pop_front();
+            }
+        }
+    }
+}
+
+/// Totals since boot from /proc/vmstat, swaps in pages, pgpg in KiB
+#[derive(Debug, Clone, Copy, Default)]
+struct VmstatCounters {
+    swap_in: u64,
+    swap_out: u64,
+    page_in: u64,
+    page_out: u64,
+}
+
+impl VmstatCounters {
+    fn read() -> Option<Self> {
+        let contents = fs::read_to_string("/proc/vmstat").ok()?;
+        let mut counters = Self::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            let value = value.trim().parse().unwrap_or(0);
+            match key {
+                "pswpin" => counters.swap_in = value,
+                "pswpout" => counters.swap_out = value,
+                "pgpgin" => counters.page_in = value,
+                "pgpgout" => counters.page_out = value,
+                _ => {}
+            }
+        }
+        Some(counters)
+    }
+}
+
+/// Usage percentages at which memory/swap are shown as warning or critical
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsageThresholds {
+    pub warning: f32,
+    pub critical: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageLevel {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Default for UsageThresholds {
+    fn default() -> Self {
+        Self {
+            warning: 70.0,
+            critical: 90.0,
+        }
+    }
+}
+
+impl UsageThresholds {
+    pub fn level(&self, percent: f32) -> UsageLevel {
+        if percent >= self.critical {
+            UsageLevel::Critical
+        } else if percent >= self.warning {
+            UsageLevel::Warning
+        } else {
+            UsageLevel::Normal
+        }
+    }
+}
+
+impl Default for MemoryInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for MemoryInfo {
+    type Snapshot = MemorySnapshot;
+
+    fn new() -> Self {
+        MemoryInfo::new()
+    }
+
+    fn update(&mut self) {
+        MemoryInfo::update(self);
+    }
+
+    fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            total_memory: self.total_memory,
+            used_memory: self.used_memory,
+            total_swap: self.total_swap,
+            used_swap: self.used_swap,
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -25,7 +166,7 @@ pub fn main() {
 
 impl MemoryInfo {
     pub fn new() -> MemoryInfo {
-        let mut system = System::new_all();
+        let mut system = System::new();
         system.refresh_memory();
 
         Self {
@@ -35,6 +176,10 @@ impl MemoryInfo {
             total_swap: system.total_swap(),
             used_swap: system.used_swap(),
             swap_history: VecDeque::with_capacity(60),
+            pressure: platform::memory_pressure(),
+            paging: None,
+            paging_history: PagingHistory::default(),
+            last_vmstat: VmstatCounters::read().map(|counters| (counters, Instant::now())),
             system,
         }
     }
@@ -47,6 +192,8 @@ impl MemoryInfo {
         self.used_memory = self.system.used_memory();
         self.total_swap = self.system.total_swap();
         self.used_swap = self.system.used_swap();
+        self.pressure = platform::memory_pressure();
+        self.update_paging();
 
         // Calculate memory percentage using available memory instead of used_memory
         let available_memory = self.system.available_memory();
@@ -72,6 +219,49 @@ impl MemoryInfo {
         }
     }
 
+    fn update_paging(&mut self) {
+        let Some(counters) = VmstatCounters::read() else {
+            self.paging = None;
+            return;
+        };
+        let now = Instant::now();
+        if let Some((last, at)) = self.last_vmstat {
+            let secs = now.duration_since(at).as_secs_f64();
+            if secs > 0.0 {
+                let page = page_size() as f64;
+                let rate = |current: u64, previous: u64, unit: f64| {
+                    current.saturating_sub(previous) as f64 * unit / secs
+                };
+                let rates = PagingRates {
+                    swap_in: rate(counters.swap_in, last.swap_in, page),
+                    swap_out: rate(counters.swap_out, last.swap_out, page),
+                    page_in: rate(counters.page_in, last.page_in, 1024.0),
+                    page_out: rate(counters.page_out, last.page_out, 1024.0),
+                };
+                self.paging_history.push(&rates);
+                self.paging = Some(rates);
+            }
+        }
+        self.last_vmstat = Some((counters, now));
+    }
+
+    /// Take readings collected elsewhere (e.g. a mirrored instance) instead of sampling
+    pub fn apply_snapshot(&mut self, snapshot: &MemorySnapshot) {
+        self.total_memory = snapshot.total_memory;
+        self.used_memory = snapshot.used_memory;
+        self.total_swap = snapshot.total_swap;
+        self.used_swap = snapshot.used_swap;
+
+        self.memory_history.push_back(self.current_memory_percent());
+        self.swap_history.push_back(self.current_swap_percent());
+        if self.memory_history.len() > 60 {
+            self.memory_history.pop_front();
+        }
+        if self.swap_history.len() > 60 {
+            self.swap_history.pop_front();
+        }
+    }
+
     pub fn _memory_graph_data(&self) -> Vec<(f64, f64)> {
         self.memory_history
             .iter()
@@ -92,20 +282,20 @@ impl MemoryInfo {
         let available = self.system.available_memory();
         let used = self.total_memory.saturating_sub(available);
         format!(
-            "Memory: {:.1}% ({:.1}GB / {:.1}GB)",
+            "Memory: {:.1}% ({} / {})",
             (used as f32 / self.total_memory as f32) * 100.0,
-            bytes_to_gb(used),
-            bytes_to_gb(self.total_memory)
+            units::format_size(used),
+            units::format_size(self.total_memory)
         )
     }
 
     pub fn swap_usage_text(&self) -> String {
         if self.total_swap > 0 {
             format!(
-                "Swap: {:.1}% ({:.1}GB / {:.1}GB)",
+                "Swap: {:.1}% ({} / {})",
                 (self.used_swap as f32 / self.total_swap as f32) * 100.0,
-                bytes_to_gb(self.used_swap),
-                bytes_to_gb(self.total_swap)
+                units::format_size(self.used_swap),
+                units::format_size(self.total_swap)
             )
         } else {
             "Swap: Not Available".to_string()
@@ -125,6 +315,133 @@ impl MemoryInfo {
     }
 }
 
-fn bytes_to_gb(bytes: u64) -> f32 {
-    bytes as f32 / 1024.0 / 1024.0 / 1024.0
+/// Buddy allocator order of a 2 MiB block with 4 KiB pages, what a huge page needs
+pub const HUGE_PAGE_ORDER: usize = 9;
+
+/// Static huge pages from /proc/meminfo
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HugePages {
+    pub total: u64,
+    pub free: u64,
+    /// Promised to a mapping but not faulted in yet
+    pub reserved: u64,
+    /// Allocated above `total` through overcommit
+    pub surplus: u64,
+    pub page_size: u64,
+    /// Memory in transparent huge pages backing anonymous mappings
+    pub anon_transparent: u64,
+}
+
+/// Free blocks per order of one zone from /proc/buddyinfo
+#[derive(Debug, Clone)]
+pub struct BuddyZone {
+    pub node: u32,
+    pub zone: String,
+    /// Free blocks of 2^order pages, index is the order
+    pub free_blocks: Vec<u64>,
+}
+
+impl BuddyZone {
+    /// Free pages in the zone
+    pub fn free_pages(&self) -> u64 {
+        self.free_blocks
+            .iter()
+            .enumerate()
+            .map(|(order, &count)| count << order)
+            .sum()
+    }
+
+    /// Share of the free memory in blocks of at least `order`. Low values mean
+    /// the memory is free but too fragmented for huge pages or large allocations.
+    pub fn free_at_least(&self, order: usize) -> f64 {
+        let large: u64 = self
+            .free_blocks
+            .iter()
+            .enumerate()
+            .skip(order)
+            .map(|(order, &count)| count << order)
+            .sum();
+        large as f64 / self.free_pages().max(1) as f64
+    }
+}
+
+/// Hugepage and fragmentation details for the expanded memory view, Linux only
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDetails {
+    pub huge_pages: Option<HugePages>,
+    /// e.g. "madvise", the selected mode of transparent huge pages
+    pub transparent_mode: Option<String>,
+    pub zones: Vec<BuddyZone>,
+    pub page_size: u64,
+}
+
+impl MemoryDetails {
+    /// Read on demand, only while the view is open
+    pub fn read() -> Self {
+        Self {
+            huge_pages: read_huge_pages(),
+            transparent_mode: fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+                .ok()
+                .and_then(|modes| {
+                    let start = modes.find('[')? + 1;
+                    let end = modes[start..].find(']')? + start;
+                    Some(modes[start..end].to_string())
+                }),
+            zones: fs::read_to_string("/proc/buddyinfo")
+                .map(|contents| parse_buddyinfo(&contents))
+                .unwrap_or_default(),
+            page_size: page_size(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(4096) as u64
+}
+
+#[cfg(not(unix))]
+fn page_size() -> u64 {
+    4096
+}
+
+fn read_huge_pages() -> Option<HugePages> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| -> Option<u64> {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+    Some(HugePages {
+        total: field("HugePages_Total")?,
+        free: field("HugePages_Free")?,
+        reserved: field("HugePages_Rsvd").unwrap_or(0),
+        surplus: field("HugePages_Surp").unwrap_or(0),
+        page_size: field("Hugepagesize").unwrap_or(2048) * 1024,
+        anon_transparent: field("AnonHugePages").unwrap_or(0) * 1024,
+    })
+}
+
+/// "Node 0, zone   Normal   3234   1299 ..." -> one BuddyZone per line
+pub fn parse_buddyinfo(contents: &str) -> Vec<BuddyZone> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (node, rest) = line.strip_prefix("Node ")?.split_once(',')?;
+            let mut fields = rest.split_whitespace();
+            if fields.next()? != "zone" {
+                return None;
+            }
+            Some(BuddyZone {
+                node: node.trim().parse().ok()?,
+                zone: fields.next()?.to_string(),
+                free_blocks: fields.filter_map(|count| count.parse().ok()).collect(),
+            })
+        })
+        .collect()
 }
@@ -0,0 +1,107 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+
+use crate::snapshot::Snapshot;
+use crate::tui::AppState;
+
+/// How often connected mirrors get a fresh snapshot
+const BROADCAST_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Per-user socket the primary instance listens on, private to the user
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => Path::new(&dir).join("m-as.sock"),
+        None => {
+            // SAFETY: getuid can't fail
+            let uid = unsafe { libc::getuid() };
+            std::env::temp_dir().join(format!("m-as-{}.sock", uid))
+        }
+    }
+}
+
+/// Listen for mirrors and stream them one JSON snapshot per line.
+///
+/// Returns false without serving when another primary already owns the socket,
+/// the TUI works fine without it.
+pub fn serve(state: Arc<Mutex<AppState>>, path: &Path) -> bool {
+    // A socket file nobody answers on is left over from a crashed instance
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return false;
+        }
+        let _ = fs::remove_file(path);
+    }
+    let Ok(listener) = UnixListener::bind(path) else {
+        return false;
+    };
+    let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+
+    let clients: Arc<Mutex<Vec<UnixStream>>> = Arc::new(Mutex::new(Vec::new()));
+    let accepted = Arc::clone(&clients);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            accepted.lock().unwrap().push(stream);
+        }
+    });
+
+    thread::spawn(move || loop {
+        thread::sleep(BROADCAST_INTERVAL);
+        let mut clients = clients.lock().unwrap();
+        if clients.is_empty() {
+            continue;
+        }
+
+        let line = {
+            let state = state.lock().unwrap();
+            let snapshot =
+                Snapshot::capture(&state.cpu_info, &state.memory_info, &state.all_processes);
+            match serde_json::to_string(&snapshot) {
+                Ok(line) => line,
+                Err(_) => continue,
+            }
+        };
+
+        // Mirrors that went away are dropped on the first failed write
+        clients.retain_mut(|client| writeln!(client, "{}", line).is_ok());
+    });
+    true
+}
+
+/// Remove the socket on exit so the next primary doesn't have to probe it
+pub fn cleanup(path: &Path) {
+    let _ = fs::remove_file(path);
+}
+
+/// Render another instance's data: read its snapshots and feed them into our state
+pub fn attach(state: Arc<Mutex<AppState>>, path: &Path) -> Result<()> {
+    let stream = UnixStream::connect(path).wrap_err_with(|| {
+        format!(
+            "no running m-as instance to mirror at {} (start one without --mirror first)",
+            path.display()
+        )
+    })?;
+
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Ok(snapshot) = serde_json::from_str::<Snapshot>(&line) {
+                state.lock().unwrap().apply_snapshot(&snapshot);
+            }
+        }
+        state.lock().unwrap().status_message =
+            Some("Mirrored instance went away, data is frozen".to_string());
+        state.lock().unwrap().dirty = true;
+    });
+    Ok(())
+}
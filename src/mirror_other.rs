@@ -0,0 +1,29 @@
+//! Mirroring goes through a Unix socket. Elsewhere there is no socket to serve
+//! on, and `--mirror` says so instead of failing to connect.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use color_eyre::Result;
+
+use crate::tui::AppState;
+
+pub fn default_socket_path() -> PathBuf {
+    std::env::temp_dir().join("m-as.sock")
+}
+
+/// Never serves, the TUI works fine without it
+pub fn serve(_state: Arc<Mutex<AppState>>, _path: &Path) -> bool {
+    false
+}
+
+pub fn cleanup(_path: &Path) {}
+
+pub fn attach(_state: Arc<Mutex<AppState>>, _path: &Path) -> Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--mirror needs Unix sockets, which this platform doesn't have",
+    )
+    .into())
+}
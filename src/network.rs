@@ -1,19 +1,322 @@
 use std::collections::VecDeque;
-use sysinfo::System;
+use std::fs;
+use std::net::IpAddr;
+use std::process::Command;
+use std::time::{Duration, Instant};
 
+use sysinfo::Networks;
+
+use crate::sampler::Sampler;
+use crate::snapshot::NetworkSnapshot;
+
+#[allow(dead_code)]
+pub fn main() {
+    let mut monitor = NetworkMonitor::new();
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        monitor.update();
+        println!("rx {} B/s tx {} B/s", monitor.rx_rate, monitor.tx_rate);
+    }
+}
+
+/// SSID and bitrate come from `iw`, which is a process spawn, so not every update
+const WIRELESS_LINK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Signal of a wireless interface, so bad throughput can be matched with bad reception
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wireless {
+    pub interface: String,
+    /// Signal level in dBm, e.g. -56
+    pub signal_dbm: i32,
+    /// Link quality as reported by the driver, usually out of 70
+    pub link_quality: u32,
+    pub ssid: Option<String>,
+    pub bitrate_mbps: Option<f64>,
+}
+
+impl Wireless {
+    /// Rough 0-100% from the signal level: -50 dBm or better is 100%, -100 dBm is 0%
+    pub fn signal_percent(&self) -> u32 {
+        ((self.signal_dbm + 100) * 2).clamp(0, 100) as u32
+    }
+}
+
+/// Throughput summed over every interface
 #[derive(Debug)]
 pub struct NetworkMonitor {
-    system: System,
-    rx_history: VecDeque<u64>,
-    tx_history: VecDeque<u64>,
+    networks: Networks,
+    pub rx_history: VecDeque<u64>,
+    pub tx_history: VecDeque<u64>,
     history_capacity: usize,
+    /// Bytes per second since the previous update
+    pub rx_rate: u64,
+    pub tx_rate: u64,
     last_rx: u64,
     last_tx: u64,
-    max_bandwidth: u64,
+    last_update: Instant,
+    /// Highest rate seen in either direction, to scale charts
+    pub max_bandwidth: u64,
+    /// Wireless interfaces, empty on machines without any
+    pub wireless: Vec<Wireless>,
+    last_link_update: Option<Instant>,
 }
 
 impl NetworkMonitor {
     pub fn new() -> Self {
-        todo!()
+        let networks = Networks::new_with_refreshed_list();
+        let (last_rx, last_tx) = totals(&networks);
+        Self {
+            networks,
+            rx_history: VecDeque::with_capacity(60),
+            tx_history: VecDeque::with_capacity(60),
+            history_capacity: 60,
+            rx_rate: 0,
+            tx_rate: 0,
+            last_rx,
+            last_tx,
+            last_update: Instant::now(),
+            max_bandwidth: 0,
+            wireless: Vec::new(),
+            last_link_update: None,
+        }
     }
+
+    pub fn update(&mut self) {
+        // Also picks up interfaces that appeared since the last update
+        self.networks.refresh(true);
+
+        let now = Instant::now();
+        let elapsed = now
+            .duration_since(self.last_update)
+            .as_secs_f64()
+            .max(0.001);
+        let (rx, tx) = totals(&self.networks);
+
+        // Counters go backwards when an interface disappears
+        self.rx_rate = (rx.saturating_sub(self.last_rx) as f64 / elapsed) as u64;
+        self.tx_rate = (tx.saturating_sub(self.last_tx) as f64 / elapsed) as u64;
+        self.max_bandwidth = self.max_bandwidth.max(self.rx_rate).max(self.tx_rate);
+
+        for (history, rate) in [
+            (&mut self.rx_history, self.rx_rate),
+            (&mut self.tx_history, self.tx_rate),
+        ] {
+            history.push_back(rate);
+            if history.len() > self.history_capacity {
+                history.pop_front();
+            }
+        }
+
+        self.last_rx = rx;
+        self.last_tx = tx;
+        self.last_update = now;
+        self.update_wireless(now);
+    }
+
+    fn update_wireless(&mut self, now: Instant) {
+        let refresh_link = self
+            .last_link_update
+            .is_none_or(|last| now.duration_since(last) >= WIRELESS_LINK_INTERVAL);
+        let previous = std::mem::take(&mut self.wireless);
+        self.wireless = fs::read_to_string("/proc/net/wireless")
+            .map(|contents| parse_proc_wireless(&contents))
+            .unwrap_or_default();
+
+        for wireless in &mut self.wireless {
+            let link = if refresh_link {
+                iw_link(&wireless.interface)
+            } else {
+                previous
+                    .iter()
+                    .find(|p| p.interface == wireless.interface)
+                    .map(|p| (p.ssid.clone(), p.bitrate_mbps))
+            };
+            if let Some((ssid, bitrate)) = link {
+                wireless.ssid = ssid;
+                wireless.bitrate_mbps = bitrate;
+            }
+        }
+        if refresh_link {
+            self.last_link_update = Some(now);
+        }
+    }
+}
+
+impl Default for NetworkMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for NetworkMonitor {
+    type Snapshot = NetworkSnapshot;
+
+    fn new() -> Self {
+        NetworkMonitor::new()
+    }
+
+    fn update(&mut self) {
+        NetworkMonitor::update(self);
+    }
+
+    fn snapshot(&self) -> NetworkSnapshot {
+        NetworkSnapshot {
+            rx_rate: self.rx_rate,
+            tx_rate: self.tx_rate,
+        }
+    }
+}
+
+/// Interfaces in /proc/net/wireless, without SSID and bitrate:
+///
+/// ```text
+/// Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+///  face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+/// wlp3s0: 0000   54.  -56.  -256        0      0      0      0      0        0
+/// ```
+pub fn parse_proc_wireless(contents: &str) -> Vec<Wireless> {
+    contents
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (interface, rest) = line.split_once(':')?;
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            // Values carry a trailing '.' when they were updated since the last read
+            let number = |i: usize| fields.get(i)?.trim_end_matches('.').parse::<f64>().ok();
+            Some(Wireless {
+                interface: interface.trim().to_string(),
+                link_quality: number(1)? as u32,
+                signal_dbm: number(2)? as i32,
+                ssid: None,
+                bitrate_mbps: None,
+            })
+        })
+        .collect()
+}
+
+/// (SSID, tx bitrate in Mbit/s) from `iw dev <interface> link`, None when iw isn't there
+fn iw_link(interface: &str) -> Option<(Option<String>, Option<f64>)> {
+    let output = Command::new("iw")
+        .args(["dev", interface, "link"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| parse_iw_link(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Picks "SSID: HomeNet" and "tx bitrate: 866.7 MBit/s ..." out of `iw dev <if> link`
+pub fn parse_iw_link(output: &str) -> (Option<String>, Option<f64>) {
+    let mut ssid = None;
+    let mut bitrate = None;
+    for line in output.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("SSID:") {
+            ssid = Some(name.trim().to_string());
+        } else if let Some(rate) = line.strip_prefix("tx bitrate:") {
+            bitrate = rate.split_whitespace().next().and_then(|r| r.parse().ok());
+        }
+    }
+    (ssid, bitrate)
+}
+
+/// An entry of the ARP (IPv4) or neighbor discovery (IPv6) table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    pub ip: String,
+    /// None while the address is still being resolved
+    pub mac: Option<String>,
+    pub interface: String,
+    /// Kernel NUD state, e.g. "REACHABLE", "STALE" or "FAILED"
+    pub state: String,
+}
+
+/// The neighbor table from `ip -j neigh`, or IPv4 only from /proc/net/arp where
+/// iproute2 isn't installed. Sorted by interface, then address.
+pub fn read_neighbors() -> Vec<Neighbor> {
+    let mut neighbors = Command::new("ip")
+        .args(["-j", "neigh"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_ip_neigh(&String::from_utf8_lossy(&output.stdout)))
+        .or_else(|| {
+            fs::read_to_string("/proc/net/arp")
+                .ok()
+                .map(|contents| parse_proc_arp(&contents))
+        })
+        .unwrap_or_default();
+    neighbors.sort_by_key(|n| (n.interface.clone(), n.ip.parse::<IpAddr>().ok()));
+    neighbors
+}
+
+/// `[{"dst":"192.168.1.1","dev":"wlp3s0","lladdr":"aa:bb:cc:dd:ee:ff","state":["REACHABLE"]}]`
+pub fn parse_ip_neigh(json: &str) -> Option<Vec<Neighbor>> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json).ok()?;
+    Some(
+        entries
+            .iter()
+            .filter_map(|entry| {
+                Some(Neighbor {
+                    ip: entry["dst"].as_str()?.to_string(),
+                    mac: entry["lladdr"].as_str().map(str::to_string),
+                    interface: entry["dev"].as_str()?.to_string(),
+                    state: entry["state"]
+                        .as_array()
+                        .map(|states| {
+                            states
+                                .iter()
+                                .filter_map(|state| state.as_str())
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        })
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// /proc/net/arp only knows whether an entry is complete, not how fresh it is:
+///
+/// ```text
+/// IP address       HW type     Flags       HW address            Mask     Device
+/// 192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:ff     *        wlp3s0
+/// ```
+pub fn parse_proc_arp(contents: &str) -> Vec<Neighbor> {
+    // ATF_COM and ATF_PERM from linux/if_arp.h
+    const COMPLETE: u32 = 0x2;
+    const PERMANENT: u32 = 0x4;
+
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(2)?.trim_start_matches("0x"), 16).ok()?;
+            let state = if flags & PERMANENT != 0 {
+                "PERMANENT"
+            } else if flags & COMPLETE != 0 {
+                "COMPLETE"
+            } else {
+                "INCOMPLETE"
+            };
+            Some(Neighbor {
+                ip: fields.first()?.to_string(),
+                mac: fields
+                    .get(3)
+                    .filter(|_| flags & COMPLETE != 0)
+                    .map(|mac| mac.to_string()),
+                interface: fields.get(5)?.to_string(),
+                state: state.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Cumulative (received, transmitted) bytes over all interfaces
+fn totals(networks: &Networks) -> (u64, u64) {
+    networks.values().fold((0, 0), |(rx, tx), data| {
+        (rx + data.total_received(), tx + data.total_transmitted())
+    })
 }
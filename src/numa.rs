@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use sysinfo::Pid;
+
+use crate::process::Process;
+
+const NODE_ROOT: &str = "/sys/devices/system/node";
+/// Only the biggest processes are checked, numa_maps walks every mapping
+const HEAVY_PROCESS_COUNT: usize = 15;
+/// A process counts as split when less than this share of its pages is on its main node
+const SPLIT_THRESHOLD_PERCENT: f64 = 90.0;
+// numa_maps reports pages, without a kernelpagesize_kB field they're base pages
+const DEFAULT_PAGE_KB: u64 = 4;
+
+#[allow(dead_code)]
+pub fn main() {
+    let mut processes = crate::process::get_all_processes();
+    crate::process::sort_by_memory(&mut processes);
+    let mut numa_info = NumaInfo::new();
+    numa_info.update(&[], &processes);
+
+    for node in &numa_info.nodes {
+        println!(
+            "node{} cpus {:?} memory {}/{} MB",
+            node.id,
+            node.cpus,
+            node.used_memory() / 1024 / 1024,
+            node.total_memory / 1024 / 1024
+        );
+    }
+    for process in &numa_info.split_processes {
+        println!(
+            "{} ({}) {:?}",
+            process.name, process.pid, process.memory_per_node
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+    /// Bytes
+    pub total_memory: u64,
+    pub free_memory: u64,
+    /// Average usage of the node's CPUs
+    pub cpu_usage: f32,
+}
+
+impl NumaNode {
+    pub fn used_memory(&self) -> u64 {
+        self.total_memory.saturating_sub(self.free_memory)
+    }
+
+    pub fn memory_percent(&self) -> f32 {
+        if self.total_memory == 0 {
+            return 0.0;
+        }
+        self.used_memory() as f32 / self.total_memory as f32 * 100.0
+    }
+}
+
+/// A heavy process whose memory isn't on a single node
+#[derive(Debug, Clone)]
+pub struct SplitProcess {
+    pub pid: Pid,
+    pub name: String,
+    /// Bytes resident on each node, by node id
+    pub memory_per_node: BTreeMap<usize, u64>,
+}
+
+impl SplitProcess {
+    /// Share of the memory on the node holding most of it
+    pub fn locality_percent(&self) -> f64 {
+        let total: u64 = self.memory_per_node.values().sum();
+        let main = self.memory_per_node.values().max().copied().unwrap_or(0);
+        if total == 0 {
+            return 100.0;
+        }
+        main as f64 / total as f64 * 100.0
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NumaInfo {
+    /// Empty on non-NUMA kernels and other platforms
+    pub nodes: Vec<NumaNode>,
+    pub split_processes: Vec<SplitProcess>,
+}
+
+impl NumaInfo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `core_usage` is indexed by CPU number, `processes` should be sorted biggest first
+    pub fn update(&mut self, core_usage: &[f32], processes: &[Process]) {
+        self.update_nodes(core_usage);
+        self.update_split_processes(processes);
+    }
+
+    /// Per-node CPU and free memory only, cheap enough for the overview
+    pub fn update_nodes(&mut self, core_usage: &[f32]) {
+        self.nodes = read_nodes();
+        for node in &mut self.nodes {
+            let usages: Vec<f32> = node
+                .cpus
+                .iter()
+                .filter_map(|cpu| core_usage.get(*cpu).copied())
+                .collect();
+            node.cpu_usage = if usages.is_empty() {
+                0.0
+            } else {
+                usages.iter().sum::<f32>() / usages.len() as f32
+            };
+        }
+    }
+
+    fn update_split_processes(&mut self, processes: &[Process]) {
+        // With a single node nothing can be split
+        self.split_processes = if self.nodes.len() < 2 {
+            Vec::new()
+        } else {
+            processes
+                .iter()
+                .take(HEAVY_PROCESS_COUNT)
+                .filter_map(|process| {
+                    let split = SplitProcess {
+                        pid: process.pid,
+                        name: process.name.clone(),
+                        memory_per_node: read_numa_maps(process.pid)?,
+                    };
+                    (split.locality_percent() < SPLIT_THRESHOLD_PERCENT).then_some(split)
+                })
+                .collect()
+        };
+    }
+}
+
+fn read_nodes() -> Vec<NumaNode> {
+    let Ok(entries) = fs::read_dir(NODE_ROOT) else {
+        return Vec::new();
+    };
+
+    let mut nodes: Vec<NumaNode> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let id = name.to_str()?.strip_prefix("node")?.parse().ok()?;
+            let dir = entry.path();
+            let cpus = fs::read_to_string(dir.join("cpulist"))
+                .map(|list| parse_cpu_list(list.trim()))
+                .unwrap_or_default();
+            let meminfo = fs::read_to_string(dir.join("meminfo")).unwrap_or_default();
+            Some(NumaNode {
+                id,
+                cpus,
+                total_memory: meminfo_field(&meminfo, "MemTotal:").unwrap_or(0),
+                free_memory: meminfo_field(&meminfo, "MemFree:").unwrap_or(0),
+                cpu_usage: 0.0,
+            })
+        })
+        .collect();
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+/// "0-3,8-11" -> [0, 1, 2, 3, 8, 9, 10, 11]
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.split(',')
+        .filter_map(|part| {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            Some(start.trim().parse::<usize>().ok()?..=end.trim().parse::<usize>().ok()?)
+        })
+        .flatten()
+        .collect()
+}
+
+/// [0, 1, 2, 3, 8] -> "0-3,8", the format sysfs uses
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// "Node 0 MemTotal:  6127352 kB" -> bytes
+fn meminfo_field(meminfo: &str, field: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let mut parts = line.split_whitespace().skip(2);
+        if parts.next()? != field {
+            return None;
+        }
+        parts.next()?.parse::<u64>().ok().map(|kb| kb * 1024)
+    })
+}
+
+/// Resident bytes per node from /proc/<pid>/numa_maps, None when it isn't readable
+fn read_numa_maps(pid: Pid) -> Option<BTreeMap<usize, u64>> {
+    let contents = fs::read_to_string(format!("/proc/{}/numa_maps", pid)).ok()?;
+    let mut per_node = BTreeMap::new();
+
+    for line in contents.lines() {
+        let page_kb = line
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix("kernelpagesize_kB="))
+            .and_then(|kb| kb.parse().ok())
+            .unwrap_or(DEFAULT_PAGE_KB);
+        // e.g. "N0=120 N1=8"
+        for field in line.split_whitespace() {
+            let Some((node, pages)) = field.strip_prefix('N').and_then(|f| f.split_once('='))
+            else {
+                continue;
+            };
+            if let (Ok(node), Ok(pages)) = (node.parse::<usize>(), pages.parse::<u64>()) {
+                *per_node.entry(node).or_insert(0) += pages * page_kb * 1024;
+            }
+        }
+    }
+    Some(per_node)
+}
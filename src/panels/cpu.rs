@@ -0,0 +1,494 @@
+//! CPU usage: per-state breakdown, the core list and the usage chart or heatmap.
+
+use std::collections::VecDeque;
+
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, Paragraph};
+use ratatui::Frame;
+
+use super::Panel;
+use crate::cpu::CpuInfo;
+use crate::cpufreq;
+use crate::history::CHART_PRESETS;
+use crate::numa::NumaNode;
+use crate::tui::{accessible, chart_points, freshness_title, render_collecting, AppState};
+
+const CORE_COLORS: &[Color] = &[
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+];
+
+// (core name, data points, line color)
+type CoreSeries = (String, Vec<(f64, f64)>, Color);
+
+pub struct CpuPanel;
+
+impl Panel for CpuPanel {
+    fn title(&self, state: &AppState) -> String {
+        let policies = &state.cpu_info.policies;
+        let governor = match cpufreq::common_governor(policies) {
+            Some(governor) => governor,
+            None if policies.is_empty() => "n/a",
+            None => "mixed",
+        };
+        format!("CPU Usage | governor: {} ", governor)
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (30, 5)
+    }
+
+    fn render(&self, frame: &mut Frame, state: &AppState, area: Rect) {
+        let cpu_info = &state.cpu_info;
+        let heatmap = state.cpu_heatmap;
+        let cpu_block = Block::default()
+            .title(self.title(state))
+            .title(freshness_title(state, "cpu"))
+            .title_bottom(if heatmap {
+                " f: governor | o: chart options | h: line chart "
+            } else {
+                " f: governor | o: chart options | h: heatmap | S: smoothing | C: colors "
+            })
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::LightCyan))
+            .style(Style::default());
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(cpu_block.inner(area));
+        if !state.freshness.collected("cpu") {
+            render_collecting(frame, sections[1]);
+            frame.render_widget(cpu_block, area);
+            return;
+        }
+        render_cpu_breakdown(frame, cpu_info, sections[0]);
+
+        if heatmap {
+            render_cpu_heatmap(frame, cpu_info, sections[1]);
+            frame.render_widget(cpu_block, area);
+            return;
+        }
+
+        let cpu_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(20), Constraint::Percentage(80)])
+            .split(sections[1]);
+
+        render_cpu_cores_list(
+            frame,
+            cpu_info,
+            &state.numa_info.nodes,
+            state.core_colors,
+            cpu_layout[0],
+        );
+        render_cpu_graphs(frame, cpu_info, state.smoothing(), cpu_layout[1]);
+
+        frame.render_widget(cpu_block, area);
+    }
+
+    fn handle_key(&self, state: &mut AppState, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('f') => state.open_governor_menu(),
+            KeyCode::Char('h') => state.cpu_heatmap = !state.cpu_heatmap,
+            KeyCode::Char('C') => state.core_colors = !state.core_colors,
+            KeyCode::Char('o') => {
+                let current = CHART_PRESETS
+                    .iter()
+                    .position(|preset| *preset == state.cpu_info.chart_config)
+                    .unwrap_or(0);
+                state.chart_options = Some(current);
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Total usage and a bar stacking the time spent per CPU state, idle is the unfilled rest
+fn render_cpu_breakdown(frame: &mut Frame, cpu_info: &CpuInfo, area: Rect) {
+    let mut spans = vec![Span::styled(
+        format!(" Total {:>5.1}% ", cpu_info.global_usage),
+        Style::default()
+            .fg(Color::LightCyan)
+            .add_modifier(Modifier::BOLD),
+    )];
+
+    let Some(breakdown) = cpu_info.breakdown else {
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+        return;
+    };
+
+    let parts = breakdown.busy_parts();
+    let colors = [
+        Color::Green,
+        Color::Blue,
+        Color::Red,
+        Color::Magenta,
+        Color::Yellow,
+        Color::LightRed,
+    ];
+    let legend: Vec<String> = parts
+        .iter()
+        .map(|(label, percent)| format!("{} {:.1}", label, percent))
+        .collect();
+    let legend_width = legend.iter().map(|l| l.len() + 1).sum::<usize>();
+    let bar_width = (area.width as usize)
+        .saturating_sub(legend_width + 16)
+        .min(60);
+
+    let mut filled = 0;
+    for ((_, percent), color) in parts.iter().zip(colors) {
+        let width = ((percent / 100.0 * bar_width as f32).round() as usize).min(bar_width - filled);
+        spans.push(Span::styled("█".repeat(width), Style::default().fg(color)));
+        filled += width;
+    }
+    spans.push(Span::styled(
+        "░".repeat(bar_width - filled),
+        Style::default().fg(Color::DarkGray),
+    ));
+    spans.push(Span::raw(" "));
+    for ((text, (_, percent)), color) in legend.into_iter().zip(parts).zip(colors) {
+        // Steal is the one VM users look for, make it stand out once it's there
+        let style = if text.starts_with("st") && percent >= 1.0 {
+            Style::default().fg(color).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        spans.push(Span::styled(format!("{} ", text), style));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// One row per core and one column per time bucket, newest on the right.
+/// When there are more cores than rows two cores share a row using half blocks.
+fn render_cpu_heatmap(frame: &mut Frame, cpu_info: &CpuInfo, area: Rect) {
+    let label_width = 7;
+    let columns = area.width.saturating_sub(label_width) as usize;
+    let rows = area.height.saturating_sub(1) as usize;
+    if columns == 0 || rows == 0 {
+        return;
+    }
+    let cores_per_row = if cpu_info.cores.len() > rows { 2 } else { 1 };
+
+    let buckets: Vec<Vec<f32>> = cpu_info
+        .cores
+        .iter()
+        .map(|core| bucket_history(&core.history, columns))
+        .collect();
+
+    let mut lines: Vec<Line> = buckets
+        .chunks(cores_per_row)
+        .zip(cpu_info.cores.chunks(cores_per_row))
+        .take(rows)
+        .map(|(row, cores)| {
+            let label = match cores {
+                [core] => core.name.clone(),
+                [first, second] => format!(
+                    "{}-{}",
+                    first.name.trim_start_matches("Core "),
+                    second.name.trim_start_matches("Core ")
+                ),
+                _ => String::new(),
+            };
+            let mut spans = vec![Span::styled(
+                format!("{:>6} ", label),
+                Style::default().fg(Color::Gray),
+            )];
+            // Right align so the newest sample is always in the last column
+            spans.push(Span::raw(" ".repeat(columns - row[0].len())));
+            spans.extend((0..row[0].len()).map(|i| {
+                match row {
+                    [upper, lower] => Span::styled(
+                        "▀",
+                        Style::default()
+                            .fg(heatmap_color(upper[i]))
+                            .bg(heatmap_color(lower.get(i).copied().unwrap_or(0.0))),
+                    ),
+                    _ => heatmap_cell(row[0][i]),
+                }
+            }));
+            Line::from(spans)
+        })
+        .collect();
+
+    let hidden = cpu_info.cores.len().saturating_sub(rows * cores_per_row);
+    let mut legend = vec![Span::styled(
+        format!("{:>6} ", ""),
+        Style::default().fg(Color::Gray),
+    )];
+    for percent in [0.0, 25.0, 50.0, 75.0, 100.0] {
+        legend.push(heatmap_cell(percent));
+        legend.push(Span::styled(
+            format!("{:.0}% ", percent),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    legend.push(Span::styled(
+        format!("| {}", cpu_info.chart_config.label()),
+        Style::default().fg(Color::Gray),
+    ));
+    if hidden > 0 {
+        legend.push(Span::styled(
+            format!(" | {} cores not shown", hidden),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    lines.push(Line::from(legend));
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// Average the newest samples into at most `columns` buckets, oldest first
+fn bucket_history(history: &VecDeque<f32>, columns: usize) -> Vec<f32> {
+    let size = history.len().div_ceil(columns).max(1);
+    let mut buckets: Vec<f32> = history
+        .iter()
+        .rev()
+        .copied()
+        .collect::<Vec<_>>()
+        .chunks(size)
+        .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+        .collect();
+    buckets.reverse();
+    buckets
+}
+
+fn heatmap_cell(percent: f32) -> Span<'static> {
+    // Shades carry the level too, so the map still reads without colors
+    let shade = match percent {
+        p if p < 10.0 => "░",
+        p if p < 40.0 => "▒",
+        p if p < 70.0 => "▓",
+        _ => "█",
+    };
+    Span::styled(shade, Style::default().fg(heatmap_color(percent)))
+}
+
+/// Idle and light load green, moderate yellow, busy red
+fn load_color(percent: f32) -> Color {
+    let (light, moderate, busy) = if accessible() {
+        (Color::White, Color::LightYellow, Color::LightRed)
+    } else {
+        (Color::Green, Color::Yellow, Color::Red)
+    };
+    match percent {
+        p if p < 30.0 => light,
+        p if p <= 70.0 => moderate,
+        _ => busy,
+    }
+}
+
+fn heatmap_color(percent: f32) -> Color {
+    if accessible() {
+        return if percent < 70.0 {
+            Color::White
+        } else {
+            Color::LightRed
+        };
+    }
+    match percent {
+        p if p < 10.0 => Color::DarkGray,
+        p if p < 25.0 => Color::Blue,
+        p if p < 50.0 => Color::Green,
+        p if p < 75.0 => Color::Yellow,
+        p if p < 90.0 => Color::LightRed,
+        _ => Color::Red,
+    }
+}
+
+/// Cores grouped under a per-node header when there's more than one NUMA node.
+/// Names keep the color of their chart line, the percentage is colored by load
+/// unless `identity_colors` is set.
+fn render_cpu_cores_list(
+    frame: &mut Frame,
+    cpu_info: &CpuInfo,
+    numa_nodes: &[NumaNode],
+    identity_colors: bool,
+    area: Rect,
+) {
+    let mixed_governors = cpufreq::common_governor(&cpu_info.policies).is_none();
+    let core_line = |i: usize| {
+        let core = cpu_info.cores.get(i)?;
+        let color = CORE_COLORS[i % CORE_COLORS.len()];
+        let kind = core
+            .kind
+            .map_or(String::new(), |kind| format!(" {}", kind.short()));
+        // Per core only when the policies differ, the title has the common one
+        let governor = mixed_governors
+            .then(|| cpufreq::governor_of(&cpu_info.policies, i))
+            .flatten()
+            .map_or(String::new(), |g| {
+                format!(" {}", cpufreq::short_governor(g))
+            });
+        Some(Line::from(vec![
+            Span::styled(
+                format!("{:>6}: ", core.name),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(
+                    "{:>5.1}%{}",
+                    core.usage,
+                    match accessible() {
+                        // Busy cores are marked for those who can't tell the colors apart
+                        true if core.usage > 70.0 => "!",
+                        true => " ",
+                        false => "",
+                    }
+                ),
+                Style::default().fg(if identity_colors {
+                    color
+                } else {
+                    load_color(core.usage)
+                }),
+            ),
+            Span::styled(kind, Style::default().fg(Color::Gray)),
+            Span::styled(governor, Style::default().fg(Color::DarkGray)),
+        ]))
+    };
+    let cores_list: Vec<Line> = if numa_nodes.len() < 2 {
+        (0..cpu_info.cores.len()).filter_map(core_line).collect()
+    } else {
+        numa_nodes
+            .iter()
+            .flat_map(|node| {
+                let header = Line::from(Span::styled(
+                    format!("node{} {:>6.1}%", node.id, node.cpu_usage),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                ));
+                std::iter::once(header).chain(node.cpus.iter().filter_map(|&cpu| core_line(cpu)))
+            })
+            .collect()
+    };
+
+    // Vertical centering
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(cores_list.len() as u16),
+            Constraint::Min(1),
+        ])
+        .split(area);
+
+    // Horizontal centering
+    let horizontal_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(if mixed_governors { 26 } else { 20 }),
+            Constraint::Min(1),
+        ])
+        .split(vertical_layout[1]);
+
+    let list_widget = Paragraph::new(cores_list)
+        .block(Block::default())
+        .alignment(Alignment::Left);
+
+    frame.render_widget(list_widget, horizontal_layout[1]);
+}
+
+fn render_cpu_graphs(frame: &mut Frame, cpu_info: &CpuInfo, smoothing: Option<f64>, area: Rect) {
+    let graph_data: Vec<CoreSeries> = cpu_info
+        .cores
+        .iter()
+        .enumerate()
+        .map(|(i, core)| {
+            let data = chart_points(core.history.iter().map(|&y| y as f64), smoothing);
+            (core.name.clone(), data, CORE_COLORS[i % CORE_COLORS.len()])
+        })
+        .collect();
+
+    // Chart widget
+    let chart_config = cpu_info.chart_config;
+    let x_max = (chart_config.capacity() - 1) as f64;
+    let window_secs = chart_config.window.as_secs();
+
+    let chart = {
+        let y_min = 0.0;
+        let y_max = 50.0;
+        let datasets = graph_data
+            .iter()
+            .map(|(name, data, color)| {
+                Dataset::default()
+                    .name(name.as_str())
+                    .data(data)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(*color))
+                    .marker(Marker::Braille)
+            })
+            .collect();
+
+        Chart::new(datasets)
+            .block(Block::default().title(format!(
+                "CPU Usage History (0-50%, {}{}) [o: options]",
+                chart_config.label(),
+                smoothing_label(smoothing)
+            )))
+            .x_axis(
+                Axis::default()
+                    .bounds([0.0, x_max])
+                    .labels::<Vec<Span>>(vec![
+                        Span::raw("0"),
+                        Span::raw(format!("{}", window_secs / 2)),
+                        Span::raw(format!("{}", window_secs)),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([y_min, y_max])
+                    .labels::<Vec<Span>>(vec![
+                        Span::raw("0"),
+                        Span::raw(format!("{:.0}", y_max / 2.0)),
+                        Span::raw(format!("{:.0}", y_max)),
+                    ]),
+            )
+    };
+
+    // Center the chart vertically
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),     // Top padding
+            Constraint::Length(12), // Increased chart height from 10 to 12
+            Constraint::Min(1),     // Bottom padding
+        ])
+        .split(area);
+
+    // Center the chart horizontally
+    let horizontal_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(1),         // Left padding
+            Constraint::Percentage(90), // Chart width
+            Constraint::Min(1),         // Right padding
+        ])
+        .split(vertical_layout[1]);
+
+    frame.render_widget(chart, horizontal_layout[1]);
+}
+
+/// ", smoothed α=0.3" for chart titles, empty for raw data
+fn smoothing_label(smoothing: Option<f64>) -> String {
+    smoothing.map_or(String::new(), |alpha| format!(", smoothed α={}", alpha))
+}
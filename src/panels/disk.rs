@@ -0,0 +1,200 @@
+//! One line per mounted filesystem: usage, inodes, SMART health and I/O rates.
+
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Sparkline};
+use ratatui::Frame;
+
+use super::{Panel, NOT_IN_SNAPSHOTS};
+use crate::disk::Disk;
+use crate::tui::{
+    accessible, freshness_title, render_collecting, render_placeholder, tail, AppState,
+};
+use crate::units;
+
+pub struct DiskPanel;
+
+impl Panel for DiskPanel {
+    fn title(&self, state: &AppState) -> String {
+        if let Some(warning) = warning(state) {
+            return warning;
+        }
+        match state.disk_info.hidden_count() {
+            0 => " Disks ".to_string(),
+            hidden => format!(" Disks ({} hidden, m shows all) ", hidden),
+        }
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (20, 3)
+    }
+
+    fn render(&self, frame: &mut Frame, state: &AppState, area: Rect) {
+        let disk_info = &state.disk_info;
+        let border_color = if warning(state).is_some() {
+            Color::Red
+        } else {
+            Color::LightYellow
+        };
+        let block = Block::default()
+            .title(self.title(state))
+            .title(freshness_title(state, "disks"))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(border_color));
+
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+        if state.from_snapshots {
+            render_placeholder(frame, inner_area, NOT_IN_SNAPSHOTS);
+            return;
+        }
+        if !state.freshness.collected("disks") {
+            render_collecting(frame, inner_area);
+            return;
+        }
+
+        // Only make room for SMART health when there is any
+        let health_width = if disk_info.visible().any(|d| disk_health(state, d).is_some()) {
+            16
+        } else {
+            0
+        };
+
+        // One line per mounted disk: mount, usage, then read/write rates with sparklines
+        for (i, disk) in disk_info
+            .visible()
+            .take(inner_area.height as usize)
+            .enumerate()
+        {
+            let row_area = Rect::new(inner_area.x, inner_area.y + i as u16, inner_area.width, 1);
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Length(16), // Mount point
+                    Constraint::Length(14), // Usage + filesystem
+                    Constraint::Length(9),  // Inode usage
+                    Constraint::Length(health_width),
+                    Constraint::Length(14), // Read rate
+                    Constraint::Min(4),     // Read sparkline
+                    Constraint::Length(14), // Write rate
+                    Constraint::Min(4),     // Write sparkline
+                ])
+                .split(row_area);
+
+            let mount = if disk.mount_point.len() > 15 {
+                format!(
+                    "{}...",
+                    disk.mount_point.chars().take(12).collect::<String>()
+                )
+            } else {
+                disk.mount_point.clone()
+            };
+
+            frame.render_widget(
+                Paragraph::new(Span::styled(mount, Style::default().fg(Color::LightYellow))),
+                columns[0],
+            );
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    format!("{:>5.1}% {:<6.6}", disk.usage_percent(), disk.file_system),
+                    Style::default().fg(Color::Gray),
+                )),
+                columns[1],
+            );
+
+            let inode_style = if disk.inodes_near_exhaustion() {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    disk.inode_usage_percent()
+                        .map_or("I:  N/A".to_string(), |p| format!("I:{:>5.1}%", p)),
+                    inode_style,
+                )),
+                columns[2],
+            );
+
+            if let Some((summary, failing)) = disk_health(state, disk) {
+                let style = if failing {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                frame.render_widget(Paragraph::new(Span::styled(summary, style)), columns[3]);
+            }
+
+            let read_data: Vec<u64> = disk.read_history.iter().copied().collect();
+            let write_data: Vec<u64> = disk.write_history.iter().copied().collect();
+
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    format!("R {:>11}", units::format_rate(disk.read_rate)),
+                    Style::default().fg(Color::LightGreen),
+                )),
+                columns[4],
+            );
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    format!("W {:>11}", units::format_rate(disk.write_rate)),
+                    Style::default().fg(Color::LightRed),
+                )),
+                columns[6],
+            );
+
+            // Sparklines shift every second, too much churn in accessible mode
+            if accessible() {
+                continue;
+            }
+            frame.render_widget(
+                Sparkline::default()
+                    .data(tail(&read_data, columns[5].width))
+                    .style(Style::default().fg(Color::LightGreen)),
+                columns[5],
+            );
+            frame.render_widget(
+                Sparkline::default()
+                    .data(tail(&write_data, columns[7].width))
+                    .style(Style::default().fg(Color::LightRed)),
+                columns[7],
+            );
+        }
+    }
+
+    fn handle_key(&self, state: &mut AppState, code: KeyCode) -> bool {
+        if code != KeyCode::Char('m') {
+            return false;
+        }
+        state.disk_info.filter.show_all = !state.disk_info.filter.show_all;
+        true
+    }
+}
+
+/// Title for a disk that needs attention. Inode exhaustion breaks writes just
+/// like a full disk, so make it hard to miss.
+fn warning(state: &AppState) -> Option<String> {
+    let disk_info = &state.disk_info;
+    if disk_info.visible().any(|d| d.inodes_near_exhaustion()) {
+        return Some(" Disks (inodes almost exhausted!) ".to_string());
+    }
+    disk_info
+        .visible()
+        .find(|d| disk_health(state, d).is_some_and(|(_, failing)| failing))
+        .map(|disk| format!(" Disks (SMART: {} failing!) ", disk.device))
+}
+
+/// SMART summary and whether the disk is failing, None without SMART data
+#[cfg(feature = "smart")]
+fn disk_health(state: &AppState, disk: &Disk) -> Option<(String, bool)> {
+    let health = state.smart.get(&disk.device)?;
+    Some((health.summary(), health.is_failing()))
+}
+
+#[cfg(not(feature = "smart"))]
+fn disk_health(_state: &AppState, _disk: &Disk) -> Option<(String, bool)> {
+    None
+}
@@ -0,0 +1,329 @@
+//! Memory and swap gauges, their history and paging rates.
+
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Axis, Block, BorderType, Borders, Chart, Dataset, Gauge, GraphType, Paragraph, Sparkline,
+};
+use ratatui::Frame;
+
+use super::Panel;
+use crate::memory::{
+    MemoryDetails, MemoryInfo, PagingHistory, PagingRates, UsageLevel, UsageThresholds,
+};
+use crate::numa::NumaNode;
+use crate::platform::MemoryPressure;
+use crate::tui::{accessible, chart_points, freshness_title, tail, threshold_color, AppState};
+use crate::units;
+
+pub struct MemoryPanel;
+
+impl Panel for MemoryPanel {
+    fn title(&self, _state: &AppState) -> String {
+        " Memory Usage ".to_string()
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (20, 3)
+    }
+
+    fn render(&self, frame: &mut Frame, state: &AppState, area: Rect) {
+        let memory_info = &state.memory_info;
+        // Memory is refreshed along with the process list
+        let block = Block::default()
+            .title(self.title(state))
+            .title(freshness_title(state, "processes"))
+            .title_bottom(" M: hugepages and fragmentation ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Yellow));
+
+        // Gauges on the left, history chart in the middle, paging rates on the right
+        let paging_width = if memory_info.paging.is_some() { 30 } else { 0 };
+        let inner_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(1),            // Left padding
+                Constraint::Percentage(45),       // Gauges
+                Constraint::Min(1),               // History chart
+                Constraint::Length(paging_width), // Swap and page rates
+            ])
+            .split(block.inner(area));
+
+        render_memory_usage(
+            frame,
+            memory_info,
+            &state.numa_info.nodes,
+            state.memory_thresholds,
+            inner_layout[1],
+        );
+        render_memory_history(frame, memory_info, state.smoothing(), inner_layout[2]);
+        if let Some(rates) = &memory_info.paging {
+            render_paging_rates(frame, rates, &memory_info.paging_history, inner_layout[3]);
+        }
+
+        // Render the outer block
+        frame.render_widget(block, area);
+    }
+
+    fn handle_key(&self, state: &mut AppState, code: KeyCode) -> bool {
+        if code != KeyCode::Char('M') {
+            return false;
+        }
+        state.memory_details = Some(MemoryDetails::read());
+        true
+    }
+}
+
+/// The level in words for accessible mode, where colors alone don't carry it
+fn level_label(level: UsageLevel) -> &'static str {
+    match level {
+        _ if !accessible() => "",
+        UsageLevel::Normal => "",
+        UsageLevel::Warning => " high",
+        UsageLevel::Critical => " critical",
+    }
+}
+
+fn render_memory_usage(
+    frame: &mut Frame,
+    memory_info: &MemoryInfo,
+    numa_nodes: &[NumaNode],
+    thresholds: UsageThresholds,
+    area: Rect,
+) {
+    let memory_percent = memory_info.current_memory_percent();
+    let swap_percent = memory_info.current_swap_percent();
+    // A line per node below swap, only worth it with more than one and when
+    // it doesn't push the gauges out of a short panel
+    let node_lines = numa_nodes.len() as u16 + 1;
+    let node_lines = if numa_nodes.len() >= 2 && area.height >= 5 + node_lines {
+        node_lines
+    } else {
+        0
+    };
+
+    // Label, gauge, empty line between Memory and Swap
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),             // Top padding
+            Constraint::Length(1),          // Memory label
+            Constraint::Length(1),          // Memory gauge
+            Constraint::Length(1),          // Spacing
+            Constraint::Length(1),          // Swap label
+            Constraint::Length(1),          // Swap gauge
+            Constraint::Length(node_lines), // NUMA nodes
+            Constraint::Min(0),             // Bottom padding
+        ])
+        .split(area);
+
+    let mut label = vec![Span::styled(
+        "Memory",
+        Style::default()
+            .fg(Color::LightBlue)
+            .add_modifier(Modifier::BOLD),
+    )];
+    if let Some(pressure) = memory_info.pressure {
+        let color = match pressure {
+            MemoryPressure::Normal => Color::Green,
+            MemoryPressure::Warning => Color::Yellow,
+            MemoryPressure::Critical => Color::Red,
+        };
+        label.push(Span::styled(
+            format!("  pressure: {}", pressure.label()),
+            Style::default().fg(color),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(label)), vertical_layout[1]);
+    frame.render_widget(
+        Gauge::default()
+            .gauge_style(Style::default().fg(threshold_color(thresholds.level(memory_percent))))
+            .ratio((memory_percent as f64 / 100.0).clamp(0.0, 1.0))
+            .label(format!(
+                "{:.1}%{}",
+                memory_percent,
+                level_label(thresholds.level(memory_percent))
+            )),
+        vertical_layout[2],
+    );
+
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            "Swap",
+            Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+        )),
+        vertical_layout[4],
+    );
+    if memory_info.total_swap > 0 {
+        frame.render_widget(
+            Gauge::default()
+                .gauge_style(Style::default().fg(threshold_color(thresholds.level(swap_percent))))
+                .ratio((swap_percent as f64 / 100.0).clamp(0.0, 1.0))
+                .label(format!(
+                    "{:.1}%{}",
+                    swap_percent,
+                    level_label(thresholds.level(swap_percent))
+                )),
+            vertical_layout[5],
+        );
+    } else {
+        frame.render_widget(
+            Paragraph::new(Span::styled("N/A", Style::default().fg(Color::LightRed))),
+            vertical_layout[5],
+        );
+    }
+
+    if node_lines > 0 {
+        let lines: Vec<Line> = std::iter::once(Line::default())
+            .chain(numa_nodes.iter().map(|node| {
+                let percent = node.memory_percent();
+                Line::from(vec![
+                    Span::styled(
+                        format!("node{:<3}", node.id),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(
+                        format!("{:>5.1}%", percent),
+                        Style::default().fg(threshold_color(thresholds.level(percent))),
+                    ),
+                    Span::styled(
+                        format!(
+                            "  {:.1} GB free",
+                            node.free_memory as f64 / 1024.0 / 1024.0 / 1024.0
+                        ),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ])
+            }))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), vertical_layout[6]);
+    }
+}
+
+/// One row per rate with its last minute as a sparkline, swap in red once it moves
+fn render_paging_rates(
+    frame: &mut Frame,
+    rates: &PagingRates,
+    history: &PagingHistory,
+    area: Rect,
+) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    let swap_color = |rate: f64| {
+        if rate > 0.0 {
+            Color::LightRed
+        } else {
+            Color::Gray
+        }
+    };
+    for (row, label, rate, history, color) in [
+        (
+            rows[1],
+            "swap in",
+            rates.swap_in,
+            &history.swap_in,
+            swap_color(rates.swap_in),
+        ),
+        (
+            rows[2],
+            "swap out",
+            rates.swap_out,
+            &history.swap_out,
+            swap_color(rates.swap_out),
+        ),
+        (
+            rows[3],
+            "page in",
+            rates.page_in,
+            &history.page_in,
+            Color::LightBlue,
+        ),
+        (
+            rows[4],
+            "page out",
+            rates.page_out,
+            &history.page_out,
+            Color::LightBlue,
+        ),
+    ] {
+        let parts = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(20), Constraint::Min(0)])
+            .split(row);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(format!("{:<9}", label), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format!("{:>10}", units::format_rate(rate as u64)),
+                    Style::default().fg(color),
+                ),
+            ])),
+            parts[0],
+        );
+        if !accessible() {
+            let data: Vec<u64> = history.iter().copied().collect();
+            frame.render_widget(
+                Sparkline::default()
+                    .data(tail(&data, parts[1].width))
+                    .style(Style::default().fg(color)),
+                parts[1],
+            );
+        }
+    }
+}
+
+fn render_memory_history(
+    frame: &mut Frame,
+    memory_info: &MemoryInfo,
+    smoothing: Option<f64>,
+    area: Rect,
+) {
+    let memory_data = chart_points(
+        memory_info.memory_history.iter().map(|&y| y as f64),
+        smoothing,
+    );
+    let swap_data = chart_points(
+        memory_info.swap_history.iter().map(|&y| y as f64),
+        smoothing,
+    );
+
+    let datasets = vec![
+        Dataset::default()
+            .name("Memory")
+            .data(&memory_data)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::LightBlue))
+            .marker(Marker::Braille),
+        Dataset::default()
+            .name("Swap")
+            .data(&swap_data)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::LightRed))
+            .marker(Marker::Braille),
+    ];
+
+    let chart = Chart::new(datasets)
+        .x_axis(Axis::default().bounds([0.0, 59.0]))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, 100.0])
+                .labels::<Vec<Span>>(vec![Span::raw("0"), Span::raw("50"), Span::raw("100")]),
+        );
+
+    frame.render_widget(chart, area);
+}
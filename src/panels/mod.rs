@@ -0,0 +1,76 @@
+//! The boxes on the overview. Each one draws itself from the shared state and
+//! handles its own keys, so a new view is a new file here plus a place in
+//! `tui::render_overview`.
+
+mod cpu;
+mod disk;
+mod memory;
+mod network;
+mod process;
+
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+pub use cpu::CpuPanel;
+pub use disk::DiskPanel;
+pub use memory::MemoryPanel;
+pub use network::NetworkPanel;
+pub use process::ProcessPanel;
+
+use crate::tui::{AppState, Tab};
+
+pub trait Panel {
+    /// Shown in the top border
+    fn title(&self, state: &AppState) -> String;
+
+    /// (width, height) including the border, below that only the title is shown
+    fn min_size(&self) -> (u16, u16);
+
+    fn render(&self, frame: &mut Frame, state: &AppState, area: Rect);
+
+    /// Returns true when the key was used
+    fn handle_key(&self, _state: &mut AppState, _code: KeyCode) -> bool {
+        false
+    }
+}
+
+/// Disks and network while another instance collects, its snapshots don't carry them
+const NOT_IN_SNAPSHOTS: &str = "not sent by the other instance";
+
+/// In the order keys are offered to them
+pub const OVERVIEW: [&dyn Panel; 5] = [
+    &CpuPanel,
+    &ProcessPanel,
+    &MemoryPanel,
+    &DiskPanel,
+    &NetworkPanel,
+];
+
+/// Render `panel`, or just its title when `area` is smaller than it needs
+pub fn draw(frame: &mut Frame, panel: &dyn Panel, state: &AppState, area: Rect) {
+    let (width, height) = panel.min_size();
+    if area.width >= width && area.height >= height {
+        panel.render(frame, state, area);
+        return;
+    }
+    frame.render_widget(
+        Paragraph::new("too small")
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .title(panel.title(state))
+                    .borders(Borders::TOP),
+            ),
+        area,
+    );
+}
+
+/// Offer a key to each overview panel, returns true once one of them used it.
+/// Other tabs have keys of their own, the panels only get them on the overview.
+pub fn handle_key(state: &mut AppState, code: KeyCode) -> bool {
+    state.active_tab == Tab::Overview && OVERVIEW.iter().any(|panel| panel.handle_key(state, code))
+}
@@ -0,0 +1,130 @@
+//! Total receive and transmit rates as a chart, wireless links in the border.
+
+use std::collections::VecDeque;
+
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Block, BorderType, Borders, Chart, Dataset, GraphType};
+use ratatui::Frame;
+
+use super::{Panel, NOT_IN_SNAPSHOTS};
+use crate::network::Wireless;
+use crate::tui::{chart_points, freshness_title, render_collecting, render_placeholder, AppState};
+use crate::units;
+
+pub struct NetworkPanel;
+
+impl Panel for NetworkPanel {
+    fn title(&self, state: &AppState) -> String {
+        format!(
+            " Network Usage | RX {} | TX {} ",
+            units::format_rate(state.network.rx_rate),
+            units::format_rate(state.network.tx_rate)
+        )
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (20, 3)
+    }
+
+    fn render(&self, frame: &mut Frame, state: &AppState, area: Rect) {
+        let network = &state.network;
+        let smoothing = state.smoothing();
+        let block = Block::default()
+            .title(self.title(state))
+            .title(freshness_title(state, "network"))
+            .title_bottom(wireless_line(&network.wireless))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::LightBlue));
+        if state.from_snapshots {
+            render_placeholder(frame, block.inner(area), NOT_IN_SNAPSHOTS);
+            frame.render_widget(block, area);
+            return;
+        }
+        if !state.freshness.collected("network") {
+            render_collecting(frame, block.inner(area));
+            frame.render_widget(block, area);
+            return;
+        }
+
+        let units = units::Units::current();
+        // Scale to what's visible rather than the all-time peak, so a past burst doesn't flatten the chart
+        let peak = network
+            .rx_history
+            .iter()
+            .chain(&network.tx_history)
+            .copied()
+            .max()
+            .unwrap_or(0);
+        let y_max = units.rate_axis_max(peak.max(1));
+
+        let to_points = |history: &VecDeque<u64>| -> Vec<(f64, f64)> {
+            chart_points(history.iter().map(|&rate| rate as f64), smoothing)
+        };
+        let rx_data = to_points(&network.rx_history);
+        let tx_data = to_points(&network.tx_history);
+
+        let datasets = vec![
+            Dataset::default()
+                .name("RX")
+                .data(&rx_data)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::LightGreen))
+                .marker(Marker::Braille),
+            Dataset::default()
+                .name("TX")
+                .data(&tx_data)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::LightRed))
+                .marker(Marker::Braille),
+        ];
+
+        let x_max = network.rx_history.len().max(2) as f64 - 1.0;
+        let chart = Chart::new(datasets)
+            .block(block)
+            .x_axis(Axis::default().bounds([0.0, x_max]))
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, y_max as f64])
+                    .style(Style::default().fg(Color::Gray))
+                    .labels::<Vec<Span>>(vec![
+                        Span::raw(units.rate(0)),
+                        Span::raw(units.rate(y_max / 2)),
+                        Span::raw(units.rate(y_max)),
+                    ]),
+            );
+
+        frame.render_widget(chart, area);
+    }
+}
+
+/// " wlp3s0 HomeNet -56 dBm (88%) 866.7 Mbit/s " per wireless interface, signal colored by strength
+fn wireless_line(wireless: &[Wireless]) -> Line<'static> {
+    let mut spans = Vec::new();
+    for link in wireless {
+        let signal_color = match link.signal_percent() {
+            60.. => Color::LightGreen,
+            30.. => Color::Yellow,
+            _ => Color::Red,
+        };
+        spans.push(Span::raw(format!(" {}", link.interface)));
+        if let Some(ssid) = &link.ssid {
+            spans.push(Span::styled(
+                format!(" {}", ssid),
+                Style::default().fg(Color::White),
+            ));
+        }
+        spans.push(Span::styled(
+            format!(" {} dBm ({}%)", link.signal_dbm, link.signal_percent()),
+            Style::default().fg(signal_color),
+        ));
+        if let Some(bitrate) = link.bitrate_mbps {
+            spans.push(Span::raw(format!(" {} Mbit/s", bitrate)));
+        }
+        spans.push(Span::raw(" "));
+    }
+    Line::from(spans)
+}
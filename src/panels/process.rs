@@ -0,0 +1,350 @@
+//! The process table with the task counts above it.
+
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use super::Panel;
+use crate::capabilities::Capability;
+use crate::columns::Column;
+use crate::fuzzy;
+use crate::process::{StateSummary, StatusFilter};
+use crate::tui::{
+    freshness_title, make_highlight_style, make_tagged_style, mini_bar, panel_border,
+    render_collecting, visible_columns, AppState, Focus,
+};
+
+pub struct ProcessPanel;
+
+impl Panel for ProcessPanel {
+    fn title(&self, state: &AppState) -> String {
+        let mut filters = Vec::new();
+        if state.status_filter != StatusFilter::All {
+            filters.push(state.status_filter.label().to_string());
+        }
+        filters.extend(state.name_filter_label());
+        if let Some(name) = state.filter_preset_name() {
+            filters.push(format!("preset {}", name));
+        }
+        if state.job.is_some() && state.job_only {
+            filters.push("job tree".to_string());
+        }
+        let mut title = if filters.is_empty() {
+            format!(" Process Information (sort: {}) ", state.sort_label())
+        } else {
+            format!(
+                " Process Information (sort: {}) [{}: {}] ",
+                state.sort_label(),
+                filters.join(", "),
+                state.processes.len()
+            )
+        };
+        if let Some(job) = &state.job {
+            let secs = job.started.elapsed().as_secs();
+            let command = if job.command.chars().count() > 30 {
+                format!("{}...", job.command.chars().take(27).collect::<String>())
+            } else {
+                job.command.clone()
+            };
+            title.push_str(&format!(
+                "[running {} for {}:{:02}] ",
+                command,
+                secs / 60,
+                secs % 60
+            ));
+        }
+        if !state.tagged.is_empty() {
+            title.push_str(&format!("[{} tagged] ", state.tagged.len()));
+        }
+        if state.order_frozen() {
+            title.push_str("[order held, u: resume] ");
+        }
+        // Many processes make refreshing slow, say so rather than looking stuck
+        let slowdown = state.collector_budget.slowdown("processes");
+        if slowdown > 1 {
+            title.push_str(&format!(
+                "[throttled {}x: every {}ms] ",
+                slowdown,
+                state
+                    .collector_interval("processes", state.process_interval)
+                    .as_millis()
+            ));
+        }
+        if !state.capabilities.allows(Capability::OtherProcesses) {
+            title.push_str(&format!(
+                "[own processes only: {}] ",
+                Capability::OtherProcesses.hint()
+            ));
+        }
+        title
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (40, 5)
+    }
+
+    fn render(&self, frame: &mut Frame, state: &AppState, area: Rect) {
+        let processes = &state.processes;
+        let selected_process = state.selected_process;
+        let scroll_offset = state.scroll_offset;
+
+        let footer = match &state.status_message {
+            Some(message) => format!(" {} ", message),
+            None => {
+                let actions = if state.read_only {
+                    "read-only"
+                } else {
+                    "G: kill group | T: kill tree | Space: tag | K: kill | +/-: nice"
+                };
+                let presets = if state.filter_presets.is_empty() {
+                    ""
+                } else {
+                    "p: preset | "
+                };
+                let job = if state.job.is_some() {
+                    "J: job/all | "
+                } else {
+                    ""
+                };
+                let export = if state.read_only {
+                    ""
+                } else {
+                    "w/W: export | "
+                };
+                format!(
+                    " Enter: details | :: command | s: sort | R: running | D: D state | Z: zombies | {}{}g: groups | F: fds | E: faults/csw | c: pods | X: cwd/exe | d: deltas | {}Tab: focus | {} ",
+                    presets, job, export, actions
+                )
+            }
+        };
+
+        let block = panel_border(
+            Block::default()
+                .title(self.title(state))
+                .title(freshness_title(state, "processes"))
+                .title_bottom(footer)
+                .borders(Borders::ALL),
+            Color::LightMagenta,
+            state.focused_panel() == Some(Focus::Processes),
+        );
+
+        // Task counts take the first line inside the border, the table the rest
+        let inner_area = block.inner(area);
+        if !state.freshness.collected("processes") {
+            frame.render_widget(block, area);
+            render_collecting(frame, inner_area);
+            return;
+        }
+        let [summary_area, table_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner_area);
+        let max_items = (table_area.height as usize).saturating_sub(2); // Account for the header
+        let scroll_offset = scroll_offset.min(processes.len().saturating_sub(max_items));
+        let mut adjusted_scroll = scroll_offset;
+
+        if selected_process < adjusted_scroll {
+            adjusted_scroll = selected_process;
+        } else if selected_process >= adjusted_scroll + max_items {
+            adjusted_scroll = selected_process - max_items + 1;
+        }
+
+        let columns = visible_columns(state);
+        let widths: Vec<Constraint> = columns.iter().map(|column| column.width()).collect();
+
+        let header = Row::new(columns.iter().map(|column| {
+            Cell::from(Span::styled(
+                column.title(),
+                Style::default()
+                    .fg(column.color())
+                    .add_modifier(Modifier::BOLD),
+            ))
+        }))
+        .height(1)
+        .bottom_margin(1);
+
+        // Create table rows
+        let rows = processes
+            .iter()
+            .enumerate()
+            .skip(adjusted_scroll)
+            .take(max_items)
+            .map(|(i, process)| {
+                let is_selected = i == selected_process;
+                let is_tagged = state.tagged.contains(&process.pid);
+
+                let style = if is_selected {
+                    make_highlight_style()
+                } else if is_tagged {
+                    make_tagged_style()
+                } else {
+                    Style::default()
+                };
+
+                let cells = columns.iter().map(|column| {
+                    let mut text = column.text(process);
+                    // Marked in text too, so tags don't rely on color alone
+                    if is_tagged && *column == Column::Pid {
+                        text.insert(0, '*');
+                    }
+                    let style = Style::default().fg(column.cell_color(process));
+                    let source = match column {
+                        Column::Name => Some(&process.name),
+                        Column::Cmdline => Some(&process.cmdline),
+                        _ => None,
+                    };
+                    match source.and_then(|source| filter_positions(state, source)) {
+                        Some(positions) => {
+                            // Truncated names end in "...", don't mark what was cut off
+                            let shown = if text == *source.unwrap_or(&text) {
+                                text.chars().count()
+                            } else {
+                                text.chars().count().saturating_sub(3)
+                            };
+                            let positions: Vec<usize> =
+                                positions.into_iter().filter(|i| *i < shown).collect();
+                            Cell::from(highlight_matches(&text, &positions, style))
+                        }
+                        None => Cell::from(Span::styled(text, style)),
+                    }
+                });
+
+                Row::new(cells).style(style)
+            });
+
+        let table = Table::new(rows.collect::<Vec<_>>(), widths.clone())
+            .header(header)
+            .widths(widths)
+            .column_spacing(2)
+            .row_highlight_style(make_highlight_style()) // Use your custom style here
+            .highlight_symbol(">> ");
+
+        let selected_position = if selected_process >= adjusted_scroll
+            && selected_process < adjusted_scroll + max_items
+        {
+            Some(selected_process - adjusted_scroll)
+        } else {
+            None
+        };
+
+        frame.render_widget(block, area);
+        render_state_summary(frame, &StateSummary::of(&state.all_processes), summary_area);
+        frame.render_stateful_widget(
+            table,
+            table_area,
+            &mut TableState::default().with_selected(selected_position),
+        );
+    }
+
+    fn handle_key(&self, state: &mut AppState, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('g') => state.show_group_columns = !state.show_group_columns,
+            KeyCode::Char('F') => {
+                state.show_fd_column = !state.show_fd_column;
+                if state.show_fd_column && !state.capabilities.allows(Capability::OpenFiles) {
+                    state.status_message = Some(format!(
+                        "FDS only counts your own processes: {}",
+                        Capability::OpenFiles.hint()
+                    ));
+                }
+            }
+            KeyCode::Char('E') => state.show_event_columns = !state.show_event_columns,
+            KeyCode::Char('c') => state.show_pod_column = !state.show_pod_column,
+            KeyCode::Char('X') => state.show_path_columns = !state.show_path_columns,
+            KeyCode::Char('d') => state.show_delta_columns = !state.show_delta_columns,
+            KeyCode::Char('J') => state.toggle_job_only(),
+            KeyCode::Char('G') => state.request_kill_group(),
+            KeyCode::Char('T') => state.request_kill_tree(),
+            KeyCode::Char(' ') => state.toggle_tag(),
+            KeyCode::Char('U') => state.tagged.clear(),
+            KeyCode::Char('K') => state.request_kill_tagged(),
+            KeyCode::Char('+') => state.renice_tagged(1),
+            KeyCode::Char('-') => state.renice_tagged(-1),
+            KeyCode::Char('s') => state.cycle_sort_key(),
+            KeyCode::Enter => state.open_detail(),
+            KeyCode::Char('p') => state.cycle_filter_preset(),
+            KeyCode::Char('R') => state.toggle_status_filter(StatusFilter::Running),
+            KeyCode::Char('D') => state.toggle_status_filter(StatusFilter::DiskSleep),
+            KeyCode::Char('Z') => state.toggle_status_filter(StatusFilter::Zombie),
+            KeyCode::Char('u') => state.resume_ordering(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// htop's "Tasks: 120, 400 thr; 2 running", followed by a bar per state when there's room
+fn render_state_summary(frame: &mut Frame, summary: &StateSummary, area: Rect) {
+    let tasks = format!(
+        " Tasks: {}, {} thr, {} kthr; {} running ",
+        summary.tasks, summary.threads, summary.kernel_threads, summary.running
+    );
+    // Only the problem states stand out, and only when there are any
+    let states = [
+        ("sleeping", summary.sleeping, Color::Gray),
+        ("D", summary.disk_sleep, Color::Yellow),
+        ("stopped", summary.stopped, Color::LightBlue),
+        ("zombie", summary.zombie, Color::LightRed),
+    ];
+    let labels: usize = states
+        .iter()
+        .map(|(label, count, _)| label.len() + count.to_string().len() + 5)
+        .sum();
+    let room = (area.width as usize).saturating_sub(tasks.chars().count() + labels);
+    let bar_width = (room / states.len()).min(8);
+
+    let mut spans = vec![Span::styled(tasks, Style::default().fg(Color::White))];
+    for (label, count, color) in states {
+        let color = if count == 0 { Color::Gray } else { color };
+        spans.push(Span::styled(
+            format!("| {} {} ", label, count),
+            Style::default().fg(color),
+        ));
+        if bar_width > 0 {
+            spans.push(Span::styled(
+                mini_bar(count as f64 / summary.total().max(1) as f64, bar_width),
+                Style::default().fg(color),
+            ));
+            spans.push(Span::raw(" "));
+        }
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Char indices of `text` the fuzzy or regex filter matched, to highlight them
+fn filter_positions(state: &AppState, text: &str) -> Option<Vec<usize>> {
+    if let Some(regex) = &state.regex_filter {
+        let ranges: Vec<_> = regex.find_iter(text).map(|m| m.range()).collect();
+        let positions = text
+            .char_indices()
+            .enumerate()
+            .filter(|(_, (byte, _))| ranges.iter().any(|range| range.contains(byte)))
+            .map(|(i, _)| i)
+            .collect();
+        return Some(positions);
+    }
+    let query = state
+        .name_filter
+        .as_deref()
+        .filter(|_| state.fuzzy_filter)?;
+    fuzzy::find(query, text).map(|matched| matched.positions)
+}
+
+/// `text` with the characters at `positions` emphasized, for fuzzy matches
+fn highlight_matches(text: &str, positions: &[usize], style: Style) -> Line<'static> {
+    let matched = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    Line::from(
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let style = if positions.contains(&i) {
+                    matched
+                } else {
+                    style
+                };
+                Span::styled(c.to_string(), style)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
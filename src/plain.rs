@@ -0,0 +1,131 @@
+//! `--plain`: one summary line in plain sentences per interval, no colors,
+//! symbols or cursor movement, so screen readers and log files get the same
+//! overview the TUI shows at a glance.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::Result;
+use sysinfo::Pid;
+
+use crate::cpu::CpuInfo;
+use crate::memory::{MemoryInfo, UsageLevel, UsageThresholds};
+use crate::process::{self, Process};
+use crate::units;
+
+/// Processes named per summary, for CPU and for memory
+const TOP_PROCESSES: usize = 3;
+
+/// Print until interrupted or stdout is closed
+pub fn run(interval: Duration, thresholds: UsageThresholds) -> Result<()> {
+    let mut cpu_info = CpuInfo::new_sampled();
+    let mut memory_info = MemoryInfo::new();
+    let mut stdout = io::stdout().lock();
+    // CPU time per process at the previous summary, a fresh process list has no usage yet
+    let mut last_times: HashMap<Pid, u64> = HashMap::new();
+    let mut last_at = Instant::now();
+    loop {
+        cpu_info.update();
+        memory_info.update();
+        let mut processes: Vec<Process> = process::get_all_processes()
+            .into_iter()
+            .filter(|p| p.thread_kind.is_none())
+            .collect();
+        process::fill_cpu_usage(&mut processes, &last_times, last_at.elapsed());
+        last_at = Instant::now();
+        last_times = processes.iter().map(|p| (p.pid, p.cpu_time_ms)).collect();
+        let line = summary(&cpu_info, &memory_info, &mut processes, thresholds);
+
+        // Reader went away (e.g. `| head`), that's a normal way to stop
+        match writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// "14:02:11. CPU 12.5%, busiest core 3 at 45.0%. Memory 20.1% used, ..."
+fn summary(
+    cpu_info: &CpuInfo,
+    memory_info: &MemoryInfo,
+    processes: &mut [Process],
+    thresholds: UsageThresholds,
+) -> String {
+    let mut line = format!(
+        "{}. CPU {:.1}%",
+        process::format_timestamp(SystemTime::now(), false),
+        cpu_info.global_usage
+    );
+    if let Some((index, core)) = cpu_info
+        .cores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.usage.total_cmp(&b.1.usage))
+    {
+        let _ = write!(line, ", busiest core {} at {:.1}%", index + 1, core.usage);
+    }
+
+    let memory_percent = memory_info.current_memory_percent();
+    let _ = write!(
+        line,
+        ". Memory {:.1}% used{}, {} of {}",
+        memory_percent,
+        level_words(thresholds.level(memory_percent)),
+        units::format_size(memory_info.used_memory),
+        units::format_size(memory_info.total_memory)
+    );
+    if let Some(pressure) = memory_info.pressure {
+        let _ = write!(line, ", pressure {}", pressure.label());
+    }
+    if memory_info.total_swap > 0 {
+        let swap_percent = memory_info.current_swap_percent();
+        let _ = write!(
+            line,
+            ". Swap {:.1}% used{}",
+            swap_percent,
+            level_words(thresholds.level(swap_percent))
+        );
+    } else {
+        line.push_str(". No swap");
+    }
+
+    process::sort_by_cpu(processes);
+    let _ = write!(
+        line,
+        ". Most CPU: {}",
+        list(processes, |p| format!("{:.1}%", p.cpu_usage))
+    );
+    process::sort_by_memory(processes);
+    let _ = write!(
+        line,
+        ". Most memory: {}.",
+        list(processes, |p| units::format_mb(p.memory_mb))
+    );
+    line
+}
+
+fn level_words(level: UsageLevel) -> &'static str {
+    match level {
+        UsageLevel::Normal => "",
+        UsageLevel::Warning => ", high",
+        UsageLevel::Critical => ", critical",
+    }
+}
+
+/// "firefox 25.0%, cargo 12.0% and bash 1.0%"
+fn list(processes: &[Process], value: impl Fn(&Process) -> String) -> String {
+    let items: Vec<String> = processes
+        .iter()
+        .take(TOP_PROCESSES)
+        .map(|p| format!("{} {}", p.name, value(p)))
+        .collect();
+    match items.split_last() {
+        None => "none".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
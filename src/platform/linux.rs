@@ -0,0 +1,483 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+
+use sysinfo::{Pid, ProcessStatus};
+
+use super::{
+    CoreKind, CpuCache, CpuTopology, EventCounters, IoCounters, MemoryPressure, PortSocket,
+    WaitState,
+};
+use crate::process::{FdKind, OpenFile};
+
+pub fn process_status(process: &sysinfo::Process) -> ProcessStatus {
+    process.status()
+}
+
+/// Intel hybrid CPUs list their core types as separate PMUs in sysfs
+pub fn core_kinds(count: usize) -> Option<Vec<CoreKind>> {
+    let read = |pmu: &str| {
+        fs::read_to_string(format!("/sys/devices/{}/cpus", pmu))
+            .ok()
+            .map(|list| parse_cpu_list(list.trim()))
+    };
+    let performance = read("cpu_core")?;
+    let efficiency = read("cpu_atom")?;
+    (0..count)
+        .map(|cpu| {
+            if performance.contains(&cpu) {
+                Some(CoreKind::Performance)
+            } else if efficiency.contains(&cpu) {
+                Some(CoreKind::Efficiency)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// "0-3,8" -> [0, 1, 2, 3, 8]
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+            None => range.parse().ok().map(|cpu| cpu..=cpu),
+        })
+        .flatten()
+        .collect()
+}
+
+/// Sockets and caches from /sys/devices/system/cpu, microcode from /proc/cpuinfo
+pub fn cpu_topology() -> Option<CpuTopology> {
+    let cpus: Vec<_> = fs::read_dir("/sys/devices/system/cpu")
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("cpu"))
+                .is_some_and(|id| id.parse::<u32>().is_ok())
+        })
+        .map(|entry| entry.path())
+        .collect();
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+
+    let mut sockets = HashSet::new();
+    // (level, kind, size, CPUs sharing it), each shared cache is listed under all of its CPUs
+    let mut caches = HashSet::new();
+    for cpu in &cpus {
+        if let Some(package) = read(&cpu.join("topology/physical_package_id")) {
+            sockets.insert(package);
+        }
+        let Ok(indexes) = fs::read_dir(cpu.join("cache")) else {
+            continue;
+        };
+        for index in indexes.flatten().map(|entry| entry.path()) {
+            let (Some(level), Some(kind), Some(size), Some(shared)) = (
+                read(&index.join("level")).and_then(|level| level.parse::<u8>().ok()),
+                read(&index.join("type")),
+                read(&index.join("size")).and_then(|size| parse_cache_size(&size)),
+                read(&index.join("shared_cpu_list")),
+            ) else {
+                continue;
+            };
+            caches.insert((level, kind, size, shared));
+        }
+    }
+
+    let mut grouped: Vec<CpuCache> = Vec::new();
+    for (level, kind, size, _) in caches {
+        match grouped
+            .iter_mut()
+            .find(|c| c.level == level && c.kind == kind && c.size_bytes == size)
+        {
+            Some(cache) => cache.instances += 1,
+            None => grouped.push(CpuCache {
+                level,
+                kind,
+                size_bytes: size,
+                instances: 1,
+            }),
+        }
+    }
+    grouped.sort_by(|a, b| (a.level, &a.kind).cmp(&(b.level, &b.kind)));
+
+    let microcode = fs::read_to_string("/proc/cpuinfo").ok().and_then(|info| {
+        info.lines()
+            .find_map(|line| line.strip_prefix("microcode"))
+            .and_then(|rest| rest.split_once(':'))
+            .map(|(_, value)| value.trim().to_string())
+    });
+    Some(CpuTopology {
+        sockets: sockets.len().max(1),
+        caches: grouped,
+        microcode,
+    })
+}
+
+/// "48K", "2048K" or "32M" in bytes
+fn parse_cache_size(size: &str) -> Option<u64> {
+    let (number, scale) = match size.strip_suffix('K') {
+        Some(kb) => (kb, 1024),
+        None => match size.strip_suffix('M') {
+            Some(mb) => (mb, 1024 * 1024),
+            None => (size, 1),
+        },
+    };
+    Some(number.parse::<u64>().ok()? * scale)
+}
+
+/// From PSI: share of time some task waited for memory over the last 10 seconds,
+/// with the same 5%/20% steps the pressure columns use. None without PSI.
+pub fn memory_pressure() -> Option<MemoryPressure> {
+    let contents = fs::read_to_string("/proc/pressure/memory").ok()?;
+    let avg10: f32 = contents
+        .lines()
+        .find(|line| line.starts_with("some"))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()?;
+    Some(if avg10 >= 20.0 {
+        MemoryPressure::Critical
+    } else if avg10 >= 5.0 {
+        MemoryPressure::Warning
+    } else {
+        MemoryPressure::Normal
+    })
+}
+
+/// Linux only has package wide energy counters (RAPL), nothing per process
+pub fn energy_nj(_pid: Pid) -> Option<u64> {
+    None
+}
+
+/// read_bytes/write_bytes from /proc/<pid>/io, what actually reached the storage layer.
+/// Only readable for our own processes unless running as root.
+pub fn io_counters(pid: Pid) -> io::Result<IoCounters> {
+    let contents = fs::read_to_string(format!("/proc/{}/io", pid))?;
+    let field = |name: &str| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().parse().ok())
+    };
+    match (field("read_bytes:"), field("write_bytes:")) {
+        (Some(read_bytes), Some(written_bytes)) => Ok(IoCounters {
+            read_bytes,
+            written_bytes,
+        }),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected /proc/<pid>/io format",
+        )),
+    }
+}
+
+/// File descriptors of a process from /proc/<pid>/fd, ordered by number.
+/// Only readable for our own processes unless running as root.
+pub fn open_files(pid: Pid) -> io::Result<Vec<OpenFile>> {
+    let dir = format!("/proc/{}/fd", pid);
+    let mut files: Vec<OpenFile> = fs::read_dir(&dir)?
+        .flatten()
+        .filter_map(|entry| {
+            let fd: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let path = fs::read_link(entry.path())
+                .ok()?
+                .to_string_lossy()
+                .into_owned();
+            Some(OpenFile {
+                fd,
+                kind: fd_kind(&entry.path(), &path),
+                mode: fd_mode(pid, fd),
+                path,
+            })
+        })
+        .collect();
+    files.sort_by_key(|file| file.fd);
+    Ok(files)
+}
+
+/// Sockets bound to local `port` from /proc/net/{tcp,udp}{,6}, readable for everyone.
+/// Which process holds one only shows in the fd directories.
+pub fn port_sockets(port: u16) -> io::Result<Vec<PortSocket>> {
+    let mut sockets = Vec::new();
+    for protocol in ["tcp", "tcp6", "udp", "udp6"] {
+        // Kernels without IPv6 have no v6 tables
+        let Ok(table) = fs::read_to_string(format!("/proc/net/{}", protocol)) else {
+            continue;
+        };
+        sockets.extend(
+            parse_socket_table(&table, port)
+                .into_iter()
+                .map(|(inode, state)| PortSocket {
+                    inode,
+                    protocol,
+                    // TCP_LISTEN, or TCP_CLOSE for a bound but unconnected UDP socket
+                    listening: state
+                        == if protocol.starts_with("tcp") {
+                            0x0a
+                        } else {
+                            0x07
+                        },
+                }),
+        );
+    }
+    Ok(sockets)
+}
+
+/// (inode, state) of the rows with local `port`, e.g.
+/// "0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000 1000 0 123456 ..."
+fn parse_socket_table(table: &str, port: u16) -> Vec<(u64, u8)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = u16::from_str_radix(fields.get(1)?.rsplit(':').next()?, 16).ok()?;
+            let state = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+            let inode: u64 = fields.get(9)?.parse().ok()?;
+            // Sockets in TIME_WAIT no longer belong to anyone
+            (local_port == port && inode != 0).then_some((inode, state))
+        })
+        .collect()
+}
+
+/// Syscall, wchan and kernel stack from /proc. The syscall file needs the same
+/// ptrace access as the fd directory, the stack is root only and left empty.
+pub fn wait_state(pid: Pid) -> io::Result<WaitState> {
+    let syscall = fs::read_to_string(format!("/proc/{}/syscall", pid))?;
+    let syscall = match syscall.split_whitespace().next() {
+        Some("running") | None => None,
+        // Blocked outside a syscall, e.g. on a page fault
+        Some("-1") => Some("no syscall".to_string()),
+        Some(number) => Some(match number.parse().ok().and_then(syscall_name) {
+            Some(name) => name.to_string(),
+            None => format!("syscall {}", number),
+        }),
+    };
+    let wchan = fs::read_to_string(format!("/proc/{}/wchan", pid))
+        .ok()
+        .map(|wchan| wchan.trim().to_string())
+        .filter(|wchan| !wchan.is_empty() && wchan != "0");
+    // "[<0>] futex_wait_queue+0x60/0xa0"
+    let stack = fs::read_to_string(format!("/proc/{}/stack", pid))
+        .map(|stack| {
+            stack
+                .lines()
+                .filter_map(|line| {
+                    let frame = line.split_once("] ").map_or(line, |(_, frame)| frame);
+                    let function = frame.split('+').next()?.trim();
+                    (!function.is_empty()).then(|| function.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(WaitState {
+        syscall,
+        wchan,
+        stack,
+    })
+}
+
+/// The syscalls processes usually block in, the rest show as numbers
+#[cfg(target_arch = "x86_64")]
+fn syscall_name(number: u32) -> Option<&'static str> {
+    Some(match number {
+        0 => "read",
+        1 => "write",
+        2 => "open",
+        3 => "close",
+        7 => "poll",
+        9 => "mmap",
+        16 => "ioctl",
+        17 => "pread64",
+        18 => "pwrite64",
+        19 => "readv",
+        20 => "writev",
+        23 => "select",
+        24 => "sched_yield",
+        26 => "msync",
+        34 => "pause",
+        35 => "nanosleep",
+        42 => "connect",
+        43 => "accept",
+        44 => "sendto",
+        45 => "recvfrom",
+        46 => "sendmsg",
+        47 => "recvmsg",
+        61 => "wait4",
+        65 => "semop",
+        70 => "msgrcv",
+        73 => "flock",
+        74 => "fsync",
+        75 => "fdatasync",
+        128 => "rt_sigtimedwait",
+        130 => "rt_sigsuspend",
+        202 => "futex",
+        208 => "io_getevents",
+        230 => "clock_nanosleep",
+        232 => "epoll_wait",
+        247 => "waitid",
+        257 => "openat",
+        270 => "pselect6",
+        271 => "ppoll",
+        281 => "epoll_pwait",
+        288 => "accept4",
+        299 => "recvmmsg",
+        307 => "sendmmsg",
+        426 => "io_uring_enter",
+        441 => "epoll_pwait2",
+        449 => "futex_waitv",
+        _ => return None,
+    })
+}
+
+/// The generic syscall table, shared by arm64 and riscv64
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+fn syscall_name(number: u32) -> Option<&'static str> {
+    Some(match number {
+        4 => "io_getevents",
+        22 => "epoll_pwait",
+        29 => "ioctl",
+        32 => "flock",
+        56 => "openat",
+        57 => "close",
+        63 => "read",
+        64 => "write",
+        65 => "readv",
+        66 => "writev",
+        67 => "pread64",
+        68 => "pwrite64",
+        72 => "pselect6",
+        73 => "ppoll",
+        82 => "fsync",
+        83 => "fdatasync",
+        95 => "waitid",
+        98 => "futex",
+        101 => "nanosleep",
+        115 => "clock_nanosleep",
+        124 => "sched_yield",
+        133 => "rt_sigsuspend",
+        137 => "rt_sigtimedwait",
+        188 => "msgrcv",
+        193 => "semop",
+        202 => "accept",
+        203 => "connect",
+        206 => "sendto",
+        207 => "recvfrom",
+        211 => "sendmsg",
+        212 => "recvmsg",
+        222 => "mmap",
+        227 => "msync",
+        242 => "accept4",
+        243 => "recvmmsg",
+        260 => "wait4",
+        269 => "sendmmsg",
+        426 => "io_uring_enter",
+        441 => "epoll_pwait2",
+        449 => "futex_waitv",
+        _ => return None,
+    })
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv64"
+)))]
+fn syscall_name(_number: u32) -> Option<&'static str> {
+    None
+}
+
+/// Faults from /proc/<pid>/stat, context switches from /proc/<pid>/status
+pub fn event_counters(pid: Pid) -> Option<EventCounters> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The name in parentheses may contain spaces, count the fields after it
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let field = |index: usize| fields.get(index)?.parse().ok();
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let switches = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))?
+            .trim()
+            .parse()
+            .ok()
+    };
+    Some(EventCounters {
+        minor_faults: field(7)?,
+        major_faults: field(9)?,
+        voluntary_switches: switches("voluntary_ctxt_switches:")?,
+        involuntary_switches: switches("nonvoluntary_ctxt_switches:")?,
+    })
+}
+
+/// VmSwap from /proc/<pid>/status
+pub fn swap_bytes(pid: Pid) -> Option<u64> {
+    let kb: u64 = fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("VmSwap:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Number of open file descriptors, None when /proc/<pid>/fd isn't readable
+pub fn fd_count(pid: Pid) -> Option<usize> {
+    fs::read_dir(format!("/proc/{}/fd", pid))
+        .ok()
+        .map(|entries| entries.count())
+}
+
+fn fd_kind(link: &Path, target: &str) -> FdKind {
+    if target.starts_with("socket:") {
+        return FdKind::Socket;
+    }
+    if target.starts_with("pipe:") {
+        return FdKind::Pipe;
+    }
+    if target.starts_with("anon_inode:") {
+        return FdKind::AnonInode;
+    }
+    // Follows the link, so this describes the opened file itself
+    let Ok(metadata) = fs::metadata(link) else {
+        return FdKind::Other;
+    };
+    let file_type = metadata.file_type();
+    if file_type.is_char_device() || file_type.is_block_device() {
+        return FdKind::Device;
+    }
+    if file_type.is_dir() {
+        FdKind::Directory
+    } else if file_type.is_file() {
+        FdKind::File
+    } else {
+        FdKind::Other
+    }
+}
+
+/// Access mode from the octal "flags:" line of /proc/<pid>/fdinfo/<fd>
+fn fd_mode(pid: Pid, fd: u32) -> &'static str {
+    let flags = fs::read_to_string(format!("/proc/{}/fdinfo/{}", pid, fd))
+        .ok()
+        .and_then(|info| {
+            info.lines()
+                .find_map(|line| line.strip_prefix("flags:"))
+                .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok())
+        });
+    // O_ACCMODE
+    match flags.map(|flags| flags & 0o3) {
+        Some(0) => "r",
+        Some(1) => "w",
+        Some(2) => "rw",
+        _ => "",
+    }
+}
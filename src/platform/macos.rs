@@ -0,0 +1,118 @@
+use std::ffi::CString;
+use std::io;
+
+use sysinfo::{Pid, ProcessStatus};
+
+use super::{
+    sysinfo_io_counters, unsupported, CoreKind, CpuTopology, EventCounters, IoCounters,
+    MemoryPressure, PortSocket, WaitState,
+};
+use crate::process::OpenFile;
+
+/// sysinfo derives it from the thread states, like `ps` does
+pub fn process_status(process: &sysinfo::Process) -> ProcessStatus {
+    process.status()
+}
+
+/// Apple Silicon numbers its efficiency cores first. perflevel0 is the fastest
+/// cluster, perflevel1 the efficiency one, Intel Macs have a single level.
+pub fn core_kinds(count: usize) -> Option<Vec<CoreKind>> {
+    if sysctl_u32("hw.nperflevels")? < 2 {
+        return None;
+    }
+    let performance = sysctl_u32("hw.perflevel0.logicalcpu")? as usize;
+    let efficiency = sysctl_u32("hw.perflevel1.logicalcpu")? as usize;
+    if performance + efficiency != count {
+        return None;
+    }
+    Some(
+        (0..count)
+            .map(|cpu| {
+                if cpu < efficiency {
+                    CoreKind::Efficiency
+                } else {
+                    CoreKind::Performance
+                }
+            })
+            .collect(),
+    )
+}
+
+pub fn cpu_topology() -> Option<CpuTopology> {
+    None
+}
+
+/// The kernel's own verdict, what Activity Monitor's memory pressure graph shows
+pub fn memory_pressure() -> Option<MemoryPressure> {
+    // kVMPressureNormal, kVMPressureWarning, kVMPressureCritical
+    match sysctl_u32("kern.memorystatus_vm_pressure_level")? {
+        1 => Some(MemoryPressure::Normal),
+        2 => Some(MemoryPressure::Warning),
+        4 => Some(MemoryPressure::Critical),
+        _ => None,
+    }
+}
+
+/// Energy billed to the process since it started in nanojoules, what
+/// Activity Monitor's energy impact is based on. Only readable for our own
+/// processes unless running as root.
+pub fn energy_nj(pid: Pid) -> Option<u64> {
+    // SAFETY: rusage_info_v4 is plain old data, all zeroes is a valid value
+    let mut info: libc::rusage_info_v4 = unsafe { std::mem::zeroed() };
+    // SAFETY: info is a rusage_info_v4, which is what RUSAGE_INFO_V4 makes the kernel write
+    let result = unsafe {
+        libc::proc_pid_rusage(
+            pid.as_u32() as libc::c_int,
+            libc::RUSAGE_INFO_V4,
+            &mut info as *mut libc::rusage_info_v4 as *mut libc::rusage_info_t,
+        )
+    };
+    (result == 0).then_some(info.ri_billed_energy)
+}
+
+pub fn io_counters(pid: Pid) -> io::Result<IoCounters> {
+    sysinfo_io_counters(pid)
+}
+
+pub fn open_files(_pid: Pid) -> io::Result<Vec<OpenFile>> {
+    unsupported("listing open files is")
+}
+
+pub fn port_sockets(_port: u16) -> io::Result<Vec<PortSocket>> {
+    unsupported("looking up sockets by port is")
+}
+
+/// The syscall and wait channel come from /proc, which macOS doesn't have
+pub fn wait_state(_pid: Pid) -> io::Result<WaitState> {
+    unsupported("sampling syscalls is")
+}
+
+pub fn event_counters(_pid: Pid) -> Option<EventCounters> {
+    None
+}
+
+/// macOS has no per-process swap accounting
+pub fn swap_bytes(_pid: Pid) -> Option<u64> {
+    None
+}
+
+pub fn fd_count(_pid: Pid) -> Option<usize> {
+    None
+}
+
+fn sysctl_u32(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>();
+    // SAFETY: value and size describe a valid u32 buffer, no new value is set
+    let result = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            &mut value as *mut u32 as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    (result == 0).then_some(value)
+}
@@ -0,0 +1,148 @@
+//! Process data the operating systems expose differently. Everything here has
+//! the same signature on every platform, features a platform lacks return an
+//! `Unsupported` error or None instead of failing in platform specific ways.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub use macos::*;
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub use windows::*;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod other;
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub use other::*;
+
+/// Bytes a process read from and wrote to storage since it started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoCounters {
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+}
+
+/// A socket bound to a local port, found in the kernel's socket tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortSocket {
+    /// Matches the `socket:[inode]` link in the owner's fd directory
+    pub inode: u64,
+    /// "tcp", "tcp6", "udp" or "udp6"
+    pub protocol: &'static str,
+    pub listening: bool,
+}
+
+/// Hybrid CPUs (Apple Silicon, Intel since Alder Lake) mix fast and frugal cores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreKind {
+    Performance,
+    Efficiency,
+}
+
+impl CoreKind {
+    pub fn short(&self) -> &'static str {
+        match self {
+            CoreKind::Performance => "P",
+            CoreKind::Efficiency => "E",
+        }
+    }
+}
+
+/// How hard the system has to work to find free memory, like Activity Monitor's gauge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryPressure {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl MemoryPressure {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MemoryPressure::Normal => "normal",
+            MemoryPressure::Warning => "warning",
+            MemoryPressure::Critical => "critical",
+        }
+    }
+}
+
+/// One cache of the CPU, e.g. L2 unified, 2MiB per core
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuCache {
+    pub level: u8,
+    /// "Data", "Instruction" or "Unified"
+    pub kind: String,
+    /// Size of one instance
+    pub size_bytes: u64,
+    /// How many there are, one per core for L1 and usually L2
+    pub instances: usize,
+}
+
+/// Static CPU layout, read once for the hardware popup
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuTopology {
+    pub sockets: usize,
+    /// Ordered by level, data before instruction
+    pub caches: Vec<CpuCache>,
+    /// Microcode revision, e.g. "0xf4"
+    pub microcode: Option<String>,
+}
+
+/// Page faults and context switches since the process started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventCounters {
+    pub minor_faults: u64,
+    /// Faults that had to wait for storage, swap-ins and file pages
+    pub major_faults: u64,
+    /// Gave up the CPU to wait for something
+    pub voluntary_switches: u64,
+    /// Preempted, a sign of CPU contention
+    pub involuntary_switches: u64,
+}
+
+/// One look at where a process is in the kernel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WaitState {
+    /// Syscall name, or "syscall N" for ones not in the table, None while running
+    pub syscall: Option<String>,
+    /// Kernel function the process sleeps in
+    pub wchan: Option<String>,
+    /// Kernel stack innermost first, empty without CAP_SYS_ADMIN
+    pub stack: Vec<String>,
+}
+
+/// Disk I/O totals through sysinfo, for platforms without anything better
+#[cfg(not(target_os = "linux"))]
+fn sysinfo_io_counters(pid: sysinfo::Pid) -> std::io::Result<IoCounters> {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_disk_usage(),
+    );
+    let process = system
+        .process(pid)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "process not found"))?;
+    let usage = process.disk_usage();
+    Ok(IoCounters {
+        read_bytes: usage.total_read_bytes,
+        written_bytes: usage.total_written_bytes,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported<T>(what: &str) -> std::io::Result<T> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{} not supported on this platform", what),
+    ))
+}
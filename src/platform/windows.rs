@@ -0,0 +1,67 @@
+use std::io;
+
+use sysinfo::{Pid, ProcessStatus};
+
+use super::{
+    sysinfo_io_counters, unsupported, CoreKind, CpuTopology, EventCounters, IoCounters,
+    MemoryPressure, PortSocket, WaitState,
+};
+use crate::process::OpenFile;
+
+/// Windows has no process state, sysinfo reports every process as running.
+/// Whether it used any CPU since the last refresh is the closest thing to it,
+/// so the running filter doesn't match every process.
+pub fn process_status(process: &sysinfo::Process) -> ProcessStatus {
+    if process.cpu_usage() > 0.0 {
+        ProcessStatus::Run
+    } else {
+        ProcessStatus::Sleep
+    }
+}
+
+pub fn core_kinds(_count: usize) -> Option<Vec<CoreKind>> {
+    None
+}
+
+pub fn cpu_topology() -> Option<CpuTopology> {
+    None
+}
+
+pub fn memory_pressure() -> Option<MemoryPressure> {
+    None
+}
+
+pub fn energy_nj(_pid: Pid) -> Option<u64> {
+    None
+}
+
+pub fn io_counters(pid: Pid) -> io::Result<IoCounters> {
+    sysinfo_io_counters(pid)
+}
+
+/// Handles aren't file descriptors, there is no per-process list of open files
+pub fn open_files(_pid: Pid) -> io::Result<Vec<OpenFile>> {
+    unsupported("listing open files is")
+}
+
+pub fn port_sockets(_port: u16) -> io::Result<Vec<PortSocket>> {
+    unsupported("looking up sockets by port is")
+}
+
+/// There's no /proc to read the syscall and wait channel from
+pub fn wait_state(_pid: Pid) -> io::Result<WaitState> {
+    unsupported("sampling syscalls is")
+}
+
+pub fn event_counters(_pid: Pid) -> Option<EventCounters> {
+    None
+}
+
+/// The page file isn't accounted per process
+pub fn swap_bytes(_pid: Pid) -> Option<u64> {
+    None
+}
+
+pub fn fd_count(_pid: Pid) -> Option<usize> {
+    None
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::ToSocketAddrs;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often every target is probed
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+/// A ping that takes longer counts as lost
+const PING_TIMEOUT_SECS: u32 = 2;
+/// 10 minutes of history at the probe interval
+const HISTORY_CAPACITY: usize = 120;
+
+/// Something checked periodically to see whether the network works
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// ICMP echo through the system `ping`, which has the privileges raw sockets need
+    Ping(String),
+    /// Name lookup through the system resolver, so /etc/hosts and nsswitch apply
+    Resolve(String),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Ping(host) => write!(f, "ping {}", host),
+            Target::Resolve(name) => write!(f, "dns {}", name),
+        }
+    }
+}
+
+/// Latency history of one target, None entries are failed probes
+#[derive(Debug, Clone)]
+pub struct ProbeHistory {
+    pub target: Target,
+    pub latencies: VecDeque<Option<Duration>>,
+    /// Why the most recent probe failed
+    pub last_error: Option<String>,
+}
+
+impl ProbeHistory {
+    pub fn last(&self) -> Option<Option<Duration>> {
+        self.latencies.back().copied()
+    }
+
+    /// Failed probes among the ones in the history
+    pub fn lost(&self) -> usize {
+        self.latencies.iter().filter(|l| l.is_none()).count()
+    }
+}
+
+/// Configured probes and their results. Every target gets its own thread, a
+/// timing out ping must neither hold up the other probes nor the samplers.
+#[derive(Debug, Default)]
+pub struct Probes {
+    pub histories: Vec<ProbeHistory>,
+    results: Option<Receiver<(usize, Result<Duration, String>)>>,
+}
+
+impl Probes {
+    pub fn new(targets: &[Target]) -> Self {
+        Self {
+            histories: targets
+                .iter()
+                .map(|target| ProbeHistory {
+                    target: target.clone(),
+                    latencies: VecDeque::with_capacity(HISTORY_CAPACITY),
+                    last_error: None,
+                })
+                .collect(),
+            results: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histories.is_empty()
+    }
+
+    /// Start probing, the threads stop once `self` is dropped
+    pub fn start(&mut self) {
+        if self.results.is_some() || self.is_empty() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        for (index, history) in self.histories.iter().enumerate() {
+            let target = history.target.clone();
+            let sender: Sender<_> = sender.clone();
+            thread::spawn(move || loop {
+                let started = Instant::now();
+                let result = probe(&target);
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+                thread::sleep(PROBE_INTERVAL.saturating_sub(started.elapsed()));
+            });
+        }
+        self.results = Some(receiver);
+    }
+
+    /// Move finished probes into the histories, true when there were any
+    pub fn collect(&mut self) -> bool {
+        let Some(results) = &self.results else {
+            return false;
+        };
+        let mut any = false;
+        for (index, result) in results.try_iter() {
+            let history = &mut self.histories[index];
+            // Only changes are worth a log line, not every lost ping
+            match (&result, history.last_error.is_some()) {
+                (Err(e), false) => tracing::warn!("{} failing: {}", history.target, e),
+                (Ok(_), true) => tracing::info!("{} works again", history.target),
+                _ => {}
+            }
+            history.last_error = result.as_ref().err().cloned();
+            history.latencies.push_back(result.ok());
+            if history.latencies.len() > HISTORY_CAPACITY {
+                history.latencies.pop_front();
+            }
+            any = true;
+        }
+        any
+    }
+}
+
+fn probe(target: &Target) -> Result<Duration, String> {
+    match target {
+        Target::Ping(host) => ping(host),
+        Target::Resolve(name) => {
+            let started = Instant::now();
+            let addresses = (name.as_str(), 0)
+                .to_socket_addrs()
+                .map_err(|e| e.to_string())?;
+            let elapsed = started.elapsed();
+            if addresses.count() == 0 {
+                return Err("no addresses".to_string());
+            }
+            Ok(elapsed)
+        }
+    }
+}
+
+/// The round trip `ping` measured, not the process runtime
+fn ping(host: &str) -> Result<Duration, String> {
+    let output = Command::new("ping")
+        .args(["-n", "-c", "1", "-W", &PING_TIMEOUT_SECS.to_string()])
+        .arg(host)
+        .output()
+        .map_err(|e| format!("ping: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if let Some(millis) = parse_ping_time(&stdout) {
+        return Ok(Duration::from_secs_f64(millis / 1000.0));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr
+        .lines()
+        .next()
+        .filter(|line| !line.is_empty())
+        .unwrap_or("timeout")
+        .to_string())
+}
+
+/// "64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=12.3 ms" -> 12.3
+pub fn parse_ping_time(output: &str) -> Option<f64> {
+    output
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix("time="))
+        .and_then(|millis| millis.trim_end_matches("ms").parse().ok())
+}
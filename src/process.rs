@@ -1,5 +1,19 @@
+use crate::history;
+use crate::platform;
+use crate::sampler::Sampler;
+use crate::snapshot::ProcessSnapshot;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
-use sysinfo::{Pid, ProcessStatus, System};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{
+    Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, ThreadKind, UpdateKind,
+    Users,
+};
 
 #[allow(dead_code)]
 pub fn main() {
@@ -15,7 +29,7 @@ pub fn main() {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Process {
     pub pid: Pid,
     pub name: String,
@@ -23,6 +37,466 @@ pub struct Process {
     pub memory_mb: f64,
     pub status: ProcessStatus,
     pub parent_pid: Option<Pid>,
+    /// Process group and session, what shells use to manage jobs and pipelines
+    pub pgid: Option<Pid>,
+    pub sid: Option<Pid>,
+    pub sandbox: Option<Sandbox>,
+    /// Total CPU time used since the process started
+    pub cpu_time_ms: u64,
+    /// Swapped out memory (VmSwap), None for kernel threads and on other platforms
+    pub swap_bytes: Option<u64>,
+    /// Open file descriptors, only counted while the FDS column is shown
+    pub fd_count: Option<usize>,
+    /// Change since the previous refresh, None until a process was seen twice
+    pub memory_delta_mb: Option<f64>,
+    pub cpu_delta: Option<f32>,
+    /// Energy billed to the process since it started, only on macOS
+    pub energy_nj: Option<u64>,
+    /// Average power since the previous refresh, from `energy_nj`
+    pub power_mw: Option<f64>,
+    /// Account name, the numeric UID when it has no name
+    pub user: Option<String>,
+    /// Full command line, arguments separated by spaces
+    pub cmdline: String,
+    /// Kernel threads and the threads listed next to their process, Linux only
+    pub thread_kind: Option<ThreadKind>,
+    /// Kubernetes "namespace/pod", only looked up while the POD column is shown
+    pub pod: Option<String>,
+    /// Faults and context switches, only read while their columns or the details are shown
+    pub event_counters: Option<EventCounters>,
+    /// Per second since the previous refresh, from `event_counters`
+    pub event_rates: Option<EventRates>,
+    /// Working directory and executable, None where they can't be read (other users' processes)
+    pub cwd: Option<String>,
+    pub exe: Option<String>,
+    /// The executable was removed or replaced since the process started, e.g. by
+    /// a package upgrade, so the process still runs the old version
+    pub exe_deleted: bool,
+}
+
+/// Stable machine readable status name, unlike sysinfo's Display which differs per platform
+pub fn status_name(status: ProcessStatus) -> &'static str {
+    match status {
+        ProcessStatus::Run => "running",
+        ProcessStatus::Sleep => "sleeping",
+        ProcessStatus::Idle => "idle",
+        ProcessStatus::Zombie => "zombie",
+        ProcessStatus::Dead => "dead",
+        ProcessStatus::Stop => "stopped",
+        ProcessStatus::UninterruptibleDiskSleep => "disk_sleep",
+        _ => "unknown",
+    }
+}
+
+pub fn status_from_name(name: &str) -> ProcessStatus {
+    match name {
+        "running" => ProcessStatus::Run,
+        "sleeping" => ProcessStatus::Sleep,
+        "idle" => ProcessStatus::Idle,
+        "zombie" => ProcessStatus::Zombie,
+        "dead" => ProcessStatus::Dead,
+        "stopped" => ProcessStatus::Stop,
+        "disk_sleep" => ProcessStatus::UninterruptibleDiskSleep,
+        _ => ProcessStatus::Unknown(0),
+    }
+}
+
+/// Quick triage views for the process table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusFilter {
+    All,
+    Running,
+    /// Uninterruptible sleep, usually stuck on storage or NFS
+    DiskSleep,
+    Zombie,
+}
+
+impl StatusFilter {
+    pub fn matches(&self, process: &Process) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Running => process.status == ProcessStatus::Run,
+            StatusFilter::DiskSleep => process.status == ProcessStatus::UninterruptibleDiskSleep,
+            StatusFilter::Zombie => process.status == ProcessStatus::Zombie,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusFilter::All => "all",
+            StatusFilter::Running => "running",
+            StatusFilter::DiskSleep => "D state",
+            StatusFilter::Zombie => "zombies",
+        }
+    }
+}
+
+/// Process counts by state, like htop's "Tasks: 120, 400 thr; 2 running"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateSummary {
+    /// Processes, without threads and kernel threads
+    pub tasks: usize,
+    pub threads: usize,
+    pub kernel_threads: usize,
+    /// By state, over processes and kernel threads but not userland threads
+    pub running: usize,
+    /// Including idle kernel threads
+    pub sleeping: usize,
+    pub disk_sleep: usize,
+    pub stopped: usize,
+    pub zombie: usize,
+    /// Dead and whatever else the platform reports
+    pub other: usize,
+}
+
+impl StateSummary {
+    pub fn of(processes: &[Process]) -> Self {
+        let mut summary = StateSummary::default();
+        for process in processes {
+            match process.thread_kind {
+                Some(ThreadKind::Userland) => {
+                    summary.threads += 1;
+                    continue;
+                }
+                Some(ThreadKind::Kernel) => summary.kernel_threads += 1,
+                None => summary.tasks += 1,
+            }
+            match process.status {
+                ProcessStatus::Run => summary.running += 1,
+                ProcessStatus::Sleep | ProcessStatus::Idle => summary.sleeping += 1,
+                ProcessStatus::UninterruptibleDiskSleep => summary.disk_sleep += 1,
+                ProcessStatus::Stop | ProcessStatus::Tracing => summary.stopped += 1,
+                ProcessStatus::Zombie => summary.zombie += 1,
+                _ => summary.other += 1,
+            }
+        }
+        summary
+    }
+
+    /// Everything counted by state
+    pub fn total(&self) -> usize {
+        self.tasks + self.kernel_threads
+    }
+}
+
+/// Processes stuck in D state longer than this almost always mean storage/NFS trouble
+pub const D_STATE_ALERT_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Remembers since when each process has been in uninterruptible sleep
+#[derive(Debug, Default)]
+pub struct DStateTracker {
+    since: HashMap<Pid, (String, Instant)>,
+}
+
+impl DStateTracker {
+    pub fn update(&mut self, processes: &[Process]) {
+        let now = Instant::now();
+        let mut since = HashMap::new();
+        for process in processes {
+            if process.status != ProcessStatus::UninterruptibleDiskSleep {
+                continue;
+            }
+            let entered = self
+                .since
+                .get(&process.pid)
+                .map_or(now, |(_, entered)| *entered);
+            since.insert(process.pid, (process.name.clone(), entered));
+        }
+        self.since = since;
+    }
+
+    /// (pid, name, time in D state) of processes stuck longer than `threshold`, longest first
+    pub fn stuck(&self, threshold: Duration) -> Vec<(Pid, String, Duration)> {
+        let mut stuck: Vec<(Pid, String, Duration)> = self
+            .since
+            .iter()
+            .map(|(pid, (name, entered))| (*pid, name.clone(), entered.elapsed()))
+            .filter(|(_, _, elapsed)| *elapsed >= threshold)
+            .collect();
+        stuck.sort_by_key(|(_, _, elapsed)| std::cmp::Reverse(*elapsed));
+        stuck
+    }
+}
+
+/// How far back the leak detector looks by default
+pub const DEFAULT_LEAK_WINDOW: Duration = Duration::from_secs(300);
+/// Growth from which a steadily growing process counts as a suspect by default
+pub const DEFAULT_LEAK_SLOPE_MB_PER_MIN: f64 = 1.0;
+/// RSS samples per window, the sample interval scales with the window
+const LEAK_SAMPLES_PER_WINDOW: u32 = 30;
+/// Drops smaller than this don't break a monotonic trend, allocators return a little now and then
+const LEAK_TOLERANCE_MB: f64 = 0.5;
+
+/// A process whose RSS only went up over the window, faster than the configured slope
+#[derive(Debug, Clone)]
+pub struct LeakSuspect {
+    pub pid: Pid,
+    pub name: String,
+    /// Least squares fit of the samples
+    pub slope_mb_per_min: f64,
+    pub growth_mb: f64,
+    pub memory_mb: f64,
+    /// Time covered by the samples
+    pub span: Duration,
+}
+
+/// Samples every process' RSS over a few minutes and flags the ones growing steadily
+#[derive(Debug)]
+pub struct LeakDetector {
+    pub window: Duration,
+    pub min_slope_mb_per_min: f64,
+    samples: HashMap<Pid, (String, VecDeque<(Instant, f64)>)>,
+    last_sample: Option<Instant>,
+    /// Fastest growing first
+    pub suspects: Vec<LeakSuspect>,
+}
+
+impl LeakDetector {
+    pub fn new(window: Duration, min_slope_mb_per_min: f64) -> Self {
+        Self {
+            window,
+            min_slope_mb_per_min,
+            samples: HashMap::new(),
+            last_sample: None,
+            suspects: Vec::new(),
+        }
+    }
+
+    fn sample_interval(&self) -> Duration {
+        self.window / LEAK_SAMPLES_PER_WINDOW
+    }
+
+    /// Called on every process refresh, only samples once per interval
+    pub fn update(&mut self, processes: &[Process]) {
+        let now = Instant::now();
+        if self
+            .last_sample
+            .is_some_and(|last| now.duration_since(last) < self.sample_interval())
+        {
+            return;
+        }
+        self.last_sample = Some(now);
+
+        let mut samples = HashMap::with_capacity(processes.len());
+        for process in processes {
+            let (_, mut history) = self
+                .samples
+                .remove(&process.pid)
+                .unwrap_or_else(|| (String::new(), VecDeque::new()));
+            history.push_back((now, process.memory_mb));
+            while history
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) > self.window)
+            {
+                history.pop_front();
+            }
+            samples.insert(process.pid, (process.name.clone(), history));
+        }
+        self.samples = samples;
+
+        let known: HashSet<Pid> = self.suspects.iter().map(|s| s.pid).collect();
+        self.suspects = self
+            .samples
+            .iter()
+            .filter_map(|(pid, (name, history))| self.suspect(*pid, name, history))
+            .collect();
+        self.suspects.sort_by(|a, b| {
+            b.slope_mb_per_min
+                .partial_cmp(&a.slope_mb_per_min)
+                .unwrap_or(Ordering::Equal)
+        });
+        for suspect in self.suspects.iter().filter(|s| !known.contains(&s.pid)) {
+            tracing::warn!(
+                "possible leak: {} ({}) grew {:.1}MB in {}s, {:.1}MB/min",
+                suspect.name,
+                suspect.pid,
+                suspect.growth_mb,
+                suspect.span.as_secs(),
+                suspect.slope_mb_per_min
+            );
+        }
+    }
+
+    fn suspect(
+        &self,
+        pid: Pid,
+        name: &str,
+        history: &VecDeque<(Instant, f64)>,
+    ) -> Option<LeakSuspect> {
+        let (first_at, first) = *history.front()?;
+        let (last_at, last) = *history.back()?;
+        let span = last_at.duration_since(first_at);
+        // Half a window before judging, a process that just started always grows
+        if span < self.window / 2 {
+            return None;
+        }
+        let monotonic = history
+            .iter()
+            .zip(history.iter().skip(1))
+            .all(|((_, before), (_, after))| *after >= before - LEAK_TOLERANCE_MB);
+        if !monotonic || last <= first {
+            return None;
+        }
+
+        let minutes = |at: Instant| at.duration_since(first_at).as_secs_f64() / 60.0;
+        let slope = history::slope(history.iter().map(|(at, mb)| (minutes(*at), *mb)))?;
+        (slope >= self.min_slope_mb_per_min).then(|| LeakSuspect {
+            pid,
+            name: name.to_string(),
+            slope_mb_per_min: slope,
+            growth_mb: last - first,
+            memory_mb: last,
+            span,
+        })
+    }
+}
+
+/// Lifecycle events kept for the log panel
+const LIFECYCLE_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleKind {
+    Started,
+    Exited,
+}
+
+#[derive(Debug, Clone)]
+pub struct LifecycleEvent {
+    pub time: SystemTime,
+    pub pid: Pid,
+    pub name: String,
+    pub kind: LifecycleKind,
+}
+
+impl fmt::Display for LifecycleEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match self.kind {
+            LifecycleKind::Started => "started",
+            LifecycleKind::Exited => "exited",
+        };
+        write!(f, "PID {} {} {}", self.pid, self.name, verb)
+    }
+}
+
+/// Finds processes that started or exited between two refreshes by diffing PID sets.
+///
+/// Processes living shorter than one refresh are never seen.
+#[derive(Debug, Default)]
+pub struct LifecycleTracker {
+    known: Option<HashMap<Pid, String>>,
+    /// Newest last
+    pub events: VecDeque<LifecycleEvent>,
+    log_file: Option<File>,
+}
+
+impl LifecycleTracker {
+    /// Also append every event to `path`
+    pub fn set_log_file(&mut self, path: &Path) -> io::Result<()> {
+        self.log_file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        Ok(())
+    }
+
+    pub fn update(&mut self, processes: &[Process]) {
+        let current: HashMap<Pid, String> =
+            processes.iter().map(|p| (p.pid, p.name.clone())).collect();
+        // The first refresh is the starting point, not a burst of "started" events
+        let Some(known) = self.known.take() else {
+            self.known = Some(current);
+            return;
+        };
+
+        let time = SystemTime::now();
+        let mut events: Vec<LifecycleEvent> = Vec::new();
+        // Only PIDs are compared, kernel workers rename themselves all the time
+        for (pid, name) in &known {
+            if !current.contains_key(pid) {
+                events.push(LifecycleEvent {
+                    time,
+                    pid: *pid,
+                    name: name.clone(),
+                    kind: LifecycleKind::Exited,
+                });
+            }
+        }
+        for (pid, name) in &current {
+            if !known.contains_key(pid) {
+                events.push(LifecycleEvent {
+                    time,
+                    pid: *pid,
+                    name: name.clone(),
+                    kind: LifecycleKind::Started,
+                });
+            }
+        }
+        events.sort_by_key(|event| event.pid);
+        self.known = Some(current);
+
+        for event in events {
+            if let Some(file) = &mut self.log_file {
+                // Losing the log file shouldn't take the monitor down, just stop writing
+                if writeln!(file, "{} {}", format_timestamp(event.time, true), event).is_err() {
+                    self.log_file = None;
+                }
+            }
+            self.events.push_back(event);
+            if self.events.len() > LIFECYCLE_LOG_CAPACITY {
+                self.events.pop_front();
+            }
+        }
+    }
+}
+
+/// Local time as "HH:MM:SS", with the date in front when `with_date` is set
+pub fn format_timestamp(time: SystemTime, with_date: bool) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call
+    unsafe { libc::localtime_r(&secs, &mut tm) };
+    let clock = format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec);
+    if with_date {
+        format!(
+            "{}-{:02}-{:02} {}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            clock
+        )
+    } else {
+        clock
+    }
+}
+
+/// Packaging format a process was launched from, with its app ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sandbox {
+    Flatpak(String),
+    Snap(String),
+    AppImage(String),
+}
+
+impl Sandbox {
+    /// Inverse of the Display impl, e.g. "snap:firefox"
+    pub fn parse(value: &str) -> Option<Self> {
+        let (kind, id) = value.split_once(':')?;
+        let id = id.to_string();
+        match kind {
+            "flatpak" => Some(Sandbox::Flatpak(id)),
+            "snap" => Some(Sandbox::Snap(id)),
+            "appimage" => Some(Sandbox::AppImage(id)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Sandbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sandbox::Flatpak(id) => write!(f, "flatpak:{}", id),
+            Sandbox::Snap(id) => write!(f, "snap:{}", id),
+            Sandbox::AppImage(id) => write!(f, "appimage:{}", id),
+        }
+    }
 }
 
 impl fmt::Display for Process {
@@ -34,6 +508,7 @@ impl fmt::Display for Process {
             ProcessStatus::Zombie => "Zombie",
             ProcessStatus::Dead => "Dead",
             ProcessStatus::Stop => "Stopped",
+            ProcessStatus::UninterruptibleDiskSleep => "DiskSlp",
             _ => "Unknown",
         };
 
@@ -52,37 +527,1406 @@ impl fmt::Display for Process {
             self.memory_mb,
             status_str,
             parent_str
-        )
+        )?;
+
+        if let Some(sandbox) = &self.sandbox {
+            write!(f, " | Origin: {}", sandbox)?;
+        }
+        Ok(())
     }
 }
 
 pub fn get_all_processes() -> Vec<Process> {
     let mut system = System::new_all();
     system.refresh_all();
+    let users = Users::new_with_refreshed_list();
     system
         .processes()
         .iter()
-        .map(|(pid, process)| Process {
-            pid: *pid,
-            name: process.name().to_string_lossy().into_owned(),
-            cpu_usage: process.cpu_usage(),
-            memory_mb: (process.memory() as f64) / 1024.0 / 1024.0,
-            status: process.status(),
-            parent_pid: process.parent(),
+        .map(|(pid, process)| {
+            let (exe, exe_deleted) = process.exe().map_or((None, false), |exe| {
+                let (path, deleted) = executable_path(exe);
+                (Some(path), deleted)
+            });
+            Process {
+                pid: *pid,
+                name: process.name().to_string_lossy().into_owned(),
+                cpu_usage: process.cpu_usage(),
+                memory_mb: (process.memory() as f64) / 1024.0 / 1024.0,
+                status: platform::process_status(process),
+                parent_pid: process.parent(),
+                pgid: process_group_id(*pid),
+                sid: process.session_id(),
+                sandbox: detect_sandbox(*pid, process.environ()),
+                cpu_time_ms: process.accumulated_cpu_time(),
+                swap_bytes: platform::swap_bytes(*pid),
+                fd_count: None,
+                memory_delta_mb: None,
+                cpu_delta: None,
+                energy_nj: platform::energy_nj(*pid),
+                power_mw: None,
+                user: process.user_id().map(|uid| {
+                    users
+                        .get_user_by_id(uid)
+                        .map_or_else(|| uid.to_string(), |user| user.name().to_string())
+                }),
+                cmdline: process
+                    .cmd()
+                    .iter()
+                    .map(|arg| arg.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                thread_kind: process.thread_kind(),
+                pod: None,
+                event_counters: None,
+                event_rates: None,
+                cwd: process.cwd().map(|cwd| cwd.to_string_lossy().into_owned()),
+                exe,
+                exe_deleted,
+            }
+        })
+        .collect()
+}
+
+/// Linux appends " (deleted)" to the exe link once the file is unlinked, which
+/// is also what an upgrade replacing the binary does. Elsewhere only a missing
+/// file can be noticed.
+fn executable_path(exe: &Path) -> (String, bool) {
+    let path = exe.to_string_lossy();
+    if cfg!(target_os = "linux") {
+        match path.strip_suffix(" (deleted)") {
+            Some(path) => (path.to_string(), true),
+            None => (path.into_owned(), false),
+        }
+    } else {
+        (path.into_owned(), !exe.exists())
+    }
+}
+
+/// Process list as a [`Sampler`], for use without the TUI
+#[derive(Debug, Default)]
+pub struct ProcessInfo {
+    pub processes: Vec<Process>,
+}
+
+impl Sampler for ProcessInfo {
+    type Snapshot = Vec<ProcessSnapshot>;
+
+    fn new() -> Self {
+        Self {
+            processes: get_all_processes(),
+        }
+    }
+
+    fn update(&mut self) {
+        self.processes = get_all_processes();
+    }
+
+    fn snapshot(&self) -> Vec<ProcessSnapshot> {
+        self.processes.iter().map(ProcessSnapshot::from).collect()
+    }
+}
+
+/// Figure out whether a process runs inside Flatpak, Snap or an AppImage.
+///
+/// The environment is checked first since it carries the app ID directly,
+/// but it's only readable for our own processes, so fall back to the cgroup
+/// path which both Flatpak and snapd name after the app.
+pub fn detect_sandbox(pid: Pid, environ: &[std::ffi::OsString]) -> Option<Sandbox> {
+    for var in environ {
+        let var = var.to_string_lossy();
+        if let Some(id) = var.strip_prefix("FLATPAK_ID=") {
+            return Some(Sandbox::Flatpak(id.to_string()));
+        }
+        if let Some(name) = var.strip_prefix("SNAP_NAME=") {
+            return Some(Sandbox::Snap(name.to_string()));
+        }
+        if let Some(path) = var.strip_prefix("APPIMAGE=") {
+            let name = Path::new(path)
+                .file_stem()
+                .map_or(path.to_string(), |stem| stem.to_string_lossy().into_owned());
+            return Some(Sandbox::AppImage(name));
+        }
+    }
+
+    let cgroup = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let unit = cgroup.lines().last()?.rsplit('/').next()?;
+
+    // e.g. app-flatpak-org.mozilla.firefox-12345.scope
+    if let Some(rest) = unit.strip_prefix("app-flatpak-") {
+        let id = rest.rsplit_once('-').map_or(rest, |(id, _)| id);
+        return Some(Sandbox::Flatpak(id.to_string()));
+    }
+    // e.g. snap.firefox.firefox-1234.scope
+    if let Some(rest) = unit.strip_prefix("snap.") {
+        let name = rest.split('.').next()?;
+        return Some(Sandbox::Snap(name.to_string()));
+    }
+    None
+}
+
+#[cfg(unix)]
+pub fn process_group_id(pid: Pid) -> Option<Pid> {
+    // SAFETY: getpgid has no memory safety requirements, it just fails for unknown pids
+    let pgid = unsafe { libc::getpgid(pid.as_u32() as libc::pid_t) };
+    (pgid >= 0).then(|| Pid::from_u32(pgid as u32))
+}
+
+#[cfg(not(unix))]
+pub fn process_group_id(_pid: Pid) -> Option<Pid> {
+    None
+}
+
+/// Send SIGTERM to every process in the group, e.g. a whole shell pipeline
+#[cfg(unix)]
+pub fn kill_process_group(pgid: Pid) -> std::io::Result<()> {
+    // SAFETY: killpg only takes plain integers
+    if unsafe { libc::killpg(pgid.as_u32() as libc::pid_t, libc::SIGTERM) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn kill_process_group(_pgid: Pid) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "process groups are not supported on this platform",
+    ))
+}
+
+/// Send SIGTERM to each pid in order, returning the ones that failed.
+/// Processes that already exited are not counted as failures.
+#[cfg(unix)]
+pub fn kill_processes(pids: &[Pid]) -> Vec<(Pid, std::io::Error)> {
+    pids.iter()
+        .filter_map(|pid| {
+            // SAFETY: kill only takes plain integers
+            if unsafe { libc::kill(pid.as_u32() as libc::pid_t, libc::SIGTERM) } == 0 {
+                return None;
+            }
+            let error = std::io::Error::last_os_error();
+            (error.raw_os_error() != Some(libc::ESRCH)).then_some((*pid, error))
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+pub fn kill_processes(pids: &[Pid]) -> Vec<(Pid, std::io::Error)> {
+    pids.iter()
+        .map(|pid| {
+            (
+                *pid,
+                std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "signals are not supported on this platform",
+                ),
+            )
         })
         .collect()
 }
 
+/// Why killing a process could take down the system or the session m-as runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Critical {
+    Init,
+    KernelThread,
+    /// The shell (or whatever else) that started the monitor
+    MonitorParent,
+}
+
+impl Critical {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Critical::Init => "is init, killing it takes the system down",
+            Critical::KernelThread => "is a kernel thread",
+            Critical::MonitorParent => "started m-as, killing it ends your session",
+        }
+    }
+}
+
+/// Processes a kill must not hit by accident, None for everything else
+pub fn critical_process(process: &Process) -> Option<Critical> {
+    if process.pid.as_u32() == 1 {
+        return Some(Critical::Init);
+    }
+    // kthreadd (PID 2) is the parent of every kernel thread, none of them has a command line
+    let kthreadd = Pid::from_u32(2);
+    if cfg!(target_os = "linux")
+        && (process.pid == kthreadd || process.parent_pid == Some(kthreadd))
+        && process.cmdline.is_empty()
+    {
+        return Some(Critical::KernelThread);
+    }
+    if monitor_parent() == Some(process.pid) {
+        return Some(Critical::MonitorParent);
+    }
+    None
+}
+
+#[cfg(unix)]
+fn monitor_parent() -> Option<Pid> {
+    Some(Pid::from_u32(std::os::unix::process::parent_id()))
+}
+
+#[cfg(not(unix))]
+fn monitor_parent() -> Option<Pid> {
+    None
+}
+
+/// Nice value (-20 to 19) of a process
+#[cfg(target_os = "linux")]
+pub fn get_nice(pid: Pid) -> Option<i32> {
+    // getpriority can legitimately return -1, errno tells errors apart
+    // SAFETY: __errno_location returns a valid pointer to this thread's errno
+    unsafe { *libc::__errno_location() = 0 };
+    // SAFETY: getpriority only takes plain integers
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid.as_u32() as libc::id_t) };
+    (nice != -1 || std::io::Error::last_os_error().raw_os_error() == Some(0)).then_some(nice)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_nice(_pid: Pid) -> Option<i32> {
+    None
+}
+
+/// Lowering the nice value (raising priority) needs root or CAP_SYS_NICE
+#[cfg(unix)]
+pub fn set_nice(pid: Pid, nice: i32) -> std::io::Result<()> {
+    // SAFETY: setpriority only takes plain integers
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid.as_u32() as libc::id_t, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn set_nice(_pid: Pid, _nice: i32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "renice is not supported on this platform",
+    ))
+}
+
+/// Processes belonging to the given process group
+pub fn group_members(processes: &[Process], pgid: Pid) -> Vec<&Process> {
+    processes
+        .iter()
+        .filter(|process| process.pgid == Some(pgid))
+        .collect()
+}
+
+/// CPUs the process is allowed to run on, like `taskset -p`
+#[cfg(target_os = "linux")]
+pub fn get_affinity(pid: Pid) -> io::Result<Vec<usize>> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: set is a properly sized, initialized cpu_set_t
+    let result = unsafe {
+        libc::sched_getaffinity(
+            pid.as_u32() as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &mut set,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((0..libc::CPU_SETSIZE as usize)
+        // SAFETY: cpu is below CPU_SETSIZE
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_affinity(_pid: Pid) -> io::Result<Vec<usize>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU affinity is not supported on this platform",
+    ))
+}
+
+/// Pin the process to `cpus`, like `taskset -p -c`
+#[cfg(target_os = "linux")]
+pub fn set_affinity(pid: Pid, cpus: &[usize]) -> io::Result<()> {
+    if cpus.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "at least one CPU is required",
+        ));
+    }
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("CPU {} is out of range", cpu),
+            ));
+        }
+        // SAFETY: cpu was checked against CPU_SETSIZE
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: set is a properly sized, initialized cpu_set_t
+    let result = unsafe {
+        libc::sched_setaffinity(
+            pid.as_u32() as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &set,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_affinity(_pid: Pid, _cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU affinity is not supported on this platform",
+    ))
+}
+
+// From linux/ioprio.h
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+const IOPRIO_LEVEL_MASK: u32 = (1 << IOPRIO_CLASS_SHIFT) - 1;
+
+/// I/O scheduling class, what `ionice -c` sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoClass {
+    /// Never set, the kernel derives a best-effort level from the nice value
+    None,
+    /// Served before everything else, needs CAP_SYS_ADMIN
+    Realtime,
+    BestEffort,
+    /// Only gets disk time when nobody else wants it
+    Idle,
+}
+
+/// I/O class and level (0 highest to 7 lowest) of a process, like `ionice -p`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: u8,
+}
+
+impl IoPriority {
+    fn from_raw(raw: u32) -> Self {
+        let class = match raw >> IOPRIO_CLASS_SHIFT {
+            1 => IoClass::Realtime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            _ => IoClass::None,
+        };
+        Self {
+            class,
+            level: (raw & IOPRIO_LEVEL_MASK) as u8,
+        }
+    }
+
+    fn to_raw(self) -> u32 {
+        let class = match self.class {
+            IoClass::None => 0,
+            IoClass::Realtime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        };
+        class << IOPRIO_CLASS_SHIFT | self.level as u32
+    }
+
+    /// The short form `parse` accepts: "rt/0", "be/4", "idle"
+    pub fn short(&self) -> String {
+        match self.class {
+            IoClass::None => "none".to_string(),
+            IoClass::Realtime => format!("rt/{}", self.level),
+            IoClass::BestEffort => format!("be/{}", self.level),
+            IoClass::Idle => "idle".to_string(),
+        }
+    }
+
+    /// "rt/0", "be/4", "idle" or "none", the level defaults to 4
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (class, level) = match value.trim().split_once('/') {
+            Some((class, level)) => (class, Some(level)),
+            None => (value.trim(), None),
+        };
+        let class = match class {
+            "rt" | "realtime" => IoClass::Realtime,
+            "be" | "best-effort" => IoClass::BestEffort,
+            "idle" => IoClass::Idle,
+            "none" => IoClass::None,
+            other => return Err(format!("unknown I/O class '{}' (rt, be, idle)", other)),
+        };
+        let level = match level {
+            Some(level) => level
+                .parse()
+                .ok()
+                .filter(|level| *level <= 7)
+                .ok_or_else(|| format!("I/O level must be 0-7, got '{}'", level))?,
+            None => 4,
+        };
+        // Idle and none have no levels
+        let level = if matches!(class, IoClass::Idle | IoClass::None) {
+            0
+        } else {
+            level
+        };
+        Ok(Self { class, level })
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.class {
+            IoClass::None => write!(f, "none (follows nice)"),
+            IoClass::Realtime => write!(f, "realtime, level {}", self.level),
+            IoClass::BestEffort => write!(f, "best-effort, level {}", self.level),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_io_priority(pid: Pid) -> io::Result<IoPriority> {
+    // SAFETY: ioprio_get only takes plain integers
+    let raw = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_get,
+            IOPRIO_WHO_PROCESS,
+            pid.as_u32() as libc::c_long,
+        )
+    };
+    if raw < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(IoPriority::from_raw(raw as u32))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_io_priority(_pid: Pid) -> io::Result<IoPriority> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "I/O priorities are not supported on this platform",
+    ))
+}
+
+/// Like `ionice -p`. Realtime needs CAP_SYS_ADMIN, other users' processes CAP_SYS_NICE.
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(pid: Pid, priority: IoPriority) -> io::Result<()> {
+    // SAFETY: ioprio_set only takes plain integers
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            pid.as_u32() as libc::c_long,
+            priority.to_raw() as libc::c_long,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_io_priority(_pid: Pid, _priority: IoPriority) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "I/O priorities are not supported on this platform",
+    ))
+}
+
+// == OOM score ==
+
+/// Range of oom_score_adj, -1000 exempts a process from the OOM killer
+pub const OOM_SCORE_ADJ_RANGE: std::ops::RangeInclusive<i32> = -1000..=1000;
+
+/// How likely the OOM killer picks the process, higher goes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OomScore {
+    /// 0-2000, mostly the share of memory the process uses, plus the adjustment
+    pub score: u32,
+    pub adj: i32,
+}
+
+#[cfg(target_os = "linux")]
+pub fn oom_score(pid: Pid) -> io::Result<OomScore> {
+    let read = |file: &str| -> io::Result<String> {
+        Ok(fs::read_to_string(format!("/proc/{}/{}", pid, file))?
+            .trim()
+            .to_string())
+    };
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+    Ok(OomScore {
+        score: read("oom_score")?.parse().map_err(invalid)?,
+        adj: read("oom_score_adj")?.parse().map_err(invalid)?,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn oom_score(_pid: Pid) -> io::Result<OomScore> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "OOM scores are not supported on this platform",
+    ))
+}
+
+/// Raising it works on your own processes, lowering it needs CAP_SYS_RESOURCE
+#[cfg(target_os = "linux")]
+pub fn set_oom_score_adj(pid: Pid, adj: i32) -> io::Result<()> {
+    fs::write(format!("/proc/{}/oom_score_adj", pid), adj.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_oom_score_adj(_pid: Pid, _adj: i32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "OOM scores are not supported on this platform",
+    ))
+}
+
+// == Per-user aggregation ==
+
+#[derive(Debug, Clone)]
+pub struct UserUsage {
+    pub user: String,
+    pub processes: usize,
+    pub cpu_usage: f32,
+    pub memory_mb: f64,
+}
+
+/// CPU, memory and process count summed per account, busiest first
+pub fn usage_by_user(processes: &[Process]) -> Vec<UserUsage> {
+    let mut by_user: HashMap<&str, UserUsage> = HashMap::new();
+    for process in processes {
+        let user = process.user.as_deref().unwrap_or("?");
+        let usage = by_user.entry(user).or_insert_with(|| UserUsage {
+            user: user.to_string(),
+            processes: 0,
+            cpu_usage: 0.0,
+            memory_mb: 0.0,
+        });
+        usage.processes += 1;
+        usage.cpu_usage += process.cpu_usage;
+        usage.memory_mb += process.memory_mb;
+    }
+
+    let mut users: Vec<UserUsage> = by_user.into_values().collect();
+    users.sort_by(|a, b| {
+        b.cpu_usage
+            .total_cmp(&a.cpu_usage)
+            .then(b.memory_mb.total_cmp(&a.memory_mb))
+    });
+    users
+}
+
+// == Process trees ==
+
+/// Every descendant of `root`, children before grandchildren
+pub fn descendants(processes: &[Process], root: Pid) -> Vec<&Process> {
+    let mut children: HashMap<Pid, Vec<&Process>> = HashMap::new();
+    for process in processes {
+        if let Some(parent) = process.parent_pid {
+            children.entry(parent).or_default().push(process);
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([root]);
+    let mut visited = HashSet::from([root]);
+    while let Some(pid) = queue.pop_front() {
+        for child in children.get(&pid).into_iter().flatten() {
+            // Guards against loops from PIDs reused between two refreshes
+            if visited.insert(child.pid) {
+                found.push(*child);
+                queue.push_back(child.pid);
+            }
+        }
+    }
+    found
+}
+
+/// `root` and its descendants ordered so every child comes before its parent,
+/// the order to kill a tree in without parents respawning or reaping mid-way
+pub fn kill_order(processes: &[Process], root: Pid) -> Vec<&Process> {
+    let mut order = descendants(processes, root);
+    // descendants() is breadth first, reversing puts the deepest level first
+    order.reverse();
+    order.extend(processes.iter().find(|process| process.pid == root));
+    order
+}
+
+/// Combined usage of a process and its descendants
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TreeUsage {
+    pub processes: usize,
+    /// Descendants that didn't exist yet when following started
+    pub spawned: usize,
+    pub cpu_usage: f32,
+    pub memory_mb: f64,
+}
+
+/// A process followed as a unit with all of its children, so forking servers
+/// (postgres, nginx, ...) are tracked as a whole, including workers spawned later
+#[derive(Debug, Clone)]
+pub struct FollowedTree {
+    pub root: Pid,
+    initial: HashSet<Pid>,
+}
+
+impl FollowedTree {
+    pub fn new(processes: &[Process], root: Pid) -> Self {
+        Self {
+            root,
+            initial: descendants(processes, root).iter().map(|p| p.pid).collect(),
+        }
+    }
+
+    /// None once the root process is gone
+    pub fn usage(&self, processes: &[Process]) -> Option<TreeUsage> {
+        let root = processes.iter().find(|p| p.pid == self.root)?;
+        let mut usage = TreeUsage {
+            processes: 1,
+            spawned: 0,
+            cpu_usage: root.cpu_usage,
+            memory_mb: root.memory_mb,
+        };
+        for child in descendants(processes, self.root) {
+            usage.processes += 1;
+            if !self.initial.contains(&child.pid) {
+                usage.spawned += 1;
+            }
+            usage.cpu_usage += child.cpu_usage;
+            usage.memory_mb += child.memory_mb;
+        }
+        Some(usage)
+    }
+}
+
+// == Environment ==
+
+// Name fragments of variables whose values are hidden until revealed
+const SENSITIVE_VAR_MARKERS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "API_KEY",
+    "PRIVATE_KEY",
+];
+
+/// Environment of a single process as (name, value), sorted by name. Empty when
+/// it isn't readable, which is the case for other users' processes.
+pub fn environment(pid: Pid) -> Vec<(String, String)> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_environ(UpdateKind::Always),
+    );
+    let Some(process) = system.process(pid) else {
+        return Vec::new();
+    };
+
+    let mut vars: Vec<(String, String)> = process
+        .environ()
+        .iter()
+        .map(|var| {
+            let var = var.to_string_lossy();
+            match var.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => (var.into_owned(), String::new()),
+            }
+        })
+        .collect();
+    vars.sort();
+    vars
+}
+
+pub fn is_sensitive_var(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SENSITIVE_VAR_MARKERS
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+// == Containers ==
+
+/// Namespace inodes from /proc/<pid>/ns, processes in one container share them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Namespaces {
+    pub net: Option<u64>,
+    pub pid: Option<u64>,
+    pub mnt: Option<u64>,
+}
+
+impl Namespaces {
+    /// Every link unreadable is an error, other users' processes need ptrace access
+    pub fn read(pid: Pid) -> io::Result<Self> {
+        let read = |kind: &str| -> io::Result<u64> {
+            // "net:[4026531840]"
+            let link = fs::read_link(format!("/proc/{}/ns/{}", pid, kind))?;
+            link.to_string_lossy()
+                .rsplit_once('[')
+                .and_then(|(_, inode)| inode.trim_end_matches(']').parse().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unexpected ns link"))
+        };
+        let (net, pid_ns, mnt) = (read("net"), read("pid"), read("mnt"));
+        if let (Err(e), Err(_), Err(_)) = (&net, &pid_ns, &mnt) {
+            return Err(io::Error::new(e.kind(), e.to_string()));
+        }
+        Ok(Self {
+            net: net.ok(),
+            pid: pid_ns.ok(),
+            mnt: mnt.ok(),
+        })
+    }
+}
+
+/// A container runtime's ID for a process, from its cgroup path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    /// "docker", "containerd", "cri-o", "podman" or "kubepods" when only the pod is known
+    pub runtime: &'static str,
+    pub id: String,
+    /// Kubernetes pod UID, from kubepods cgroups
+    pub pod_uid: Option<String>,
+}
+
+impl Container {
+    /// The first 12 characters, like `docker ps`
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(12)]
+    }
+}
+
+pub fn container(pid: Pid) -> Option<Container> {
+    parse_container(&fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?)
+}
+
+/// Handles both cgroup drivers, e.g. (cgroupfs)
+/// `/kubepods/burstable/pod<uid>/<id>` and (systemd)
+/// `/kubepods.slice/kubepods-pod<uid>.slice/cri-containerd-<id>.scope`
+pub fn parse_container(cgroup: &str) -> Option<Container> {
+    const PREFIXES: [(&str, &str); 5] = [
+        ("docker-", "docker"),
+        ("cri-containerd-", "containerd"),
+        ("containerd-", "containerd"),
+        ("crio-", "cri-o"),
+        ("libpod-", "podman"),
+    ];
+    let is_id = |text: &str| text.len() == 64 && text.bytes().all(|b| b.is_ascii_hexdigit());
+
+    for line in cgroup.lines() {
+        let path = line.splitn(3, ':').nth(2)?;
+        let mut runtime = None;
+        let mut id = None;
+        let mut pod_uid = None;
+        let mut parent = "";
+        let kubepods = path.contains("kubepods");
+        for segment in path.split('/') {
+            let name = segment.trim_end_matches(".scope");
+            if let Some((prefix, name)) = PREFIXES
+                .iter()
+                .find_map(|(prefix, runtime)| Some((*runtime, name.strip_prefix(prefix)?)))
+                .filter(|(_, name)| is_id(name))
+            {
+                runtime = Some(prefix);
+                id = Some(name.to_string());
+            } else if is_id(name) {
+                runtime = Some(match parent {
+                    "docker" => "docker",
+                    "libpod_parent" | "machine.slice" => "podman",
+                    _ => "containerd",
+                });
+                id = Some(name.to_string());
+            }
+            // kubepods-burstable-pod1234_abcd.slice or pod1234-abcd
+            if kubepods {
+                if let Some((_, uid)) = segment.trim_end_matches(".slice").rsplit_once("pod") {
+                    if uid.len() >= 32 {
+                        pod_uid = Some(uid.replace('_', "-"));
+                    }
+                }
+            }
+            parent = segment;
+        }
+        match (runtime, id, pod_uid) {
+            (Some(runtime), Some(id), pod_uid) => {
+                return Some(Container {
+                    runtime,
+                    id,
+                    pod_uid,
+                })
+            }
+            (None, None, Some(pod_uid)) => {
+                return Some(Container {
+                    runtime: "kubepods",
+                    id: String::new(),
+                    pod_uid: Some(pod_uid),
+                })
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// == Open files ==
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdKind {
+    File,
+    Directory,
+    Socket,
+    Pipe,
+    Device,
+    /// eventfd, epoll, inotify, ...
+    AnonInode,
+    Other,
+}
+
+impl FdKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FdKind::File => "file",
+            FdKind::Directory => "dir",
+            FdKind::Socket => "socket",
+            FdKind::Pipe => "pipe",
+            FdKind::Device => "device",
+            FdKind::AnonInode => "anon",
+            FdKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenFile {
+    pub fd: u32,
+    /// Link target, e.g. "/var/log/syslog" or "socket:[12345]"
+    pub path: String,
+    pub kind: FdKind,
+    /// "r", "w" or "rw", empty when fdinfo isn't readable
+    pub mode: &'static str,
+}
+
+// Each platform reads these differently, see the platform module
+pub use crate::platform::{
+    event_counters, fd_count, io_counters, open_files, port_sockets, swap_bytes, EventCounters,
+    PortSocket,
+};
+
+// == Who owns a port or file ==
+
+/// What `:who-owns` looks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Owned {
+    Port(u16),
+    Path(PathBuf),
+}
+
+impl fmt::Display for Owned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Owned::Port(port) => write!(f, "port {}", port),
+            Owned::Path(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A process holding the port or file, and through what
+#[derive(Debug, Clone)]
+pub struct Owner {
+    pub pid: Pid,
+    pub name: String,
+    /// "fd 6 tcp listening", "fd 3", "cwd" or "exe"
+    pub via: String,
+}
+
+/// Processes that have `target` open, and how many fd directories weren't
+/// readable (other users' processes without CAP_SYS_PTRACE)
+pub fn who_owns(processes: &[Process], target: &Owned) -> io::Result<(Vec<Owner>, usize)> {
+    let sockets: HashMap<u64, PortSocket> = match target {
+        Owned::Port(port) => port_sockets(*port)?
+            .into_iter()
+            .map(|socket| (socket.inode, socket))
+            .collect(),
+        Owned::Path(_) => HashMap::new(),
+    };
+    if matches!(target, Owned::Port(_)) && sockets.is_empty() {
+        return Ok((Vec::new(), 0));
+    }
+
+    let mut owners = Vec::new();
+    let mut unreadable = 0;
+    for process in processes.iter().filter(|p| p.thread_kind.is_none()) {
+        let mut found = |via: String| {
+            owners.push(Owner {
+                pid: process.pid,
+                name: process.name.clone(),
+                via,
+            })
+        };
+        if let Owned::Path(path) = target {
+            let path = path.to_string_lossy();
+            if process.cwd.as_deref() == Some(path.as_ref()) {
+                found("cwd".to_string());
+            }
+            if process.exe.as_deref() == Some(path.as_ref()) {
+                found("exe".to_string());
+            }
+        }
+        let files = match open_files(process.pid) {
+            Ok(files) => files,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                unreadable += 1;
+                continue;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return Err(e),
+            // Exited since the last refresh
+            Err(_) => continue,
+        };
+        for file in files {
+            match target {
+                Owned::Port(_) => {
+                    let socket = file
+                        .path
+                        .strip_prefix("socket:[")
+                        .and_then(|inode| inode.strip_suffix(']'))
+                        .and_then(|inode| inode.parse().ok())
+                        .and_then(|inode: u64| sockets.get(&inode));
+                    if let Some(socket) = socket {
+                        found(format!(
+                            "fd {} {}{}",
+                            file.fd,
+                            socket.protocol,
+                            if socket.listening { " listening" } else { "" }
+                        ));
+                    }
+                }
+                Owned::Path(path) => {
+                    if Path::new(&file.path) == path {
+                        found(format!("fd {}", file.fd));
+                    }
+                }
+            }
+        }
+    }
+    Ok((owners, unreadable))
+}
+
+/// `EventCounters` per second between two refreshes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EventRates {
+    pub minor_faults: f64,
+    pub major_faults: f64,
+    pub voluntary_switches: f64,
+    pub involuntary_switches: f64,
+}
+
+impl EventRates {
+    fn between(old: EventCounters, new: EventCounters, secs: f64) -> Self {
+        let rate = |old: u64, new: u64| new.saturating_sub(old) as f64 / secs;
+        Self {
+            minor_faults: rate(old.minor_faults, new.minor_faults),
+            major_faults: rate(old.major_faults, new.major_faults),
+            voluntary_switches: rate(old.voluntary_switches, new.voluntary_switches),
+            involuntary_switches: rate(old.involuntary_switches, new.involuntary_switches),
+        }
+    }
+}
+
+// == Wait trace ==
+
+pub use crate::platform::WaitState;
+
+/// Poor man's strace: where a process waits, sampled over and over and counted,
+/// which shows what it's blocked on without attaching to it
+#[derive(Debug)]
+pub struct WaitTrace {
+    pub pid: Pid,
+    pub samples: usize,
+    /// "futex @ futex_wait_queue" and how many samples saw it
+    counts: HashMap<String, usize>,
+    pub last: Option<WaitState>,
+    /// Why the last sample failed, the trace stops counting until it works again
+    pub error: Option<io::Error>,
+}
+
+impl WaitTrace {
+    pub fn new(pid: Pid) -> Self {
+        let mut trace = Self {
+            pid,
+            samples: 0,
+            counts: HashMap::new(),
+            last: None,
+            error: None,
+        };
+        trace.sample();
+        trace
+    }
+
+    pub fn sample(&mut self) {
+        match platform::wait_state(self.pid) {
+            Ok(state) => {
+                let key = match (&state.syscall, &state.wchan) {
+                    (None, _) => "running".to_string(),
+                    (Some(syscall), Some(wchan)) => format!("{} @ {}", syscall, wchan),
+                    (Some(syscall), None) => syscall.clone(),
+                };
+                *self.counts.entry(key).or_default() += 1;
+                self.samples += 1;
+                self.last = Some(state);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// The most frequent waits with their share of the samples, most frequent first
+    pub fn top(&self, count: usize) -> Vec<(&str, f64)> {
+        let mut waits: Vec<(&str, usize)> = self
+            .counts
+            .iter()
+            .map(|(wait, seen)| (wait.as_str(), *seen))
+            .collect();
+        waits.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        waits
+            .into_iter()
+            .take(count)
+            .map(|(wait, seen)| (wait, seen as f64 / self.samples.max(1) as f64))
+            .collect()
+    }
+}
+
+// == Per-PID history ==
+
+/// Number of refreshes kept per process
+const PROCESS_HISTORY_CAPACITY: usize = 60;
+
+#[derive(Debug, Clone, Default)]
+pub struct ProcessSamples {
+    pub cpu: VecDeque<f32>,
+    pub memory_mb: VecDeque<f64>,
+    /// Previous energy reading and when it was taken
+    energy: Option<(u64, Instant)>,
+    pub power_mw: Option<f64>,
+    /// Previous fault and context switch reading, dropped while they aren't read
+    events: Option<(EventCounters, Instant)>,
+    pub event_rates: Option<EventRates>,
+}
+
+/// Recent CPU/memory samples of every live process, keyed by PID
+#[derive(Debug, Default)]
+pub struct ProcessHistory {
+    samples: HashMap<Pid, ProcessSamples>,
+}
+
+impl ProcessHistory {
+    /// Record the latest refresh and forget processes that exited
+    pub fn update(&mut self, processes: &[Process]) {
+        let mut samples = HashMap::with_capacity(processes.len());
+        let now = Instant::now();
+        for process in processes {
+            let mut entry = self.samples.remove(&process.pid).unwrap_or_default();
+            if let Some(energy) = process.energy_nj {
+                entry.power_mw = entry.energy.and_then(|(last, at)| {
+                    let secs = now.duration_since(at).as_secs_f64();
+                    // nJ/s is nW
+                    (secs > 0.0).then(|| energy.saturating_sub(last) as f64 / secs / 1e6)
+                });
+                entry.energy = Some((energy, now));
+            }
+            match process.event_counters {
+                Some(counters) => {
+                    entry.event_rates = entry.events.and_then(|(last, at)| {
+                        let secs = now.duration_since(at).as_secs_f64();
+                        (secs > 0.0).then(|| EventRates::between(last, counters, secs))
+                    });
+                    entry.events = Some((counters, now));
+                }
+                None => {
+                    entry.events = None;
+                    entry.event_rates = None;
+                }
+            }
+            entry.cpu.push_back(process.cpu_usage);
+            entry.memory_mb.push_back(process.memory_mb);
+            if entry.cpu.len() > PROCESS_HISTORY_CAPACITY {
+                entry.cpu.pop_front();
+                entry.memory_mb.pop_front();
+            }
+            samples.insert(process.pid, entry);
+        }
+        self.samples = samples;
+    }
+
+    pub fn get(&self, pid: Pid) -> Option<&ProcessSamples> {
+        self.samples.get(&pid)
+    }
+
+    /// Fill in each process' change since the previous refresh, after `update`
+    pub fn fill_deltas(&self, processes: &mut [Process]) {
+        fn last_change<T: Copy + std::ops::Sub<Output = T>>(samples: &VecDeque<T>) -> Option<T> {
+            let len = samples.len();
+            Some(*samples.get(len.checked_sub(1)?)? - *samples.get(len.checked_sub(2)?)?)
+        }
+        for process in processes {
+            let samples = self.get(process.pid);
+            process.memory_delta_mb = samples.and_then(|s| last_change(&s.memory_mb));
+            process.cpu_delta = samples.and_then(|s| last_change(&s.cpu));
+            process.power_mw = samples.and_then(|s| s.power_mw);
+            process.event_rates = samples.and_then(|s| s.event_rates);
+        }
+    }
+
+    /// Memory growth in MB over the retained history
+    pub fn memory_growth(&self, pid: Pid) -> f64 {
+        self.get(pid)
+            .and_then(|s| Some(s.memory_mb.back()? - s.memory_mb.front()?))
+            .unwrap_or(0.0)
+    }
+
+    /// CPU% change since the previous refresh
+    pub fn cpu_delta(&self, pid: Pid) -> f32 {
+        self.get(pid)
+            .and_then(|s| {
+                let len = s.cpu.len();
+                Some(s.cpu.get(len.checked_sub(1)?)? - s.cpu.get(len.checked_sub(2)?)?)
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+/// Samples kept for the selected process
+pub const SELECTED_HISTORY_CAPACITY: usize = 60;
+/// With the default refresh that's a minute of history
+const SELECTED_HISTORY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// CPU/memory of the process being looked at, at most one sample per second.
+/// ProcessHistory follows the refresh rate, at 250ms its 60 samples are 15s.
+#[derive(Debug, Default)]
+pub struct SelectedHistory {
+    pub pid: Option<Pid>,
+    pub cpu: VecDeque<f32>,
+    pub memory_mb: VecDeque<f64>,
+    /// CPU time at the previous sample and when it was taken
+    last: Option<(u64, Instant)>,
+}
+
+impl SelectedHistory {
+    /// Add the latest refresh, starting over when another process is selected
+    pub fn record(&mut self, process: &Process) {
+        if self.pid != Some(process.pid) {
+            *self = Self {
+                pid: Some(process.pid),
+                ..Self::default()
+            };
+        }
+        let now = Instant::now();
+        // From the CPU time between samples, which covers the whole second
+        // instead of whatever the last refresh saw
+        let cpu = match self.last {
+            Some((_, at)) if now.duration_since(at) < SELECTED_HISTORY_INTERVAL => return,
+            Some((cpu_time_ms, at)) => {
+                process.cpu_time_ms.saturating_sub(cpu_time_ms) as f32
+                    / now.duration_since(at).as_millis() as f32
+                    * 100.0
+            }
+            None => process.cpu_usage,
+        };
+        self.last = Some((process.cpu_time_ms, now));
+        self.cpu.push_back(cpu);
+        self.memory_mb.push_back(process.memory_mb);
+        if self.cpu.len() > SELECTED_HISTORY_CAPACITY {
+            self.cpu.pop_front();
+            self.memory_mb.pop_front();
+        }
+    }
+}
+
 // == Functions for sorting processes ==
 
-pub fn _sort_by_cpu(processes: &mut [Process]) {
-    processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap());
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    Memory,
+    Cpu,
+    /// Fastest growing memory over the per-PID history
+    MemoryGrowth,
+    /// Biggest CPU change since the last refresh
+    CpuDelta,
+    /// Total CPU time over the process' lifetime (TIME+)
+    CpuTime,
+    Swap,
 }
 
-pub fn sort_by_memory(processes: &mut [Process]) {
+impl SortKey {
+    pub fn next(&self) -> Self {
+        match self {
+            SortKey::Memory => SortKey::Cpu,
+            SortKey::Cpu => SortKey::MemoryGrowth,
+            SortKey::MemoryGrowth => SortKey::CpuDelta,
+            SortKey::CpuDelta => SortKey::CpuTime,
+            SortKey::CpuTime => SortKey::Swap,
+            SortKey::Swap => SortKey::Memory,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Memory => "memory",
+            SortKey::Cpu => "cpu",
+            SortKey::MemoryGrowth => "memory growth",
+            SortKey::CpuDelta => "cpu delta",
+            SortKey::CpuTime => "cpu time",
+            SortKey::Swap => "swap",
+        }
+    }
+
+    /// Look a key up by any of its `:sort` names
+    pub fn parse(name: &str) -> Option<Self> {
+        COMPARATORS
+            .iter()
+            .find(|(_, names, _)| names.contains(&name))
+            .map(|(key, _, _)| *key)
+    }
+
+    /// Primary `:sort` name of every key, for help and error messages
+    pub fn names() -> Vec<&'static str> {
+        COMPARATORS.iter().map(|(_, names, _)| names[0]).collect()
+    }
+
+    fn comparator(&self) -> Comparator {
+        COMPARATORS
+            .iter()
+            .find(|(key, _, _)| key == self)
+            .map(|(_, _, comparator)| *comparator)
+            .expect("every sort key has a comparator")
+    }
+}
+
+/// Orders two processes biggest first, `history` backs the growth and delta keys
+type Comparator = fn(&Process, &Process, &ProcessHistory) -> Ordering;
+
+/// Every sort key with the names `:sort` accepts (first one canonical) and its comparator
+const COMPARATORS: [(SortKey, &[&str], Comparator); 6] = [
+    (SortKey::Memory, &["memory", "mem"], |a, b, _| {
+        descending(a.memory_mb, b.memory_mb)
+    }),
+    (SortKey::Cpu, &["cpu"], |a, b, _| {
+        descending(a.cpu_usage, b.cpu_usage)
+    }),
+    (SortKey::MemoryGrowth, &["growth"], |a, b, history| {
+        descending(history.memory_growth(a.pid), history.memory_growth(b.pid))
+    }),
+    (SortKey::CpuDelta, &["delta"], |a, b, history| {
+        descending(
+            history.cpu_delta(a.pid).abs(),
+            history.cpu_delta(b.pid).abs(),
+        )
+    }),
+    (SortKey::CpuTime, &["time"], |a, b, _| {
+        b.cpu_time_ms.cmp(&a.cpu_time_ms)
+    }),
+    (SortKey::Swap, &["swap"], |a, b, _| {
+        b.swap_bytes.cmp(&a.swap_bytes)
+    }),
+];
+
+/// NaN compares equal instead of panicking
+fn descending<T: PartialOrd>(a: T, b: T) -> Ordering {
+    b.partial_cmp(&a).unwrap_or(Ordering::Equal)
+}
+
+/// Stable sort by `key`, ties broken by `then` when given
+pub fn sort_processes(
+    processes: &mut [Process],
+    key: SortKey,
+    then: Option<SortKey>,
+    history: &ProcessHistory,
+) {
+    let primary = key.comparator();
+    let secondary = then.map(|then| then.comparator());
     processes.sort_by(|a, b| {
+        primary(a, b, history)
+            .then_with(|| secondary.map_or(Ordering::Equal, |secondary| secondary(a, b, history)))
+    });
+}
+
+/// The `n` processes using the most CPU, without reordering the input
+pub fn top_by_cpu(processes: &[Process], n: usize) -> Vec<&Process> {
+    let mut top: Vec<&Process> = processes.iter().collect();
+    top.sort_by(|a, b| {
+        b.cpu_usage
+            .partial_cmp(&a.cpu_usage)
+            .unwrap_or(Ordering::Equal)
+    });
+    top.truncate(n);
+    top
+}
+
+/// The `n` processes using the most memory, without reordering the input
+pub fn top_by_memory(processes: &[Process], n: usize) -> Vec<&Process> {
+    let mut top: Vec<&Process> = processes.iter().collect();
+    top.sort_by(|a, b| {
         b.memory_mb
             .partial_cmp(&a.memory_mb)
-            .unwrap_or(std::cmp::Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
     });
+    top.truncate(n);
+    top
+}
+
+pub fn sort_by_cpu(processes: &mut [Process]) {
+    processes.sort_by(|a, b| descending(a.cpu_usage, b.cpu_usage));
+}
+
+pub fn sort_by_memory(processes: &mut [Process]) {
+    processes.sort_by(|a, b| descending(a.memory_mb, b.memory_mb));
+}
+
+/// CPU time per process at the last refresh. sysinfo only knows usage between
+/// two refreshes of the same `System`, and every process list is a new one.
+#[derive(Debug)]
+pub struct CpuTimes {
+    times: HashMap<Pid, u64>,
+    at: Instant,
+}
+
+impl Default for CpuTimes {
+    fn default() -> Self {
+        Self {
+            times: HashMap::new(),
+            at: Instant::now(),
+        }
+    }
+}
+
+impl CpuTimes {
+    /// Fill in the usage since the previous call and remember this reading
+    pub fn fill(&mut self, processes: &mut [Process]) {
+        fill_cpu_usage(processes, &self.times, self.at.elapsed());
+        self.at = Instant::now();
+        self.times = processes.iter().map(|p| (p.pid, p.cpu_time_ms)).collect();
+    }
+}
+
+/// CPU usage from the CPU time used since `previous` (PID to `cpu_time_ms`) was
+/// taken `elapsed` ago, a fresh process list has no usage of its own yet
+pub fn fill_cpu_usage(processes: &mut [Process], previous: &HashMap<Pid, u64>, elapsed: Duration) {
+    let elapsed_ms = elapsed.as_millis().max(1) as f32;
+    for process in processes {
+        if let Some(last) = previous.get(&process.pid) {
+            process.cpu_usage =
+                process.cpu_time_ms.saturating_sub(*last) as f32 / elapsed_ms * 100.0;
+        }
+    }
 }
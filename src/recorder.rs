@@ -0,0 +1,108 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::cli::RecordOptions;
+
+/// Line oriented file writer that rotates by size/age and fsyncs on an interval,
+/// so long unattended recordings neither fill the disk nor vanish on power loss.
+///
+/// Rotated files are renamed to `<path>.1`, `<path>.2`, ... with `.1` being the newest.
+pub struct RotatingWriter {
+    options: RecordOptions,
+    file: BufWriter<File>,
+    written: u64,
+    opened_at: Instant,
+    last_sync: Instant,
+}
+
+impl RotatingWriter {
+    pub fn open(options: RecordOptions) -> io::Result<Self> {
+        let file = open_append(&options.path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            options,
+            file: BufWriter::new(file),
+            written,
+            opened_at: Instant::now(),
+            last_sync: Instant::now(),
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.should_rotate(line.len() as u64 + 1) {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+
+        match self.options.fsync_interval {
+            Some(interval) if self.last_sync.elapsed() >= interval => self.sync()?,
+            // Without fsync still hand every line to the OS so a crash of
+            // this process alone doesn't lose buffered data
+            _ => self.file.flush()?,
+        }
+        Ok(())
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        let too_big = self
+            .options
+            .max_size
+            .is_some_and(|max| self.written > 0 && self.written + incoming > max);
+        let too_old = self
+            .options
+            .max_age
+            .is_some_and(|max| self.opened_at.elapsed() >= max);
+        too_big || too_old
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.sync()?;
+
+        let path = self.options.path.clone();
+        if self.options.keep == 0 {
+            fs::remove_file(&path)?;
+        } else {
+            // Shift path.(n-1) -> path.n, dropping whatever falls off the end
+            let _ = fs::remove_file(rotated_path(&path, self.options.keep));
+            for n in (1..self.options.keep).rev() {
+                let from = rotated_path(&path, n);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&path, n + 1))?;
+                }
+            }
+            fs::rename(&path, rotated_path(&path, 1))?;
+        }
+
+        self.file = BufWriter::new(open_append(&path)?);
+        self.written = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for RotatingWriter {
+    fn drop(&mut self) {
+        let _ = self.sync();
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
@@ -0,0 +1,369 @@
+//! Remote monitoring: `--serve` samples without a TUI and streams snapshots to
+//! every client, `--connect` renders a server's snapshots in the TUI and `--ssh`
+//! starts a server on another host with its output as the only client.
+//!
+//! Each message is a 4 byte big-endian length followed by that many bytes of
+//! JSON, one [`Snapshot`] per message. Unlike the mirror socket's JSON lines,
+//! a reader knows the size up front and doesn't have to scan for newlines.
+
+use std::fmt;
+#[cfg(unix)]
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::os::unix::io::FromRawFd;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use color_eyre::eyre::{bail, WrapErr};
+use color_eyre::Result;
+
+use crate::cpu::CpuInfo;
+use crate::memory::MemoryInfo;
+use crate::process::{self, CpuTimes, ProcessHistory};
+use crate::snapshot::Snapshot;
+use crate::tui::AppState;
+
+/// A snapshot of a few thousand processes is well below this, anything bigger is garbage
+const MAX_MESSAGE_SIZE: u32 = 64 * 1024 * 1024;
+/// First descriptor systemd passes with socket activation (SD_LISTEN_FDS_START)
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+/// A client that stops reading is dropped after this instead of stalling everybody else
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where `--serve` listens and `--connect` connects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    Unix(PathBuf),
+    /// host:port
+    Tcp(String),
+    /// The socket systemd passed through socket activation, only for `--serve`
+    Systemd,
+    /// Stdout as the only client, used by `--ssh`, only for `--serve`
+    Stdio,
+}
+
+impl Address {
+    /// "unix:PATH", anything with a slash, "HOST:PORT", "systemd" or "stdio"
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if value == "systemd" {
+            Ok(Address::Systemd)
+        } else if value == "stdio" {
+            Ok(Address::Stdio)
+        } else if let Some(path) = value.strip_prefix("unix:") {
+            Ok(Address::Unix(PathBuf::from(path)))
+        } else if value.contains('/') {
+            Ok(Address::Unix(PathBuf::from(value)))
+        } else if value
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        {
+            Ok(Address::Tcp(value.to_string()))
+        } else {
+            Err(format!(
+                "invalid address '{}', expected unix:PATH, HOST:PORT, systemd or stdio",
+                value
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Unix(path) => write!(f, "unix:{}", path.display()),
+            Address::Tcp(address) => write!(f, "{}", address),
+            Address::Systemd => write!(f, "systemd socket"),
+            Address::Stdio => write!(f, "stdout"),
+        }
+    }
+}
+
+enum Listener {
+    #[cfg(unix)]
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+impl Listener {
+    fn bind(address: &Address) -> Result<Self> {
+        match address {
+            #[cfg(unix)]
+            Address::Unix(path) => {
+                // Left over from a previous run, a live server would still answer
+                if path.exists() && UnixStream::connect(path).is_err() {
+                    let _ = fs::remove_file(path);
+                }
+                let listener = UnixListener::bind(path)
+                    .wrap_err_with(|| format!("failed to listen on {}", path.display()))?;
+                let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+                Ok(Listener::Unix(listener))
+            }
+            #[cfg(not(unix))]
+            Address::Unix(_) => bail!("unix sockets are not supported on this platform"),
+            Address::Tcp(address) => Ok(Listener::Tcp(
+                TcpListener::bind(address)
+                    .wrap_err_with(|| format!("failed to listen on {}", address))?,
+            )),
+            Address::Systemd => activated_listener(),
+            Address::Stdio => unreachable!("stdout has no listener"),
+        }
+    }
+
+    fn accept(&self) -> io::Result<Box<dyn Write + Send>> {
+        Ok(match self {
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Box::new(stream)
+            }
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                let _ = stream.set_nodelay(true);
+                Box::new(stream)
+            }
+        })
+    }
+}
+
+/// The listening socket from LISTEN_FDS/LISTEN_PID, see sd_listen_fds(3)
+#[cfg(unix)]
+fn activated_listener() -> Result<Listener> {
+    let ours = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        == Some(std::process::id());
+    let count = std::env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|count| count.parse::<u32>().ok())
+        .unwrap_or(0);
+    if !ours || count == 0 {
+        bail!("no socket passed by systemd (LISTEN_FDS is not set for this process)");
+    }
+
+    let fd = LISTEN_FDS_START;
+    // SAFETY: sockaddr_storage is plain data, getsockname fills at most `len` bytes of it
+    let family = unsafe {
+        let mut address: libc::sockaddr_storage = std::mem::zeroed();
+        let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        if libc::getsockname(fd, &mut address as *mut _ as *mut libc::sockaddr, &mut len) != 0 {
+            return Err(io::Error::last_os_error()).wrap_err("passed descriptor is not a socket");
+        }
+        address.ss_family as libc::c_int
+    };
+    // SAFETY: systemd hands the descriptor over to us, nothing else owns it
+    Ok(if family == libc::AF_UNIX {
+        Listener::Unix(unsafe { UnixListener::from_raw_fd(fd) })
+    } else {
+        Listener::Tcp(unsafe { TcpListener::from_raw_fd(fd) })
+    })
+}
+
+#[cfg(not(unix))]
+fn activated_listener() -> Result<Listener> {
+    bail!("socket activation is not supported on this platform")
+}
+
+fn write_message(writer: &mut dyn Write, payload: &[u8]) -> io::Result<()> {
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+fn read_message(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length);
+    if length > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes, not an m-as server?", length),
+        ));
+    }
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Sample every `interval` and send the snapshot to all connected clients.
+/// Runs until killed, or with `Address::Stdio` until stdout is closed.
+pub fn serve(address: &Address, interval: Duration) -> Result<()> {
+    let clients: Arc<Mutex<Vec<Box<dyn Write + Send>>>> = Arc::new(Mutex::new(Vec::new()));
+    if *address == Address::Stdio {
+        clients.lock().unwrap().push(Box::new(io::stdout()));
+    } else {
+        let listener = Listener::bind(address)?;
+        eprintln!("Serving snapshots on {}", address);
+        let accepted = Arc::clone(&clients);
+        thread::spawn(move || loop {
+            match listener.accept() {
+                Ok(client) => accepted.lock().unwrap().push(client),
+                // e.g. EMFILE, retrying right away would spin
+                Err(_) => thread::sleep(Duration::from_secs(1)),
+            }
+        });
+    }
+
+    let mut cpu_info = CpuInfo::new_sampled();
+    let mut memory_info = MemoryInfo::new();
+    let mut history = ProcessHistory::default();
+    let mut cpu_times = CpuTimes::default();
+    loop {
+        cpu_info.update();
+        memory_info.update();
+        let mut processes = process::get_all_processes();
+        cpu_times.fill(&mut processes);
+        process::sort_by_memory(&mut processes);
+        history.update(&processes);
+        history.fill_deltas(&mut processes);
+
+        let payload = serde_json::to_vec(&Snapshot::capture(&cpu_info, &memory_info, &processes))?;
+        // Clients that went away are dropped on the first failed write
+        let mut clients = clients.lock().unwrap();
+        clients.retain_mut(|client| write_message(client.as_mut(), &payload).is_ok());
+        // The SSH session ended, nobody will ever connect again
+        if *address == Address::Stdio && clients.is_empty() {
+            return Ok(());
+        }
+        drop(clients);
+
+        thread::sleep(interval);
+    }
+}
+
+/// Render a server's data: read its snapshots and feed them into our state
+pub fn connect(state: Arc<Mutex<AppState>>, address: &Address) -> Result<()> {
+    let mut reader: Box<dyn Read + Send> = match address {
+        #[cfg(unix)]
+        Address::Unix(path) => Box::new(
+            UnixStream::connect(path)
+                .wrap_err_with(|| format!("failed to connect to {}", address))?,
+        ),
+        #[cfg(not(unix))]
+        Address::Unix(_) => bail!("unix sockets are not supported on this platform"),
+        Address::Tcp(host) => Box::new(
+            TcpStream::connect(host)
+                .wrap_err_with(|| format!("failed to connect to {}", address))?,
+        ),
+        Address::Systemd | Address::Stdio => bail!("--connect needs unix:PATH or HOST:PORT"),
+    };
+    state.lock().unwrap().status_message = Some(format!("Connected to {}", address));
+
+    let address = address.clone();
+    thread::spawn(move || {
+        let error = read_snapshots(&state, &mut reader);
+        let message = if error.kind() == io::ErrorKind::UnexpectedEof {
+            format!("{} closed the connection, data is frozen", address)
+        } else {
+            format!("Lost {}: {}, data is frozen", address, error)
+        };
+        tracing::warn!("{}", message);
+        let mut state = state.lock().unwrap();
+        state.status_message = Some(message);
+        state.dirty = true;
+    });
+    Ok(())
+}
+
+/// Run `command --serve stdio` on `destination` through ssh and render its
+/// snapshots. Only the m-as binary is needed there, not a terminal.
+pub fn connect_ssh(
+    state: Arc<Mutex<AppState>>,
+    destination: &str,
+    command: &str,
+    interval: Duration,
+) -> Result<()> {
+    let mut child = Command::new("ssh")
+        // The TUI owns the terminal, a password prompt would end up in the middle of it
+        .args(["-T", "-o", "BatchMode=yes", destination])
+        .arg(format!(
+            "{} --serve stdio --interval {}",
+            command,
+            interval.as_millis()
+        ))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err("failed to run ssh")?;
+    let mut reader = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    // Read as it comes, a remote that keeps warning would otherwise fill the
+    // pipe and stall the session
+    let last_line = thread::spawn(move || {
+        BufReader::new(stderr)
+            .lines()
+            .map_while(io::Result::ok)
+            .filter(|line| !line.trim().is_empty())
+            .last()
+            .map(|line| line.trim().to_string())
+    });
+    state.lock().unwrap().status_message = Some(format!("Connecting to {} over SSH", destination));
+
+    let destination = destination.to_string();
+    thread::spawn(move || {
+        let error = read_snapshots(&state, &mut reader);
+        let message = match ssh_failure(child, last_line) {
+            Some(reason) => format!("SSH to {} failed: {}, data is frozen", destination, reason),
+            None if error.kind() == io::ErrorKind::UnexpectedEof => {
+                format!("SSH session to {} ended, data is frozen", destination)
+            }
+            None => format!("Lost {}: {}, data is frozen", destination, error),
+        };
+        tracing::warn!("{}", message);
+        let mut state = state.lock().unwrap();
+        state.status_message = Some(message);
+        state.dirty = true;
+    });
+    Ok(())
+}
+
+/// Last line ssh or the remote command printed to stderr when it exited with an error
+fn ssh_failure(mut child: Child, last_line: JoinHandle<Option<String>>) -> Option<String> {
+    // The stream already ended, so this only waits for the exit status
+    let status = child.wait().ok()?;
+    if status.success() {
+        return None;
+    }
+    Some(
+        last_line
+            .join()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| status.to_string()),
+    )
+}
+
+/// Apply snapshots from `reader` until it fails, returns why it stopped
+fn read_snapshots(state: &Mutex<AppState>, reader: &mut impl Read) -> io::Error {
+    let mut first = true;
+    loop {
+        let payload = match read_message(reader) {
+            Ok(payload) => payload,
+            Err(e) => return e,
+        };
+        match serde_json::from_slice::<Snapshot>(&payload) {
+            Ok(snapshot) => {
+                let mut state = state.lock().unwrap();
+                state.apply_snapshot(&snapshot);
+                // Replace "Connecting to" once there is data
+                if first {
+                    state.status_message = None;
+                    first = false;
+                }
+            }
+            Err(e) => return e.into(),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+/// Common shape of the collectors, so they can be driven without the TUI:
+/// create once, call `update` on your own schedule, read a serializable `snapshot`.
+pub trait Sampler {
+    type Snapshot;
+
+    fn new() -> Self
+    where
+        Self: Sized;
+
+    /// Take a new reading. Rates and usage percentages are relative to the previous call.
+    fn update(&mut self);
+
+    fn snapshot(&self) -> Self::Snapshot;
+}
@@ -0,0 +1,195 @@
+//! One clock for every sampler. The background thread (or the async runtime)
+//! wakes on a fixed tick and runs whatever is due. Periods are whole ticks
+//! counted from the same start, so samplers never drift apart and everything
+//! sampled on a tick carries that tick's timestamp.
+
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cpu;
+use crate::history_db;
+use crate::influx;
+use crate::selfmon;
+use crate::tui::{
+    AppState, BATTERY_UPDATE_INTERVAL, DISK_UPDATE_INTERVAL, KERNEL_UPDATE_INTERVAL,
+    MEMORY_DETAILS_UPDATE_INTERVAL, NEIGHBOR_UPDATE_INTERVAL, NETWORK_UPDATE_INTERVAL,
+    NUMA_UPDATE_INTERVAL, PROBE_UPDATE_INTERVAL, SERVICE_UPDATE_INTERVAL, SESSION_UPDATE_INTERVAL,
+    WAIT_TRACE_INTERVAL,
+};
+
+/// Resolution of the schedule, periods are rounded to a multiple of it
+pub const TICK: Duration = Duration::from_millis(50);
+
+/// Samplers whose period is changed at runtime (`:interval`, chart options), not in the config file
+pub const RUNTIME_INTERVALS: [&str; 2] = ["processes", "cpu"];
+
+pub type Sample = fn(&mut AppState);
+pub type Interval = fn(&AppState) -> Duration;
+
+/// Every sampler as (name, how often, what to run). Adding a new sampler
+/// (GPU, containers, ...) only needs a new entry here.
+pub fn samplers() -> Vec<(&'static str, Interval, Sample)> {
+    #[allow(unused_mut)]
+    let mut samplers: Vec<(&'static str, Interval, Sample)> = vec![
+        (
+            "processes",
+            AppState::process_interval,
+            AppState::sample_processes,
+        ),
+        ("cpu", AppState::cpu_interval, AppState::sample_cpu),
+        ("disks", |_| DISK_UPDATE_INTERVAL, AppState::sample_disks),
+        (
+            "network",
+            |_| NETWORK_UPDATE_INTERVAL,
+            AppState::sample_network,
+        ),
+        (
+            "kernel",
+            |_| KERNEL_UPDATE_INTERVAL,
+            AppState::sample_kernel,
+        ),
+        (
+            "memory_details",
+            |_| MEMORY_DETAILS_UPDATE_INTERVAL,
+            AppState::sample_memory_details,
+        ),
+        (
+            "sessions",
+            |_| SESSION_UPDATE_INTERVAL,
+            AppState::sample_sessions,
+        ),
+        (
+            "services",
+            |_| SERVICE_UPDATE_INTERVAL,
+            AppState::sample_services,
+        ),
+        ("numa", |_| NUMA_UPDATE_INTERVAL, AppState::sample_numa),
+        (
+            "neighbors",
+            |_| NEIGHBOR_UPDATE_INTERVAL,
+            AppState::sample_neighbors,
+        ),
+        ("probes", |_| PROBE_UPDATE_INTERVAL, AppState::sample_probes),
+        (
+            "battery",
+            |_| BATTERY_UPDATE_INTERVAL,
+            AppState::sample_battery,
+        ),
+        (
+            "wait_trace",
+            |_| WAIT_TRACE_INTERVAL,
+            AppState::sample_wait_trace,
+        ),
+        (
+            "history_db",
+            |_| history_db::RECORD_INTERVAL,
+            AppState::sample_history_db,
+        ),
+        (
+            "influx",
+            |state| {
+                state
+                    .influx
+                    .as_ref()
+                    .map_or(influx::DEFAULT_INTERVAL, |exporter| exporter.interval)
+            },
+            AppState::sample_influx,
+        ),
+    ];
+    #[cfg(feature = "smart")]
+    samplers.push((
+        "smart",
+        |_| crate::tui::SMART_UPDATE_INTERVAL,
+        AppState::sample_smart,
+    ));
+    samplers
+}
+
+struct Entry {
+    name: &'static str,
+    interval: Interval,
+    sample: Sample,
+    /// Offset into the period in ticks, so samplers sharing a period take turns
+    phase: u64,
+    next: u64,
+}
+
+pub struct Scheduler {
+    entries: Vec<Entry>,
+    start: Instant,
+    started_at: SystemTime,
+    tick: u64,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        let entries = samplers()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, interval, sample))| {
+                // CPU usage needs readings some time apart, the first was taken in CpuInfo::new
+                let earliest = match name {
+                    "cpu" => cpu::MIN_SAMPLE_INTERVAL,
+                    _ => Duration::ZERO,
+                };
+                let phase = ticks(selfmon::stagger_offset(index).max(earliest));
+                Entry {
+                    name,
+                    interval,
+                    sample,
+                    phase,
+                    next: phase,
+                }
+            })
+            .collect();
+        Self {
+            entries,
+            start: Instant::now(),
+            started_at: SystemTime::now(),
+            tick: 0,
+        }
+    }
+
+    /// Wall clock time of the current tick
+    pub fn taken_at(&self) -> SystemTime {
+        self.started_at + tick_offset(self.tick)
+    }
+
+    /// Samplers to run on the current tick as (name, base interval, sampler).
+    /// Their next runs are placed back on their grid, so a changed period or
+    /// a slowdown from the overhead budget doesn't shift the phase.
+    pub fn due(&mut self, state: &AppState) -> Vec<(&'static str, Duration, Sample)> {
+        let tick = self.tick;
+        let mut due = Vec::new();
+        for entry in self.entries.iter_mut().filter(|entry| entry.next <= tick) {
+            let base = state
+                .sampler_intervals
+                .get(entry.name)
+                .copied()
+                .unwrap_or_else(|| (entry.interval)(state));
+            let period = ticks(state.collector_interval(entry.name, base)).max(1);
+            entry.next = entry.phase + ((tick - entry.phase) / period + 1) * period;
+            due.push((entry.name, base, entry.sample));
+        }
+        due
+    }
+
+    /// Move on to the next tick and return how long to wait for it. Ticks
+    /// that already passed (slow samplers, a suspended machine) are skipped
+    /// rather than caught up on.
+    pub fn advance(&mut self) -> Duration {
+        let elapsed = self.start.elapsed();
+        let current = (elapsed.as_millis() / TICK.as_millis()) as u64;
+        self.tick = (self.tick + 1).max(current);
+        tick_offset(self.tick).saturating_sub(elapsed)
+    }
+}
+
+/// Nearest whole number of ticks
+fn ticks(duration: Duration) -> u64 {
+    let tick = TICK.as_millis();
+    ((duration.as_millis() + tick / 2) / tick) as u64
+}
+
+fn tick_offset(tick: u64) -> Duration {
+    Duration::from_millis(TICK.as_millis() as u64 * tick)
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Tracks the monitor's own CPU and memory footprint, so users can check it
+/// stays lightweight and regressions in the samplers are easy to spot
+#[derive(Debug)]
+pub struct SelfMonitor {
+    pub cpu_usage: f32,
+    pub memory_mb: f64,
+    last_cpu_time: Duration,
+    last_update: Instant,
+}
+
+impl SelfMonitor {
+    pub fn new() -> Self {
+        Self {
+            cpu_usage: 0.0,
+            memory_mb: 0.0,
+            last_cpu_time: own_cpu_time(),
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let cpu_time = own_cpu_time();
+        let elapsed = now.duration_since(self.last_update).as_secs_f64();
+        if elapsed > 0.0 {
+            let used = cpu_time.saturating_sub(self.last_cpu_time).as_secs_f64();
+            self.cpu_usage = (used / elapsed * 100.0) as f32;
+        }
+        self.memory_mb = own_rss_bytes() as f64 / 1024.0 / 1024.0;
+        self.last_cpu_time = cpu_time;
+        self.last_update = now;
+    }
+}
+
+/// Share of its own interval a single collector may spend working, unless configured
+pub const DEFAULT_COLLECTOR_BUDGET: f64 = 0.15;
+// Consecutive ticks over budget before a collector is slowed down, so one slow tick doesn't count
+const OVER_BUDGET_TICKS: u32 = 3;
+// Consecutive ticks that would fit the budget at half the slowdown before speeding back up,
+// more than OVER_BUDGET_TICKS so a collector near the limit doesn't flip back and forth
+const UNDER_BUDGET_TICKS: u32 = 10;
+const MAX_SLOWDOWN: u32 = 8;
+
+/// Gap between the first runs of consecutive samplers, more than the sampler
+/// thread's 50ms poll so samplers sharing an interval land on different passes
+pub const STAGGER_STEP: Duration = Duration::from_millis(60);
+
+/// Delay before the first run of the `index`th sampler, so samplers with the same
+/// interval don't all take the shared state at the same moment every time
+pub fn stagger_offset(index: usize) -> Duration {
+    STAGGER_STEP * index as u32
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CollectorCost {
+    /// How long the last run took
+    pub last: Duration,
+    pub max: Duration,
+    total: Duration,
+    pub runs: u64,
+    /// Configured interval at the last run, before the slowdown
+    pub base: Duration,
+    /// Measured time between the last two runs
+    pub period: Option<Duration>,
+    last_run: Option<Instant>,
+    /// The collector runs this many times less often than configured
+    pub slowdown: u32,
+    over_budget: u32,
+    under_budget: u32,
+}
+
+impl CollectorCost {
+    pub fn average(&self) -> Duration {
+        self.total / self.runs.max(1) as u32
+    }
+}
+
+/// Per collector cost accounting. Collectors that keep exceeding the budget on
+/// this machine get their interval doubled, up to `MAX_SLOWDOWN` times, and
+/// halved again once they got cheap (e.g. far fewer processes).
+#[derive(Debug)]
+pub struct CollectorBudget {
+    /// Share of the interval a collector may spend, 0.15 is 15%
+    share: f64,
+    collectors: BTreeMap<&'static str, CollectorCost>,
+}
+
+impl CollectorBudget {
+    pub fn new(share: f64) -> Self {
+        Self {
+            share,
+            collectors: BTreeMap::new(),
+        }
+    }
+
+    /// Account one run of `name`, returns a message for the user when it was just slowed down
+    pub fn record(&mut self, name: &'static str, cost: Duration, base: Duration) -> Option<String> {
+        let collector = self.collectors.entry(name).or_insert(CollectorCost {
+            last: Duration::ZERO,
+            max: Duration::ZERO,
+            total: Duration::ZERO,
+            runs: 0,
+            base,
+            period: None,
+            last_run: None,
+            slowdown: 1,
+            over_budget: 0,
+            under_budget: 0,
+        });
+        let now = Instant::now();
+        collector.period = collector.last_run.map(|last| now.duration_since(last));
+        collector.last_run = Some(now);
+        collector.last = cost;
+        collector.max = collector.max.max(cost);
+        collector.total += cost;
+        collector.runs += 1;
+        collector.base = base;
+
+        let interval = base * collector.slowdown;
+        let allowed = interval.as_secs_f64() * self.share;
+        if cost.as_secs_f64() <= allowed {
+            collector.over_budget = 0;
+            if collector.slowdown == 1 || cost.as_secs_f64() > allowed / 2.0 {
+                collector.under_budget = 0;
+                return None;
+            }
+            collector.under_budget += 1;
+            if collector.under_budget < UNDER_BUDGET_TICKS {
+                return None;
+            }
+            collector.under_budget = 0;
+            collector.slowdown /= 2;
+            return Some(format!(
+                "{} sampling got cheaper ({}ms), back to every {}ms",
+                name,
+                cost.as_millis(),
+                (base * collector.slowdown).as_millis()
+            ));
+        }
+        collector.under_budget = 0;
+        collector.over_budget += 1;
+        if collector.over_budget < OVER_BUDGET_TICKS || collector.slowdown >= MAX_SLOWDOWN {
+            return None;
+        }
+
+        collector.over_budget = 0;
+        collector.slowdown *= 2;
+        Some(format!(
+            "{} sampling takes {}ms here, now every {}ms to limit overhead",
+            name,
+            cost.as_millis(),
+            (base * collector.slowdown).as_millis()
+        ))
+    }
+
+    /// `base` stretched by the collector's slowdown
+    pub fn interval(&self, name: &str, base: Duration) -> Duration {
+        base * self.slowdown(name)
+    }
+
+    /// How much slower than configured `name` currently runs, 1 when it isn't throttled
+    pub fn slowdown(&self, name: &str) -> u32 {
+        self.collectors.get(name).map_or(1, |c| c.slowdown)
+    }
+
+    /// Every collector that ran at least once, by name
+    pub fn collectors(&self) -> impl Iterator<Item = (&'static str, &CollectorCost)> {
+        self.collectors.iter().map(|(name, cost)| (*name, cost))
+    }
+
+    /// Collectors currently running slower than configured
+    pub fn throttled(&self) -> impl Iterator<Item = (&'static str, &CollectorCost)> {
+        self.collectors
+            .iter()
+            .filter(|(_, cost)| cost.slowdown > 1)
+            .map(|(name, cost)| (*name, cost))
+    }
+}
+
+/// When each collector last delivered data and how far apart its last two
+/// updates were, so panels can show how fresh they are
+#[derive(Debug, Default)]
+pub struct Freshness {
+    collectors: BTreeMap<&'static str, (Instant, Option<Duration>)>,
+}
+
+impl Freshness {
+    /// `name` delivered data that was current at `at`
+    pub fn record(&mut self, name: &'static str, at: Instant) {
+        let interval = self
+            .collectors
+            .get(name)
+            .map(|(last, _)| at.saturating_duration_since(*last));
+        self.collectors.insert(name, (at, interval));
+    }
+
+    /// Whether `name` delivered anything yet
+    pub fn collected(&self, name: &str) -> bool {
+        self.collectors.contains_key(name)
+    }
+
+    /// "(every 1.0s, 0.3s ago)" for a panel title, empty before the first update
+    pub fn label(&self, name: &str) -> String {
+        let Some((last, interval)) = self.collectors.get(name) else {
+            return String::new();
+        };
+        let age = format_age(last.elapsed());
+        match interval {
+            Some(interval) => format!("(every {}, {} ago)", format_age(*interval), age),
+            None => format!("({} ago)", age),
+        }
+    }
+}
+
+/// "0.4s", "12s" or "3m"
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs_f64();
+    if secs < 10.0 {
+        format!("{:.1}s", secs)
+    } else if secs < 120.0 {
+        format!("{:.0}s", secs)
+    } else {
+        format!("{}m", age.as_secs() / 60)
+    }
+}
+
+/// User + system CPU time of this process, all threads included
+#[cfg(unix)]
+fn own_cpu_time() -> Duration {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: usage is a properly sized out parameter
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return Duration::ZERO;
+    }
+    let to_duration = |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+    to_duration(usage.ru_utime) + to_duration(usage.ru_stime)
+}
+
+#[cfg(not(unix))]
+fn own_cpu_time() -> Duration {
+    Duration::ZERO
+}
+
+fn own_rss_bytes() -> u64 {
+    let Ok(pid) = sysinfo::get_current_pid() else {
+        return 0;
+    };
+    let mut system = sysinfo::System::new();
+    system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).map_or(0, |p| p.memory())
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+const CGROUP_MOUNT: &str = "/sys/fs/cgroup";
+const CGROUP_ROOT: &str = "/sys/fs/cgroup/system.slice";
+// Scope name prefixes container runtimes use for their cgroups
+const CONTAINER_SCOPE_PREFIXES: &[&str] = &["docker-", "libpod-", "crio-", "cri-containerd-"];
+
+#[allow(dead_code)]
+pub fn main() {
+    let mut service_info = ServiceInfo::new();
+    service_info.update();
+
+    for service in &service_info.services {
+        println!(
+            "{:<40} {:<10} {:<10} {:>8.1}MB",
+            service.unit,
+            service.active,
+            service.sub,
+            service.memory_bytes.unwrap_or(0) as f64 / 1024.0 / 1024.0
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl fmt::Display for ServiceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceAction::Start => write!(f, "start"),
+            ServiceAction::Stop => write!(f, "stop"),
+            ServiceAction::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Service {
+    pub unit: String,
+    pub load: String,
+    /// High level state: active, inactive, failed, ...
+    pub active: String,
+    /// Low level state: running, exited, dead, ...
+    pub sub: String,
+    pub description: String,
+    /// From the unit's cgroup, None when the cgroup doesn't exist (unit not running, cgroup v1)
+    pub memory_bytes: Option<u64>,
+    pub cpu_usage: Option<f32>,
+    pub pressure: Pressure,
+}
+
+/// Percentage of the last 10 seconds in which at least one task of the
+/// cgroup was stalled on the resource ("some avg10" in the cgroup v2 PSI files).
+/// None without cgroup v2 or a kernel built without PSI.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pressure {
+    pub cpu: Option<f32>,
+    pub memory: Option<f32>,
+    pub io: Option<f32>,
+}
+
+impl Pressure {
+    pub fn read(cgroup: &Path) -> Self {
+        Self {
+            cpu: read_psi_avg10(&cgroup.join("cpu.pressure")),
+            memory: read_psi_avg10(&cgroup.join("memory.pressure")),
+            io: read_psi_avg10(&cgroup.join("io.pressure")),
+        }
+    }
+
+    pub fn worst(&self) -> f32 {
+        [self.cpu, self.memory, self.io]
+            .into_iter()
+            .flatten()
+            .fold(0.0, f32::max)
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.cpu.is_some() || self.memory.is_some() || self.io.is_some()
+    }
+}
+
+/// A slice or container cgroup, to see which one is suffering instead of only the host
+#[derive(Debug, Clone)]
+pub struct CgroupPressure {
+    /// Path below the cgroup mount, e.g. "user.slice" or "system.slice/docker-1a2b.scope"
+    pub name: String,
+    pub pressure: Pressure,
+}
+
+#[derive(Debug)]
+pub struct ServiceInfo {
+    pub services: Vec<Service>,
+    /// False when systemctl isn't there, e.g. non-systemd distros or other platforms
+    pub available: bool,
+    /// Slices and containers with PSI data, most pressured first
+    pub cgroups: Vec<CgroupPressure>,
+    // Cumulative cgroup CPU time in microseconds per unit from the previous update
+    last_cpu_usec: HashMap<String, u64>,
+    last_update: Instant,
+}
+
+impl ServiceInfo {
+    pub fn new() -> Self {
+        Self {
+            services: Vec::new(),
+            available: true,
+            cgroups: Vec::new(),
+            last_cpu_usec: HashMap::new(),
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        let Some(mut services) = list_units() else {
+            if self.available {
+                tracing::warn!("systemctl list-units failed, no services to show");
+            }
+            self.available = false;
+            self.services.clear();
+            return;
+        };
+        self.available = true;
+
+        let now = Instant::now();
+        let elapsed_usec = now.duration_since(self.last_update).as_micros().max(1) as f64;
+        let mut cpu_usec = HashMap::new();
+
+        for service in &mut services {
+            let cgroup = format!("{}/{}", CGROUP_ROOT, service.unit);
+            service.memory_bytes = fs::read_to_string(format!("{}/memory.current", cgroup))
+                .ok()
+                .and_then(|v| v.trim().parse().ok());
+
+            service.pressure = Pressure::read(Path::new(&cgroup));
+
+            if let Some(usage) = read_cpu_usec(&cgroup) {
+                service.cpu_usage = self
+                    .last_cpu_usec
+                    .get(&service.unit)
+                    .map(|last| (usage.saturating_sub(*last) as f64 / elapsed_usec * 100.0) as f32);
+                cpu_usec.insert(service.unit.clone(), usage);
+            }
+        }
+
+        // Busiest services first, like the process table
+        services.sort_by_key(|service| std::cmp::Reverse(service.memory_bytes));
+
+        self.services = services;
+        self.cgroups = read_cgroup_pressure();
+        self.last_cpu_usec = cpu_usec;
+        self.last_update = now;
+    }
+}
+
+/// Run `systemctl <action> <unit>`, returning systemctl's error output on failure
+pub fn run_action(unit: &str, action: ServiceAction) -> Result<(), String> {
+    let output = Command::new("systemctl")
+        .arg(action.to_string())
+        .arg(unit)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn list_units() -> Option<Vec<Service>> {
+    let output = Command::new("systemctl")
+        .args([
+            "list-units",
+            "--type=service",
+            "--all",
+            "--no-legend",
+            "--no-pager",
+            "--plain",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let services = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            // UNIT LOAD ACTIVE SUB DESCRIPTION...
+            let mut fields = line.split_whitespace();
+            Some(Service {
+                unit: fields.next()?.to_string(),
+                load: fields.next()?.to_string(),
+                active: fields.next()?.to_string(),
+                sub: fields.next()?.to_string(),
+                description: fields.collect::<Vec<_>>().join(" "),
+                memory_bytes: None,
+                cpu_usage: None,
+                pressure: Pressure::default(),
+            })
+        })
+        .collect();
+    Some(services)
+}
+
+/// `usage_usec` from the cgroup v2 cpu.stat file
+fn read_cpu_usec(cgroup: &str) -> Option<u64> {
+    fs::read_to_string(format!("{}/cpu.stat", cgroup))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Top level slices plus container scopes, skipping cgroups without PSI data
+fn read_cgroup_pressure() -> Vec<CgroupPressure> {
+    let mut names = child_cgroups("", |name| name.ends_with(".slice"));
+    names.extend(child_cgroups("system.slice", |name| {
+        CONTAINER_SCOPE_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+    }));
+    // systemd-nspawn and libvirt put their guests here
+    names.extend(child_cgroups("machine.slice", |_| true));
+
+    let mut cgroups: Vec<CgroupPressure> = names
+        .into_iter()
+        .map(|name| CgroupPressure {
+            pressure: Pressure::read(&Path::new(CGROUP_MOUNT).join(&name)),
+            name,
+        })
+        .filter(|cgroup| cgroup.pressure.is_available())
+        .collect();
+    cgroups.sort_by(|a, b| b.pressure.worst().total_cmp(&a.pressure.worst()));
+    cgroups
+}
+
+/// Names of the cgroup directories below `parent` accepted by `filter`, as paths below the mount
+fn child_cgroups(parent: &str, filter: impl Fn(&str) -> bool) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(Path::new(CGROUP_MOUNT).join(parent)) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| filter(name))
+        .map(|name| {
+            if parent.is_empty() {
+                name
+            } else {
+                format!("{}/{}", parent, name)
+            }
+        })
+        .collect()
+}
+
+/// "some avg10=1.23 avg60=..." -> 1.23
+fn read_psi_avg10(path: &Path) -> Option<f32> {
+    fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
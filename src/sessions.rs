@@ -0,0 +1,181 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// utmp records on Linux are fixed size, see `man 5 utmp`
+const UTMP_PATH: &str = "/var/run/utmp";
+const UTMP_RECORD_SIZE: usize = 384;
+const USER_PROCESS: i16 = 7;
+
+// Auth logs differ between distributions, the first readable one wins
+const AUTH_LOG_PATHS: &[&str] = &["/var/log/auth.log", "/var/log/secure"];
+
+#[allow(dead_code)]
+pub fn main() {
+    let mut session_info = SessionInfo::new();
+    session_info.update();
+
+    println!("=== SSH SESSIONS ===");
+    for session in &session_info.sessions {
+        println!(
+            "{:<12} {:<20} {}",
+            session.user,
+            session.source,
+            format_duration(session.duration())
+        );
+    }
+    match session_info.failed_logins {
+        Some(count) => println!("Failed logins: {}", count),
+        None => println!("Failed logins: auth log not readable"),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SshSession {
+    pub user: String,
+    pub source: String,
+    pub tty: String,
+    pub login_time: SystemTime,
+}
+
+impl SshSession {
+    pub fn duration(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.login_time)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionInfo {
+    pub sessions: Vec<SshSession>,
+    /// Since m-as started, None when no auth log could be read (usually
+    /// missing permissions)
+    pub failed_logins: Option<usize>,
+    /// None until the first update, which only notes where the log ends
+    auth_log_offset: Option<u64>,
+}
+
+impl SessionInfo {
+    pub fn new() -> Self {
+        Self {
+            sessions: Vec::new(),
+            failed_logins: None,
+            auth_log_offset: None,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.sessions = read_ssh_sessions();
+        self.update_failed_logins();
+    }
+
+    /// Only reads what was appended to the auth log since the last update
+    fn update_failed_logins(&mut self) {
+        let Some(mut file) = AUTH_LOG_PATHS.iter().find_map(|path| File::open(path).ok()) else {
+            self.failed_logins = None;
+            return;
+        };
+
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        // The log can go back months, what happened before m-as started isn't recent
+        let Some(mut offset) = self.auth_log_offset else {
+            self.auth_log_offset = Some(len);
+            self.failed_logins = Some(0);
+            return;
+        };
+        // Log was rotated, the new one only has lines written since
+        if len < offset {
+            offset = 0;
+        }
+
+        let mut contents = Vec::new();
+        if file.seek(SeekFrom::Start(offset)).is_err() || file.read_to_end(&mut contents).is_err() {
+            return;
+        }
+        self.auth_log_offset = Some(offset + contents.len() as u64);
+
+        let new_failures = String::from_utf8_lossy(&contents)
+            .lines()
+            .filter(|line| is_failed_login(line))
+            .count();
+        self.failed_logins = Some(self.failed_logins.unwrap_or(0) + new_failures);
+    }
+}
+
+/// One line per attempt. For an unknown user sshd logs "Invalid user" before
+/// "Failed password", and pam_unix adds an "authentication failure" of its own,
+/// so for sshd only "Failed password" counts.
+fn is_failed_login(line: &str) -> bool {
+    if line.contains("sshd") {
+        line.contains("Failed password")
+    } else {
+        line.contains("authentication failure")
+    }
+}
+
+fn read_ssh_sessions() -> Vec<SshSession> {
+    let mut data = Vec::new();
+    if File::open(UTMP_PATH)
+        .and_then(|mut f| f.read_to_end(&mut data))
+        .is_err()
+    {
+        return Vec::new();
+    }
+
+    data.chunks_exact(UTMP_RECORD_SIZE)
+        .filter_map(parse_utmp_record)
+        .collect()
+}
+
+fn parse_utmp_record(record: &[u8]) -> Option<SshSession> {
+    let ut_type = i16::from_ne_bytes([record[0], record[1]]);
+    let pid = i32::from_ne_bytes(record[4..8].try_into().ok()?);
+    if ut_type != USER_PROCESS {
+        return None;
+    }
+
+    // SSH logins get a pseudo terminal and the client's address as host. Local
+    // X sessions put their display (":0") there, mosh and terminal
+    // multiplexers describe themselves.
+    let host = c_string(&record[76..332]);
+    let tty = c_string(&record[8..40]);
+    if host.is_empty()
+        || host.starts_with(':')
+        || host.contains("mosh")
+        || host.starts_with("tmux(")
+        || !tty.starts_with("pts/")
+    {
+        return None;
+    }
+
+    // utmp may contain stale entries when sshd didn't clean up
+    if !Path::new(&format!("/proc/{}", pid)).exists() {
+        return None;
+    }
+
+    let tv_sec = i32::from_ne_bytes(record[340..344].try_into().ok()?);
+
+    Some(SshSession {
+        user: c_string(&record[44..76]),
+        source: host,
+        tty,
+        login_time: UNIX_EPOCH + Duration::from_secs(tv_sec.max(0) as u64),
+    })
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60);
+    if days > 0 {
+        format!("{}d {:02}h", days, hours)
+    } else {
+        format!("{:02}h {:02}m", hours, minutes)
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+// ATA attribute ids worth a warning as soon as their raw value isn't zero
+const REALLOCATED_SECTORS: u64 = 5;
+const PENDING_SECTORS: u64 = 197;
+const UNCORRECTABLE_SECTORS: u64 = 198;
+
+/// What `smartctl` reports about one physical disk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SmartHealth {
+    /// The drive's own overall verdict, None when it didn't give one
+    pub passed: Option<bool>,
+    pub temperature: Option<i64>,
+    pub reallocated_sectors: Option<u64>,
+    /// Attributes below their threshold or with bad sectors, e.g. "Current_Pending_Sector"
+    pub failing: Vec<String>,
+}
+
+impl SmartHealth {
+    pub fn is_failing(&self) -> bool {
+        self.passed == Some(false)
+            || !self.failing.is_empty()
+            || self.reallocated_sectors.is_some_and(|count| count > 0)
+    }
+
+    /// Short cell text: "OK 38°C", "FAIL 41°C", "12 realloc"
+    pub fn summary(&self) -> String {
+        let verdict = match (self.passed, self.reallocated_sectors) {
+            (Some(false), _) => "FAIL".to_string(),
+            (_, Some(count)) if count > 0 => format!("{} realloc", count),
+            _ if !self.failing.is_empty() => "WARN".to_string(),
+            (Some(true), _) => "OK".to_string(),
+            (None, _) => "?".to_string(),
+        };
+        match self.temperature {
+            Some(celsius) => format!("{} {}°C", verdict, celsius),
+            None => verdict,
+        }
+    }
+}
+
+/// Health of the disks behind `devices`, keyed by the given names, so partitions
+/// ("sda1", "sda2") get the health of their disk. Disks smartctl can't read are
+/// left out, which is all of them without root or when smartctl isn't installed.
+pub fn read_all<'a>(devices: impl IntoIterator<Item = &'a str>) -> HashMap<String, SmartHealth> {
+    let mut by_disk: HashMap<String, Option<SmartHealth>> = HashMap::new();
+    devices
+        .into_iter()
+        .filter_map(|device| {
+            let health = by_disk
+                .entry(whole_disk(device))
+                .or_insert_with_key(|disk| read(disk))
+                .clone()?;
+            Some((device.to_string(), health))
+        })
+        .collect()
+}
+
+fn read(disk: &str) -> Option<SmartHealth> {
+    let output = Command::new("smartctl")
+        .args(["-j", "-H", "-A"])
+        .arg(format!("/dev/{}", disk))
+        .output()
+        .ok()?;
+    // smartctl's exit status is a bit mask that's non-zero for failing disks
+    // too, so only the JSON tells whether the drive could be read at all
+    let json: Value = serde_json::from_slice(&output.stdout).ok()?;
+    let health = parse(&json);
+    if health.is_none() {
+        tracing::debug!("smartctl has no SMART data for /dev/{}", disk);
+    }
+    health
+}
+
+/// None when the output has no SMART data, e.g. virtual disks or missing permissions
+fn parse(json: &Value) -> Option<SmartHealth> {
+    let passed = json["smart_status"]["passed"].as_bool();
+    let temperature = json["temperature"]["current"].as_i64();
+    let mut health = SmartHealth {
+        passed,
+        temperature,
+        ..SmartHealth::default()
+    };
+
+    for attribute in json["ata_smart_attributes"]["table"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let name = attribute["name"].as_str().unwrap_or("unknown").to_string();
+        let raw = attribute["raw"]["value"].as_u64().unwrap_or(0);
+        let id = attribute["id"].as_u64().unwrap_or(0);
+        if id == REALLOCATED_SECTORS {
+            health.reallocated_sectors = Some(raw);
+        }
+        let below_threshold = attribute["when_failed"]
+            .as_str()
+            .is_some_and(|when| !when.is_empty());
+        let bad_sectors = matches!(id, PENDING_SECTORS | UNCORRECTABLE_SECTORS) && raw > 0;
+        if below_threshold || bad_sectors {
+            health.failing.push(name);
+        }
+    }
+
+    // NVMe drives have a health log instead of attributes
+    let nvme = &json["nvme_smart_health_information_log"];
+    if nvme["critical_warning"]
+        .as_u64()
+        .is_some_and(|bits| bits != 0)
+    {
+        health.failing.push("critical_warning".to_string());
+    }
+    if nvme["media_errors"].as_u64().is_some_and(|count| count > 0) {
+        health.failing.push("media_errors".to_string());
+    }
+
+    (passed.is_some() || temperature.is_some() || !health.failing.is_empty()).then_some(health)
+}
+
+/// "sda1" -> "sda", "nvme0n1p2" -> "nvme0n1". Partitions sit below their disk in sysfs.
+fn whole_disk(device: &str) -> String {
+    let sys_path = Path::new("/sys/class/block").join(device);
+    if sys_path.join("partition").exists() {
+        if let Some(disk) = fs::canonicalize(&sys_path)
+            .ok()
+            .and_then(|path| Some(path.parent()?.file_name()?.to_string_lossy().into_owned()))
+        {
+            return disk;
+        }
+    }
+    device.to_string()
+}
@@ -0,0 +1,164 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ThreadKind};
+
+use crate::cpu::{CpuBreakdown, CpuInfo};
+use crate::memory::MemoryInfo;
+use crate::process::{self, Process, Sandbox};
+use crate::sampler::Sampler;
+
+/// Point-in-time view of everything the samplers collected, used by the
+/// non-interactive output modes. The JSON keys predate the field names.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Milliseconds since the unix epoch, the scheduler tick the data belongs to
+    #[serde(rename = "timestamp")]
+    pub taken_at: u64,
+    pub cpu: CpuSnapshot,
+    #[serde(rename = "memory")]
+    pub mem: MemorySnapshot,
+    #[serde(rename = "processes")]
+    pub procs: Vec<ProcessSnapshot>,
+    /// Only where a network monitor runs alongside, i.e. the TUI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net: Option<NetworkSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuSnapshot {
+    pub global_usage: f32,
+    pub cores: Vec<f32>,
+    #[serde(default)]
+    pub breakdown: Option<CpuBreakdown>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    pub total_memory: u64,
+    pub used_memory: u64,
+    pub total_swap: u64,
+    pub used_swap: u64,
+}
+
+/// Produced by [`crate::network::NetworkMonitor`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkSnapshot {
+    /// Bytes per second over all interfaces
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSnapshot {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory_mb: f64,
+    pub status: String,
+    pub parent_pid: Option<u32>,
+    pub pgid: Option<u32>,
+    pub sid: Option<u32>,
+    pub origin: Option<String>,
+    #[serde(default)]
+    pub cpu_time_ms: u64,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub swap_bytes: Option<u64>,
+    /// "kernel" or "user" for threads, missing for processes
+    #[serde(default)]
+    pub thread: Option<String>,
+}
+
+impl Snapshot {
+    pub fn capture(cpu_info: &CpuInfo, memory_info: &MemoryInfo, processes: &[Process]) -> Self {
+        Self::capture_at(SystemTime::now(), cpu_info, memory_info, processes)
+    }
+
+    /// Stamped with `taken_at` rather than now, e.g. the tick the samplers ran on
+    pub fn capture_at(
+        taken_at: SystemTime,
+        cpu_info: &CpuInfo,
+        memory_info: &MemoryInfo,
+        processes: &[Process],
+    ) -> Self {
+        Self {
+            taken_at: taken_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            cpu: cpu_info.snapshot(),
+            mem: memory_info.snapshot(),
+            procs: processes.iter().map(ProcessSnapshot::from).collect(),
+            net: None,
+        }
+    }
+
+    pub fn with_network(mut self, network: NetworkSnapshot) -> Self {
+        self.net = Some(network);
+        self
+    }
+}
+
+impl From<&Process> for ProcessSnapshot {
+    fn from(process: &Process) -> Self {
+        Self {
+            pid: process.pid.as_u32(),
+            name: process.name.clone(),
+            cpu_usage: process.cpu_usage,
+            memory_mb: process.memory_mb,
+            status: process::status_name(process.status).to_string(),
+            parent_pid: process.parent_pid.map(|pid| pid.as_u32()),
+            pgid: process.pgid.map(|pid| pid.as_u32()),
+            sid: process.sid.map(|pid| pid.as_u32()),
+            origin: process.sandbox.as_ref().map(|sandbox| sandbox.to_string()),
+            cpu_time_ms: process.cpu_time_ms,
+            user: process.user.clone(),
+            swap_bytes: process.swap_bytes,
+            thread: process.thread_kind.map(|kind| {
+                match kind {
+                    ThreadKind::Kernel => "kernel",
+                    ThreadKind::Userland => "user",
+                }
+                .to_string()
+            }),
+        }
+    }
+}
+
+/// Rebuild a process from a snapshot received from another instance
+impl From<&ProcessSnapshot> for Process {
+    fn from(snapshot: &ProcessSnapshot) -> Self {
+        Self {
+            pid: Pid::from_u32(snapshot.pid),
+            name: snapshot.name.clone(),
+            cpu_usage: snapshot.cpu_usage,
+            memory_mb: snapshot.memory_mb,
+            status: process::status_from_name(&snapshot.status),
+            parent_pid: snapshot.parent_pid.map(Pid::from_u32),
+            pgid: snapshot.pgid.map(Pid::from_u32),
+            sid: snapshot.sid.map(Pid::from_u32),
+            sandbox: snapshot.origin.as_deref().and_then(Sandbox::parse),
+            cpu_time_ms: snapshot.cpu_time_ms,
+            swap_bytes: snapshot.swap_bytes,
+            fd_count: None,
+            memory_delta_mb: None,
+            cpu_delta: None,
+            energy_nj: None,
+            power_mw: None,
+            user: snapshot.user.clone(),
+            cmdline: String::new(),
+            pod: None,
+            event_counters: None,
+            event_rates: None,
+            cwd: None,
+            exe: None,
+            exe_deleted: false,
+            thread_kind: match snapshot.thread.as_deref() {
+                Some("kernel") => Some(ThreadKind::Kernel),
+                Some("user") => Some(ThreadKind::Userland),
+                _ => None,
+            },
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+
+use crate::cli::{RecordOptions, StreamFormat};
+use crate::columns::{self, Column};
+use crate::cpu::CpuInfo;
+use crate::history_db::{self, HistoryDb};
+use crate::influx::{Endpoint, Exporter};
+use crate::memory::MemoryInfo;
+use crate::process::{self, CpuTimes, ProcessHistory};
+use crate::recorder::RotatingWriter;
+use crate::snapshot::Snapshot;
+
+enum Sink {
+    Stdout(io::StdoutLock<'static>),
+    File(RotatingWriter),
+}
+
+impl Sink {
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Sink::Stdout(stdout) => writeln!(stdout, "{}", line).and_then(|_| stdout.flush()),
+            Sink::File(writer) => writer.write_line(line),
+        }
+    }
+}
+
+/// Print one snapshot per line until interrupted or stdout is closed,
+/// or record them to a rotating file when `record` is set. With `columns`
+/// processes only carry those fields. With `history_db` the totals are also
+/// recorded there, with `influx` pushed there.
+pub fn run(
+    format: StreamFormat,
+    interval: Duration,
+    record: Option<RecordOptions>,
+    columns: Option<Vec<Column>>,
+    history_db: Option<PathBuf>,
+    influx: Option<(Endpoint, Duration)>,
+) -> Result<()> {
+    let mut sink = match record {
+        Some(options) => Sink::File(RotatingWriter::open(options)?),
+        None => Sink::Stdout(io::stdout().lock()),
+    };
+    let mut cpu_info = CpuInfo::new_sampled();
+    let mut memory_info = MemoryInfo::new();
+    let mut history = ProcessHistory::default();
+    let mut cpu_times = CpuTimes::default();
+    let history_db = history_db
+        .map(|path| {
+            HistoryDb::open(&path)
+                .wrap_err_with(|| format!("failed to open history database {}", path.display()))
+        })
+        .transpose()?;
+    let mut last_recorded: Option<Instant> = None;
+    let mut exporter =
+        influx.map(|(endpoint, interval)| (Exporter::start(endpoint, interval), None::<Instant>));
+    let wants_events = columns
+        .iter()
+        .flatten()
+        .any(|column| Column::EVENTS.contains(column));
+
+    loop {
+        cpu_info.update();
+        memory_info.update();
+        let mut processes = process::get_all_processes();
+        cpu_times.fill(&mut processes);
+        process::sort_by_memory(&mut processes);
+        if wants_events {
+            for process in &mut processes {
+                process.event_counters = process::event_counters(process.pid);
+            }
+        }
+        history.update(&processes);
+        history.fill_deltas(&mut processes);
+        let snapshot = Snapshot::capture(&cpu_info, &memory_info, &processes);
+        if let Some(db) = &history_db {
+            if last_recorded.is_none_or(|at| at.elapsed() >= history_db::RECORD_INTERVAL) {
+                db.record(&snapshot.cpu, &snapshot.mem)?;
+                last_recorded = Some(Instant::now());
+            }
+        }
+        if let Some((exporter, last_pushed)) = &mut exporter {
+            if last_pushed.is_none_or(|at| at.elapsed() >= exporter.interval) {
+                // No log panel here, failures go to stderr
+                let failing = exporter.last_error.is_some();
+                exporter.send(&snapshot);
+                match (&exporter.last_error, failing) {
+                    (Some(e), false) => eprintln!("influx push failing: {}", e),
+                    (None, true) => eprintln!("influx push works again"),
+                    _ => {}
+                }
+                *last_pushed = Some(Instant::now());
+            }
+        }
+
+        let line = match (format, &columns) {
+            (StreamFormat::Json, None) => serde_json::to_string(&snapshot)?,
+            (StreamFormat::Json, Some(columns)) => {
+                let mut value = serde_json::to_value(&snapshot)?;
+                value["processes"] = processes
+                    .iter()
+                    .map(|process| columns::to_json(columns, process))
+                    .collect();
+                value.to_string()
+            }
+        };
+
+        // Reader went away (e.g. `| head`), that's a normal way to stop
+        match sink.write_line(&line) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+
+        thread::sleep(interval);
+    }
+}
@@ -0,0 +1,170 @@
+//! Light or dark terminal background, so the colors can be adjusted for
+//! light terminals. Asked with an OSC 11 query where the terminal answers it,
+//! otherwise taken from `COLORFGBG` as set by rxvt, Konsole and others.
+
+use std::sync::OnceLock;
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+use crate::commands::Theme;
+
+/// Upper bound on the wait for the terminal's answers at startup
+#[cfg(unix)]
+const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+static LIGHT_BACKGROUND: OnceLock<bool> = OnceLock::new();
+
+/// Ask the terminal once, it has to be in raw mode and nothing else may be
+/// reading input yet. Dark when it can't be told.
+pub fn detect() {
+    let light = query_background()
+        .or_else(|| {
+            std::env::var("COLORFGBG")
+                .ok()
+                .and_then(|value| parse_colorfgbg(&value))
+        })
+        .unwrap_or(false);
+    tracing::info!(
+        "terminal background is {}",
+        if light { "light" } else { "dark" }
+    );
+    let _ = LIGHT_BACKGROUND.set(light);
+}
+
+/// Whether `theme` ends up with the light variant, auto and contrast follow the terminal
+pub fn is_light(theme: Theme) -> bool {
+    match theme {
+        Theme::Light => true,
+        Theme::Dark => false,
+        Theme::Auto | Theme::Contrast | Theme::Mono => {
+            LIGHT_BACKGROUND.get().copied().unwrap_or(false)
+        }
+    }
+}
+
+/// Rewrite a rendered frame for a light background. The render code picks
+/// colors for dark terminals, so white turns black, light colors turn into
+/// their darker versions and dark backgrounds into pale tints.
+pub fn adapt_to_light(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        cell.fg = foreground_on_light(cell.fg);
+        cell.bg = background_on_light(cell.bg);
+    }
+}
+
+/// Drop every color from a rendered frame. Cells that stood out by their
+/// background (selection, gauge fills) are shown in reverse video instead.
+pub fn strip_colors(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
+}
+
+fn foreground_on_light(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Gray => Color::DarkGray,
+        Color::LightRed => Color::Red,
+        Color::LightGreen | Color::Green => Color::Rgb(0, 120, 0),
+        Color::LightYellow | Color::Yellow => Color::Rgb(140, 100, 0),
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan | Color::Cyan => Color::Rgb(0, 115, 130),
+        other => other,
+    }
+}
+
+fn background_on_light(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        // Three quarters of the way to white keeps the hue
+        Color::Rgb(r, g, b) => {
+            let pale = |c: u8| c + (255 - c) / 4 * 3;
+            Color::Rgb(pale(r), pale(g), pale(b))
+        }
+        other => other,
+    }
+}
+
+/// "15;0" or "0;default;15", the last field is the background palette index
+fn parse_colorfgbg(value: &str) -> Option<bool> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    // 7 is light gray and 9-15 the bright colors, 8 is dark gray
+    Some(background == 7 || (9..=15).contains(&background))
+}
+
+/// "\x1b]11;rgb:ffff/ffff/dddd\x1b\\" with 1 to 4 hex digits per component
+fn parse_osc11(reply: &str) -> Option<bool> {
+    let rgb = &reply[reply.find("rgb:")? + 4..];
+    let mut channels = rgb.split('/').map(|part| {
+        let digits: String = part
+            .chars()
+            .take_while(char::is_ascii_hexdigit)
+            .take(4)
+            .collect();
+        let max = (1u32 << (4 * digits.len() as u32)) - 1;
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .map(|value| value as f64 / max as f64)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    Some(0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5)
+}
+
+/// Sends OSC 11 followed by a device attributes request. Every terminal answers
+/// the latter, so when that answer arrives first OSC 11 isn't supported and
+/// there's no need to wait out the timeout.
+#[cfg(unix)]
+fn query_background() -> Option<bool> {
+    use std::io::Write;
+    use std::time::Instant;
+
+    // SAFETY: isatty only inspects the descriptors
+    if unsafe { libc::isatty(libc::STDIN_FILENO) == 0 || libc::isatty(libc::STDOUT_FILENO) == 0 } {
+        return None;
+    }
+    let mut stdout = std::io::stdout();
+    stdout.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    stdout.flush().ok()?;
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut poll = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: one valid pollfd
+        if unsafe { libc::poll(&mut poll, 1, remaining.as_millis() as libc::c_int) } <= 0 {
+            break;
+        }
+        let mut chunk = [0u8; 256];
+        // SAFETY: reads into a buffer of the given length
+        let read =
+            unsafe { libc::read(libc::STDIN_FILENO, chunk.as_mut_ptr().cast(), chunk.len()) };
+        if read <= 0 {
+            break;
+        }
+        reply.extend_from_slice(&chunk[..read as usize]);
+        // The device attributes answer, "\x1b[?62;22c", comes last
+        if reply.windows(3).any(|w| w == b"\x1b[?") && reply.ends_with(b"c") {
+            break;
+        }
+    }
+    parse_osc11(&String::from_utf8_lossy(&reply))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<bool> {
+    None
+}
@@ -0,0 +1,107 @@
+//! `møas top`: the busiest processes as one plain table on stdout, for
+//! scripts and slow links where the TUI is more than needed.
+
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use ratatui::layout::Constraint;
+
+use crate::cli::TopSort;
+use crate::columns::Column;
+use crate::process::{self, Process};
+
+/// Time between the two readings CPU usage is computed from
+const SAMPLE_TIME: Duration = Duration::from_millis(500);
+
+const COLUMNS: [Column; 7] = [
+    Column::Pid,
+    Column::User,
+    Column::Cpu,
+    Column::Rss,
+    Column::Time,
+    Column::Status,
+    Column::Cmdline,
+];
+
+pub fn run(count: usize, sort: TopSort) -> Result<()> {
+    let before: HashMap<_, _> = process::get_all_processes()
+        .into_iter()
+        .map(|p| (p.pid, p.cpu_time_ms))
+        .collect();
+    let started = Instant::now();
+    thread::sleep(SAMPLE_TIME);
+    let mut processes: Vec<Process> = process::get_all_processes()
+        .into_iter()
+        .filter(|p| p.thread_kind.is_none())
+        .collect();
+    process::fill_cpu_usage(&mut processes, &before, started.elapsed());
+    match sort {
+        TopSort::Cpu => process::sort_by_cpu(&mut processes),
+        TopSort::Memory => process::sort_by_memory(&mut processes),
+    }
+
+    // Like ps, rows are cut to the terminal but kept whole in pipes
+    let width = io::stdout()
+        .is_terminal()
+        .then(|| ratatui::crossterm::terminal::size().ok())
+        .flatten()
+        .map(|(columns, _)| columns as usize);
+    let mut out = String::new();
+    let header = COLUMNS.map(|column| column.title().to_string());
+    for cells in std::iter::once(header).chain(
+        processes
+            .iter()
+            .take(count)
+            .map(|process| COLUMNS.map(|column| cell(column, process))),
+    ) {
+        let line = row(cells);
+        match width {
+            Some(width) => out.extend(line.chars().take(width)),
+            None => out.push_str(&line),
+        }
+        out.push('\n');
+    }
+    // Reader went away (e.g. `| head`), that's fine for a one-shot
+    match io::stdout().lock().write_all(out.as_bytes()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Cells padded to the TUI's column widths, numbers right-aligned
+fn row(cells: [String; COLUMNS.len()]) -> String {
+    let mut line = String::new();
+    for (column, text) in COLUMNS.iter().zip(cells) {
+        match column.width() {
+            Constraint::Length(width) => {
+                let width = width as usize;
+                let text: String = text.chars().take(width).collect();
+                if matches!(
+                    column,
+                    Column::Pid | Column::Cpu | Column::Rss | Column::Time
+                ) {
+                    line.push_str(&format!("{:>width$}  ", text));
+                } else {
+                    line.push_str(&format!("{:<width$}  ", text));
+                }
+            }
+            _ => line.push_str(&text),
+        }
+    }
+    line.truncate(line.trim_end().len());
+    line
+}
+
+/// Kernel threads and zombies have no command line, top shows the name in
+/// brackets. Newlines in arguments would break the table.
+fn cell(column: Column, process: &Process) -> String {
+    let text = match column {
+        Column::Cmdline if process.cmdline.is_empty() => format!("[{}]", process.name),
+        Column::User if process.user.is_none() => "-".to_string(),
+        _ => column.text(process),
+    };
+    text.replace(char::is_control, " ")
+}
@@ -1,561 +1,5187 @@
-use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::cpu::CpuInfo;
-use crate::memory::MemoryInfo;
-use crate::process::{self, get_all_processes, Process};
+use crate::alerts::{AlertMonitor, Readings};
+use crate::battery::{self, BatteryMonitor, Estimate};
+use crate::capabilities::{self, Capabilities, Capability};
+use crate::cli::Args;
+use crate::columns::Column;
+use crate::commands::{self, Command, Theme};
+use crate::config::{self, FilterPreset};
+use crate::cpu::{CpuInfo, HardwareInfo};
+use crate::cpufreq;
+use crate::crash;
+use crate::diff::{Baseline, Change};
+use crate::disk::DiskInfo;
+use crate::export;
+use crate::fuzzy;
+use crate::history::{self, CHART_PRESETS};
+use crate::history_db::{self, Bucket, HistoryDb, HistoryRange};
+use crate::influx::Exporter;
+use crate::job::Job;
+use crate::kernel::{self, KernelStats};
+use crate::kube::PodIndex;
+use crate::logging::{self, LogBuffer};
+use crate::memory::{self, MemoryDetails, MemoryInfo, UsageLevel, UsageThresholds};
+use crate::mirror;
+use crate::network::{self, Neighbor, NetworkMonitor};
+use crate::numa::{self, NumaInfo};
+use crate::panels::{self, CpuPanel, DiskPanel, MemoryPanel, NetworkPanel, ProcessPanel};
+use crate::probes::{self, Probes};
+use crate::process::{
+    self, get_all_processes, CpuTimes, Critical, DStateTracker, FollowedTree, IoPriority,
+    LeakDetector, LifecycleKind, LifecycleTracker, Owned, Process, ProcessHistory, SelectedHistory,
+    SortKey, StatusFilter, WaitTrace, SELECTED_HISTORY_CAPACITY,
+};
+use crate::remote;
+use crate::selfmon::{self, CollectorBudget, Freshness, SelfMonitor};
+use crate::services::{self, CgroupPressure, ServiceAction, ServiceInfo};
+use crate::sessions::{self, SessionInfo};
+#[cfg(feature = "smart")]
+use crate::smart::{self, SmartHealth};
+use crate::snapshot::Snapshot;
+use crate::theme;
+use crate::ui_state::{self, UiState};
+use crate::units;
+use color_eyre::eyre::{eyre, WrapErr};
 use color_eyre::Result;
+use m_as::Sampler;
 use ratatui::crossterm::event::{self, Event, KeyCode};
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::symbols::Marker;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
-    Axis, Block, BorderType, Borders, Cell, Chart, Dataset, GraphType, Paragraph, Row, Table,
-    TableState,
+    Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, GraphType, List, ListItem,
+    ListState, Paragraph, Row, Sparkline, Table, TableState, Tabs,
 };
 use ratatui::{DefaultTerminal, Frame};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::thread;
-use sysinfo::ProcessStatus;
-
-const CORE_COLORS: &[Color] = &[
-    Color::Red,
-    Color::Green,
-    Color::Yellow,
-    Color::Blue,
-    Color::Magenta,
-    Color::Cyan,
-    Color::Gray,
-    Color::LightRed,
-    Color::LightGreen,
-    Color::LightYellow,
-    Color::LightBlue,
-    Color::LightMagenta,
-    Color::LightCyan,
-];
-
-const fn make_highlight_style() -> Style {
+use sysinfo::Pid;
+
+const TOP_N: usize = 5;
+// How long the UI thread sleeps waiting for input before checking for new data
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the process list (and memory) is refreshed
+pub const PROCESS_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+// Disk throughput is a rate, sample it on a steady 1s tick
+pub const DISK_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+pub const NETWORK_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// Context switch and interrupt rates, a 1s tick like the other rates
+pub const KERNEL_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+// Sessions and auth logs barely change, no need to hit utmp often
+pub const SESSION_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+pub const SERVICE_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+/// Open files listed in the detail popup before it gets too tall
+const DETAIL_MAX_FILES: usize = 15;
+/// Most frequent waits and innermost kernel frames shown for a wait trace
+const WAIT_TRACE_TOP: usize = 5;
+const WAIT_TRACE_FRAMES: usize = 4;
+pub const NUMA_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+/// Hugepages and buddyinfo, only read while the memory details are open
+pub const MEMORY_DETAILS_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+pub const NEIGHBOR_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+/// Probes run on their own threads, this only picks up their results
+pub const PROBE_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// Battery counters only move every few seconds
+pub const BATTERY_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+/// Wait trace samples of the detail process, often enough to catch short waits
+pub const WAIT_TRACE_INTERVAL: Duration = Duration::from_millis(100);
+/// smartctl is slow and SMART attributes change slowly
+#[cfg(feature = "smart")]
+pub const SMART_UPDATE_INTERVAL: Duration = Duration::from_secs(300);
+
+const ALERT_FLASH_DURATION: Duration = Duration::from_secs(1);
+
+/// Redraws caused by new data are held back to this rate in accessible mode
+const REDUCED_MOTION_FRAME_INTERVAL: Duration = Duration::from_secs(2);
+/// Redraw at least this often so the data age in panel titles keeps counting
+/// up while a collector is slow or stuck
+const DATA_AGE_REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+// Read by the style helpers, which are called from render functions that don't get the state
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+
+/// High contrast, reduced motion mode (--accessible or `A`), also part of monochrome
+pub fn accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed) || monochrome()
+}
+
+// Set from the theme, frames are recolored for light terminals after rendering
+static LIGHT: AtomicBool = AtomicBool::new(false);
+// Colors are stripped after rendering (--monochrome, NO_COLOR or `:theme mono`)
+static MONOCHROME: AtomicBool = AtomicBool::new(false);
+
+fn monochrome() -> bool {
+    MONOCHROME.load(Ordering::Relaxed)
+}
+
+/// Rows tagged with Space for batch actions
+pub fn make_tagged_style() -> Style {
+    if accessible() {
+        return Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    }
+    Style::new()
+        .bg(Color::Rgb(70, 40, 60))
+        .add_modifier(Modifier::BOLD)
+}
+
+pub fn make_highlight_style() -> Style {
+    if accessible() {
+        return Style::new()
+            .bg(Color::White)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD);
+    }
     Style::new()
         .bg(Color::Rgb(70, 70, 90))
         .fg(Color::White)
         .add_modifier(Modifier::BOLD)
 }
 
+/// Failed actions also go to the log, the status line is gone with the next key
+fn failure(message: String) -> String {
+    tracing::warn!("{}", message);
+    message
+}
+
+/// Border of the block that has focus (popups), much stronger in accessible mode
+fn focus_border(block: Block<'_>, color: Color) -> Block<'_> {
+    if accessible() {
+        block.border_type(BorderType::Thick).border_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        block
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(color))
+    }
+}
+
+/// " ↓12 " while a newest-first list is scrolled away from the top
+fn scrolled_title(scroll: usize) -> String {
+    if scroll == 0 {
+        String::new()
+    } else {
+        format!(" ↓{} ", scroll)
+    }
+}
+
+/// Thick border on the panel the arrow keys go to
+pub fn panel_border(block: Block<'_>, color: Color, focused: bool) -> Block<'_> {
+    if !focused {
+        return block
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(color));
+    }
+    if accessible() {
+        return focus_border(block, color);
+    }
+    block
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tab {
+    Overview,
+    Services,
+    Diff,
+    Numa,
+    Users,
+    Network,
+    Log,
+    Power,
+    History,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 9] = [
+        Tab::Overview,
+        Tab::Services,
+        Tab::Diff,
+        Tab::Numa,
+        Tab::Users,
+        Tab::Network,
+        Tab::Log,
+        Tab::Power,
+        Tab::History,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Services => "Services",
+            Tab::Diff => "Diff",
+            Tab::Numa => "NUMA",
+            Tab::Users => "Users",
+            Tab::Network => "Network",
+            Tab::Log => "Log",
+            Tab::Power => "Power",
+            Tab::History => "History",
+        }
+    }
+}
+
+/// Panels that take the arrow keys, Tab/Shift-Tab cycles through the ones on the active tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Focus {
+    Processes,
+    Sessions,
+    Events,
+    Neighbors,
+    Log,
+}
+
+impl Focus {
+    /// Empty for tabs that handle the arrow keys themselves
+    fn of_tab(tab: Tab) -> &'static [Focus] {
+        match tab {
+            Tab::Overview => &[Focus::Processes, Focus::Sessions, Focus::Events],
+            Tab::Network => &[Focus::Neighbors],
+            Tab::Log => &[Focus::Log],
+            Tab::Services | Tab::Diff | Tab::Numa | Tab::Users | Tab::Power | Tab::History => &[],
+        }
+    }
+}
+
 pub struct AppState {
+    pub active_tab: Tab,
+    /// Last focused panel, the first one of the tab when it isn't on the active tab
+    pub focus: Focus,
+    /// Scroll position of the panels other than the process table, in rows
+    pub panel_scroll: HashMap<Focus, usize>,
     pub cpu_info: CpuInfo,
+    /// CPU model, caches and topology, read once at startup
+    pub hardware: HardwareInfo,
+    /// Hardware popup, toggled with I
+    pub hardware_open: bool,
     pub memory_info: MemoryInfo,
+    pub memory_thresholds: UsageThresholds,
+    /// Hugepage and fragmentation view, toggled with M
+    pub memory_details: Option<MemoryDetails>,
+    /// Sampler timing popup
+    pub diagnostics_open: bool,
+    /// Last one picked with `:theme`, the one that is saved. `--accessible`,
+    /// `--monochrome` and NO_COLOR only apply to the current run.
+    pub theme: Theme,
+    pub disk_info: DiskInfo,
+    /// SMART health by disk device name
+    #[cfg(feature = "smart")]
+    pub smart: HashMap<String, SmartHealth>,
+    pub network: NetworkMonitor,
+    pub kernel: KernelStats,
+    pub battery: BatteryMonitor,
+    /// `--history-db`, None when not recording
+    pub history_db: Option<HistoryDb>,
+    /// `--influx` pusher
+    pub influx: Option<Exporter>,
+    pub history_range: HistoryRange,
+    /// Summary of `history_range`, refreshed while the History tab is open
+    pub history_buckets: Vec<Bucket>,
+    pub history_error: Option<String>,
+    pub selected_bucket: usize,
+    /// ARP/neighbor table, only refreshed while the Network tab is open
+    pub neighbors: Vec<Neighbor>,
+    /// Ping and DNS latency of the configured targets
+    pub probes: Probes,
+    /// Recent internal log entries for the Log tab
+    pub log: LogBuffer,
+    pub session_info: SessionInfo,
+    pub service_info: ServiceInfo,
+    pub selected_service: usize,
+    pub numa_info: NumaInfo,
+    /// Every process from the last refresh, `processes` is the filtered view of it
+    pub all_processes: Vec<Process>,
     pub processes: Vec<Process>,
+    pub status_filter: StatusFilter,
+    /// Case-insensitive text the name or command line must contain, set with `:filter`
+    pub name_filter: Option<String>,
+    /// `name_filter` is matched fuzzily and the list ranked by match quality
+    pub fuzzy_filter: bool,
+    /// `name_filter` compiled, when it was given as `:filter re:PATTERN`
+    pub regex_filter: Option<Regex>,
+    /// `[filters]` from the config file, cycled with `p`
+    pub filter_presets: Vec<FilterPreset>,
+    /// Index into `filter_presets`, applied on top of the other filters
+    pub filter_preset: Option<usize>,
+    pub sort_key: SortKey,
+    /// Breaks ties of `sort_key`, set with `:sort cpu,memory`
+    pub then_sort_key: Option<SortKey>,
+    /// Command palette input while it's open
+    pub command_line: Option<String>,
+    /// Process list refresh interval, changed with `:interval`
+    pub process_interval: Duration,
+    pub process_history: ProcessHistory,
+    /// Last CPU time per process, for the usage of the next refresh
+    cpu_times: CpuTimes,
+    /// Per second history of the selected process (or the one in the detail popup)
+    pub selected_history: SelectedHistory,
+    pub d_state_tracker: DStateTracker,
+    pub leaks: LeakDetector,
+    pub lifecycle: LifecycleTracker,
     pub selected_process: usize,
+    /// Rows keep their order for this long after the last navigation key, None to never hold
+    pub freeze_delay: Option<Duration>,
+    pub navigated_at: Option<Instant>,
+    /// Processes tagged with Space, batch actions apply to these
+    pub tagged: HashSet<Pid>,
     pub scroll_offset: usize,
+    /// Highlighted preset while the chart options popup is open
+    pub chart_options: Option<usize>,
+    /// Line charts show an exponential moving average instead of the raw samples
+    pub smooth_charts: bool,
+    pub smoothing_alpha: f64,
+    pub governor_menu: Option<GovernorMenu>,
+    pub show_group_columns: bool,
+    /// Counting descriptors means a readdir per process, so it's opt-in
+    pub show_fd_column: bool,
+    /// Fault and context switch rates, two /proc reads per process so opt-in too
+    pub show_event_columns: bool,
+    /// Kubernetes pod of containerized processes, names from `pod_index`
+    pub show_pod_column: bool,
+    pub pod_index: PodIndex,
+    /// CWD and EXE columns, toggled with X
+    pub show_path_columns: bool,
+    /// ΔMEM and ΔCPU since the previous refresh
+    pub show_delta_columns: bool,
+    /// Destructive action waiting for the user to confirm with y/n
+    pub pending_action: Option<ConfirmAction>,
+    /// Set when the pending kill hits a critical process
+    pub name_guard: Option<NameGuard>,
+    /// Result of the last action, shown until the next key press
+    pub status_message: Option<String>,
+    /// What the monitor isn't allowed to see or do
+    pub capabilities: Capabilities,
+    /// Set by the sampler thread whenever new data arrived since the last frame
+    pub dirty: bool,
+    /// When each collector last updated its panels
+    pub freshness: Freshness,
+    pub alerts: AlertMonitor,
+    /// Ring the terminal bell / flash the screen border when an alert fires
+    pub bell: bool,
+    pub flash: bool,
+    /// Set by the samplers, the UI thread writes the bell character
+    pub ring_bell: bool,
+    pub flash_until: Option<Instant>,
+    pub self_monitor: SelfMonitor,
+    pub collector_budget: CollectorBudget,
+    /// Command started with `møas run`, the TUI quits when it exits
+    pub job: Option<Job>,
+    /// Only list the job and its children
+    pub job_only: bool,
+    /// Periods from the config file's `[intervals]`, they replace the built-in ones
+    pub sampler_intervals: HashMap<String, Duration>,
+    /// Scheduler tick the latest samples belong to
+    pub sampled_at: SystemTime,
+    pub show_overhead: bool,
+    /// Per-core heatmap instead of the line chart
+    pub cpu_heatmap: bool,
+    /// Cores list in the rainbow colors of their chart lines instead of load bands, toggled with C
+    pub core_colors: bool,
+    /// Set by --serve-readonly, destructive keys are dropped from the keymap
+    pub read_only: bool,
+    /// PIDs are another host's (--connect, --ssh), the local /proc knows nothing about them
+    pub remote: bool,
+    /// Fed by another instance's snapshots (--mirror, --connect, --ssh), which
+    /// only carry CPU, memory and processes. The local samplers don't run.
+    pub from_snapshots: bool,
+    /// Process shown in the detail popup, pinned by PID so re-sorting doesn't change it
+    pub detail_pid: Option<Pid>,
+    /// Descendants of the detail process, aggregated in the popup
+    pub followed_tree: Option<FollowedTree>,
+    /// Environment list opened from the detail popup
+    pub env_view: Option<EnvView>,
+    /// CPU list being typed in the detail popup, e.g. "0-3,8"
+    pub affinity_input: Option<String>,
+    /// I/O priority being typed in the detail popup, e.g. "be/4"
+    pub io_priority_input: Option<String>,
+    /// oom_score_adj being typed in the detail popup
+    pub oom_adj_input: Option<String>,
+    /// Syscall and wchan sampling of the detail process, toggled with `w`
+    pub wait_trace: Option<WaitTrace>,
+    /// Marked with `b`, the Diff tab compares the current processes against it
+    pub baseline: Option<Baseline>,
+    pub selected_delta: usize,
+}
+
+/// Governor picker, opened with `f` on the CPU panel
+pub struct GovernorMenu {
+    /// Governors every policy offers
+    pub governors: Vec<String>,
+    pub selected: usize,
+    /// Index into `CpuInfo::policies` the choice applies to, None for all of them
+    pub policy: Option<usize>,
+    /// Whether the sysfs files are writable, checked when the menu opens
+    pub can_switch: bool,
+}
+
+pub struct EnvView {
+    pub vars: Vec<(String, String)>,
+    pub scroll: usize,
+    pub search: String,
+    /// Keys go to the search field while set
+    pub searching: bool,
+    /// Show values of sensitive looking variables instead of masking them
+    pub reveal: bool,
+}
+
+impl EnvView {
+    /// Variables matching the search, values only count while revealed
+    pub fn filtered(&self) -> Vec<&(String, String)> {
+        let search = self.search.to_lowercase();
+        self.vars
+            .iter()
+            .filter(|(name, value)| {
+                name.to_lowercase().contains(&search)
+                    || (self.shows_value(name) && value.to_lowercase().contains(&search))
+            })
+            .collect()
+    }
+
+    pub fn shows_value(&self, name: &str) -> bool {
+        self.reveal || !process::is_sensitive_var(name)
+    }
+}
+
+pub enum ConfirmAction {
+    KillGroup {
+        pgid: Pid,
+        members: Vec<(Pid, String)>,
+    },
+    /// Tagged processes, or just the selected one
+    Kill {
+        members: Vec<(Pid, String)>,
+    },
+    /// Children first, the root process last
+    KillTree {
+        root: Pid,
+        members: Vec<(Pid, String)>,
+    },
+    Service {
+        unit: String,
+        action: ServiceAction,
+    },
+}
+
+/// A kill that hits a critical process only goes through once its name was typed
+#[derive(Debug, Clone)]
+pub struct NameGuard {
+    pub pid: Pid,
+    pub name: String,
+    pub reason: Critical,
+    pub typed: String,
 }
 
 impl AppState {
-    pub fn new() -> Self {
-        let mut processes = process::get_all_processes();
-        process::sort_by_memory(&mut processes);
+    pub fn new(args: &Args) -> Self {
+        // Cheap to create, the first real readings come from the samplers
+        let cpu_info = CpuInfo::new();
 
         Self {
-            cpu_info: CpuInfo::new(),
+            active_tab: Tab::Overview,
+            focus: Focus::Processes,
+            panel_scroll: HashMap::new(),
+            service_info: ServiceInfo::new(),
+            selected_service: 0,
+            numa_info: NumaInfo::new(),
+            all_processes: Vec::new(),
+            status_filter: StatusFilter::All,
+            name_filter: None,
+            fuzzy_filter: false,
+            regex_filter: None,
+            filter_presets: Vec::new(),
+            filter_preset: None,
+            command_line: None,
+            process_interval: PROCESS_UPDATE_INTERVAL,
+            sort_key: SortKey::Memory,
+            then_sort_key: None,
+            process_history: ProcessHistory::default(),
+            cpu_times: CpuTimes::default(),
+            selected_history: SelectedHistory::default(),
+            d_state_tracker: DStateTracker::default(),
+            leaks: LeakDetector::new(args.leak_window, args.leak_slope),
+            lifecycle: LifecycleTracker::default(),
+            hardware: cpu_info.hardware(),
+            cpu_info,
             memory_info: MemoryInfo::new(),
-            processes,
+            memory_thresholds: args.memory_thresholds,
+            disk_info: DiskInfo::new(args.mount_filter.clone()),
+            #[cfg(feature = "smart")]
+            smart: HashMap::new(),
+            memory_details: None,
+            diagnostics_open: false,
+            theme: Theme::Auto,
+            hardware_open: false,
+            network: NetworkMonitor::new(),
+            kernel: KernelStats::new(),
+            battery: BatteryMonitor::new(),
+            history_db: None,
+            influx: None,
+            history_range: HistoryRange::Hours6,
+            history_buckets: Vec::new(),
+            history_error: None,
+            selected_bucket: 0,
+            neighbors: Vec::new(),
+            probes: Probes::new(&args.probes),
+            log: LogBuffer::default(),
+            session_info: SessionInfo::new(),
+            processes: Vec::new(),
             selected_process: 0,
+            freeze_delay: args.freeze_delay,
+            navigated_at: None,
+            tagged: HashSet::new(),
             scroll_offset: 0,
+            chart_options: None,
+            smooth_charts: false,
+            smoothing_alpha: args.smoothing,
+            governor_menu: None,
+            show_group_columns: false,
+            show_fd_column: false,
+            show_event_columns: false,
+            show_pod_column: false,
+            show_path_columns: false,
+            pod_index: PodIndex::default(),
+            show_delta_columns: false,
+            pending_action: None,
+            name_guard: None,
+            status_message: None,
+            capabilities: Capabilities::detect(),
+            dirty: true,
+            freshness: Freshness::default(),
+            alerts: AlertMonitor::new(&args.alerts),
+            bell: args.bell,
+            flash: args.flash,
+            ring_bell: false,
+            flash_until: None,
+            self_monitor: SelfMonitor::new(),
+            collector_budget: CollectorBudget::new(args.sampler_budget),
+            job: None,
+            job_only: true,
+            sampler_intervals: HashMap::new(),
+            sampled_at: SystemTime::now(),
+            show_overhead: args.show_overhead,
+            cpu_heatmap: false,
+            core_colors: false,
+            read_only: args.read_only,
+            remote: args.connect.is_some() || args.ssh.is_some(),
+            from_snapshots: args.mirror || args.connect.is_some() || args.ssh.is_some(),
+            detail_pid: None,
+            followed_tree: None,
+            env_view: None,
+            affinity_input: None,
+            io_priority_input: None,
+            oom_adj_input: None,
+            wait_trace: None,
+            baseline: None,
+            selected_delta: 0,
         }
     }
 
     pub fn update_processes(&mut self) {
-        self.processes = get_all_processes();
-        process::sort_by_memory(&mut self.processes);
+        let mut processes = get_all_processes();
+        self.cpu_times.fill(&mut processes);
+        self.set_processes(processes);
     }
-}
 
-pub fn main() -> Result<()> {
-    color_eyre::install()?;
-    let terminal = ratatui::init();
-    let result = run(terminal);
-    ratatui::restore();
-    result
-}
+    fn set_processes(&mut self, mut processes: Vec<Process>) {
+        if !self.remote {
+            self.read_local_details(&mut processes);
+        }
+        self.all_processes = processes;
+        // PIDs get reused, a tag must not carry over to an unrelated process
+        let alive: HashSet<Pid> = self.all_processes.iter().map(|p| p.pid).collect();
+        self.tagged.retain(|pid| alive.contains(pid));
+        self.process_history.update(&self.all_processes);
+        self.process_history.fill_deltas(&mut self.all_processes);
+        self.d_state_tracker.update(&self.all_processes);
+        self.leaks.update(&self.all_processes);
+        self.lifecycle.update(&self.all_processes);
+        if self.order_frozen() {
+            self.keep_order();
+        } else {
+            self.sort_and_filter();
+        }
 
-pub fn run(mut terminal: DefaultTerminal) -> Result<()> {
-    // Shared state between threads
-    let state = Arc::new(Mutex::new(AppState::new()));
-    let state_thread = Arc::clone(&state);
+        let watched = self.detail_pid.or_else(|| self.selected().map(|p| p.pid));
+        if let Some(process) =
+            watched.and_then(|pid| self.all_processes.iter().find(|p| p.pid == pid))
+        {
+            self.selected_history.record(process);
+        }
+    }
 
-    // Spawn background thread for data updates
-    thread::spawn(move || {
-        let mut last_cpu_update = Instant::now();
-        let cpu_update_interval = Duration::from_millis(1000);
+    /// Columns read from /proc per PID, only when they are shown
+    fn read_local_details(&mut self, processes: &mut [Process]) {
+        if self.show_fd_column {
+            for process in processes.iter_mut() {
+                process.fd_count = process::fd_count(process.pid);
+            }
+        }
+        for process in processes.iter_mut() {
+            if self.show_event_columns || self.detail_pid == Some(process.pid) {
+                process.event_counters = process::event_counters(process.pid);
+            }
+        }
+        if self.show_pod_column {
+            self.pod_index.annotate(processes);
+        }
+    }
 
-        loop {
-            let now = Instant::now();
+    fn sort_and_filter(&mut self) {
+        process::sort_processes(
+            &mut self.all_processes,
+            self.sort_key,
+            self.then_sort_key,
+            &self.process_history,
+        );
+        self.apply_filter();
+    }
 
-            // Update system information frequently (250ms)
-            {
-                let mut state = state_thread.lock().unwrap();
-                state.memory_info.update();
-                state.update_processes();
-            }
+    /// Whether the user navigated recently enough that rows shouldn't move
+    pub fn order_frozen(&self) -> bool {
+        self.freeze_delay
+            .zip(self.navigated_at)
+            .is_some_and(|(delay, at)| at.elapsed() < delay)
+    }
 
-            // Update CPU less frequently (1s) since it's more expensive
-            if now.duration_since(last_cpu_update) >= cpu_update_interval {
-                let mut state = state_thread.lock().unwrap();
-                state.cpu_info.update();
-                last_cpu_update = now;
-            }
+    pub fn touch_navigation(&mut self) {
+        self.navigated_at = Some(Instant::now());
+    }
 
-            thread::sleep(Duration::from_millis(50)); // Small sleep to prevent busy-wait
+    /// Back to live ordering right away instead of waiting for the delay
+    pub fn resume_ordering(&mut self) {
+        if self.navigated_at.take().is_some() {
+            self.sort_and_filter();
         }
-    });
+    }
 
-    // Main thread handles only UI and input
-    let mut visible_height = terminal.size()?.height as usize - 4;
-    loop {
-        // Non-blocking event processing
-        while event::poll(Duration::from_millis(0))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        let mut state = state.lock().unwrap();
-                        if state.selected_process < state.processes.len().saturating_sub(1) {
-                            state.selected_process += 1;
-                        }
-                        if state.selected_process >= state.scroll_offset + visible_height {
-                            state.scroll_offset = state.selected_process - visible_height + 1;
-                        }
-                    }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        let mut state = state.lock().unwrap();
-                        if state.selected_process > 0 {
-                            state.selected_process -= 1;
-                        }
-                        if state.selected_process < state.scroll_offset {
-                            state.scroll_offset = state.selected_process;
-                        }
-                    }
-                    _ => {}
-                }
-            }
+    /// Sort as usual, then move the rows that are already listed back to their
+    /// position. New processes go to the end, the selection follows its PID.
+    fn keep_order(&mut self) {
+        let selected = self.selected().map(|p| p.pid);
+        let positions: HashMap<Pid, usize> = self
+            .processes
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.pid, i))
+            .collect();
+        process::sort_processes(
+            &mut self.all_processes,
+            self.sort_key,
+            self.then_sort_key,
+            &self.process_history,
+        );
+        self.all_processes
+            .sort_by_key(|p| positions.get(&p.pid).copied().unwrap_or(usize::MAX));
+        self.apply_filter();
+        if let Some(index) =
+            selected.and_then(|pid| self.processes.iter().position(|p| p.pid == pid))
+        {
+            self.selected_process = index;
         }
+    }
 
-        // Smooth rendering at 60fps
-        terminal.draw(|f| {
-            visible_height = f.area().height as usize - 4;
-            let state = state.lock().unwrap();
-            render(f, &state)
-        })?;
-
-        // Small sleep to prevent 100% CPU usage on UI thread
-        thread::sleep(Duration::from_millis(16)); // ~60fps
+    /// "cpu" or "cpu, then memory"
+    pub fn sort_label(&self) -> String {
+        match self.then_sort_key {
+            Some(then) => format!("{}, then {}", self.sort_key.label(), then.label()),
+            None => self.sort_key.label().to_string(),
+        }
     }
-}
 
-fn render(frame: &mut Frame, state: &AppState) {
-    let main_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(40), // Top 40% for CPU
-            Constraint::Percentage(60), // Bottom 60% for processes, etc.
-        ])
-        .split(frame.area());
+    /// The panel arrow keys go to on the active tab
+    pub fn focused_panel(&self) -> Option<Focus> {
+        let panels = Focus::of_tab(self.active_tab);
+        if panels.contains(&self.focus) {
+            Some(self.focus)
+        } else {
+            panels.first().copied()
+        }
+    }
 
-    render_cpu_section(frame, &state.cpu_info, main_layout[0]);
+    pub fn cycle_focus(&mut self, forward: bool) {
+        let panels = Focus::of_tab(self.active_tab);
+        let Some(current) = self.focused_panel() else {
+            return;
+        };
+        let index = panels.iter().position(|p| *p == current).unwrap_or(0);
+        let next = if forward {
+            (index + 1) % panels.len()
+        } else {
+            (index + panels.len() - 1) % panels.len()
+        };
+        self.focus = panels[next];
+    }
 
-    let bottom_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(50), // Left 50% for processes
-            Constraint::Percentage(50), // Right 50% for other info
-        ])
-        .split(main_layout[1]);
+    pub fn panel_scroll(&self, panel: Focus) -> usize {
+        self.panel_scroll.get(&panel).copied().unwrap_or(0)
+    }
 
-    render_process_section(
-        frame,
-        &state.processes,
-        state.selected_process,
-        state.scroll_offset,
-        bottom_layout[0],
-    );
+    /// Move the focused panel (not the process table) by `rows`, kept within its items
+    pub fn scroll_focused(&mut self, rows: isize) {
+        let Some(panel) = self.focused_panel() else {
+            return;
+        };
+        let len = match panel {
+            Focus::Processes => return,
+            Focus::Sessions => self.session_info.sessions.len(),
+            Focus::Events => self.lifecycle.events.len(),
+            Focus::Neighbors => self.neighbors.len(),
+            Focus::Log => self.log.lock().unwrap().len(),
+        };
+        let offset = self
+            .panel_scroll(panel)
+            .saturating_add_signed(rows)
+            .min(len.saturating_sub(1));
+        self.panel_scroll.insert(panel, offset);
+    }
 
-    let right_side_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(50), // Top 50% for memory
-            Constraint::Percentage(50), // Bottom 50% for network
-        ])
-        .split(bottom_layout[1]);
+    /// EMA weight for the line charts, None while they show raw samples
+    pub fn smoothing(&self) -> Option<f64> {
+        self.smooth_charts.then_some(self.smoothing_alpha)
+    }
 
-    render_memory_section(frame, &state.memory_info, right_side_layout[0]);
-    render_network_section(frame, right_side_layout[1]);
-}
+    pub fn cycle_sort_key(&mut self) {
+        self.sort_key = self.sort_key.next();
+        // Cycling is the quick single key sort, `:sort` sets a second one
+        self.then_sort_key = None;
+        self.sort_and_filter();
+    }
 
-fn render_cpu_section(frame: &mut Frame, cpu_info: &CpuInfo, area: Rect) {
-    let cpu_block = Block::default()
-        .title("CPU Usage")
-        .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::LightCyan))
-        .style(Style::default());
+    fn apply_filter(&mut self) {
+        let filter = self.status_filter;
+        let preset = self.filter_preset.map(|i| &self.filter_presets[i]);
+        let job_tree: Option<HashSet<Pid>> =
+            self.job.as_ref().filter(|_| self.job_only).map(|job| {
+                process::descendants(&self.all_processes, job.pid)
+                    .into_iter()
+                    .map(|p| p.pid)
+                    .chain([job.pid])
+                    .collect()
+            });
+        let in_preset = |p: &Process| {
+            preset.is_none_or(|preset| preset.matches(&p.name, &p.cmdline))
+                && job_tree.as_ref().is_none_or(|tree| tree.contains(&p.pid))
+        };
+        if let Some(query) = self.name_filter.as_ref().filter(|_| self.fuzzy_filter) {
+            let mut ranked: Vec<(i64, &Process)> = self
+                .all_processes
+                .iter()
+                .filter(|p| filter.matches(p) && in_preset(p))
+                .filter_map(|p| Some((fuzzy_score(query, p)?, p)))
+                .collect();
+            // Stable, so equally good matches keep the sort key's order
+            ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            self.processes = ranked.into_iter().map(|(_, p)| p.clone()).collect();
+            self.selected_process = self
+                .selected_process
+                .min(self.processes.len().saturating_sub(1));
+            return;
+        }
+        let text = self.name_filter.as_ref().map(|text| text.to_lowercase());
+        let regex = self.regex_filter.as_ref();
+        self.processes = self
+            .all_processes
+            .iter()
+            .filter(|p| filter.matches(p) && in_preset(p))
+            .filter(|p| match (regex, &text) {
+                (Some(regex), _) => {
+                    regex.is_match(&p.name)
+                        || regex.is_match(&p.cmdline)
+                        || regex.is_match(&p.pid.to_string())
+                }
+                (None, Some(text)) => {
+                    p.name.to_lowercase().contains(text) || p.cmdline.to_lowercase().contains(text)
+                }
+                (None, None) => true,
+            })
+            .cloned()
+            .collect();
+        self.selected_process = self
+            .selected_process
+            .min(self.processes.len().saturating_sub(1));
+    }
 
-    let cpu_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)])
-        .split(area);
+    // == Samplers, called from the background thread or async tasks ==
 
-    render_cpu_cores_list(frame, cpu_info, cpu_layout[0]);
-    render_cpu_graphs(frame, cpu_info, cpu_layout[1]);
+    /// Run a sampler and account its cost, it's slowed down when it's too expensive
+    pub fn run_collector(&mut self, name: &'static str, base: Duration, sample: fn(&mut AppState)) {
+        let started = Instant::now();
+        sample(self);
+        self.freshness.record(name, Instant::now());
+        if let Some(message) = self.collector_budget.record(name, started.elapsed(), base) {
+            tracing::warn!(collector = name, "{}", message);
+            self.status_message = Some(message);
+        }
+    }
 
-    frame.render_widget(cpu_block, area);
-}
+    /// The sampler's interval after any slowdown from the overhead budget
+    pub fn collector_interval(&self, name: &str, base: Duration) -> Duration {
+        self.collector_budget.interval(name, base)
+    }
 
-fn render_cpu_cores_list(frame: &mut Frame, cpu_info: &CpuInfo, area: Rect) {
-    let cores_list: Vec<Line> = cpu_info
-        .cores
-        .iter()
-        .enumerate()
-        .map(|(i, core)| {
-            let color = CORE_COLORS[i % CORE_COLORS.len()];
-            Line::from(vec![
-                Span::styled(
-                    format!("{:>6}: ", core.name),
-                    Style::default().fg(color).add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(format!("{:>5.1}%", core.usage), Style::default().fg(color)),
-            ])
-        })
-        .collect();
+    pub fn sample_processes(&mut self) {
+        self.memory_info.update();
+        self.update_processes();
+        if self.show_overhead {
+            self.self_monitor.update();
+        }
+        self.check_alerts();
+        crash::record(|| self.snapshot());
+        self.dirty = true;
+    }
 
-    // Vertical centering
-    let vertical_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Min(1),
-            Constraint::Length(cores_list.len() as u16),
-            Constraint::Min(1),
-        ])
-        .split(area);
+    /// Everything sampled so far, stamped with the tick of the latest samples
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::capture_at(
+            self.sampled_at,
+            &self.cpu_info,
+            &self.memory_info,
+            &self.all_processes,
+        )
+        .with_network(self.network.snapshot())
+    }
 
-    // Horizontal centering
-    let horizontal_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Min(1),
-            Constraint::Length(20),
-            Constraint::Min(1),
-        ])
-        .split(vertical_layout[1]);
+    /// Mirror mode: show data another instance collected
+    pub fn apply_snapshot(&mut self, snapshot: &Snapshot) {
+        self.cpu_info.apply_snapshot(&snapshot.cpu);
+        self.memory_info.apply_snapshot(&snapshot.mem);
+        self.set_processes(snapshot.procs.iter().map(Process::from).collect());
+        // The data is as old as the snapshot, not the moment it arrived here
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let taken = Instant::now()
+            .checked_sub(Duration::from_millis(
+                now_ms.saturating_sub(snapshot.taken_at),
+            ))
+            .unwrap_or_else(Instant::now);
+        self.freshness.record("processes", taken);
+        self.freshness.record("cpu", taken);
+        self.check_alerts();
+        self.dirty = true;
+    }
 
-    let list_widget = Paragraph::new(cores_list)
-        .block(Block::default())
-        .alignment(Alignment::Left);
+    pub fn cpu_interval(&self) -> Duration {
+        self.cpu_info.chart_config.sample_interval
+    }
 
-    frame.render_widget(list_widget, horizontal_layout[1]);
-}
+    pub fn sample_cpu(&mut self) {
+        self.cpu_info.update();
+        self.check_alerts();
+        self.dirty = true;
+    }
 
-fn render_cpu_graphs(frame: &mut Frame, cpu_info: &CpuInfo, area: Rect) {
-    let graph_data: Vec<(String, Vec<(f64, f64)>, Color)> = cpu_info
-        .cores
-        .iter()
-        .enumerate()
-        .map(|(i, core)| {
-            let data = core
-                .history
-                .iter()
-                .enumerate()
-                .map(|(x, &y)| (x as f64, y as f64))
-                .collect();
-            (core.name.clone(), data, CORE_COLORS[i % CORE_COLORS.len()])
-        })
-        .collect();
+    fn check_alerts(&mut self) {
+        let fired = self.alerts.check(Readings {
+            cpu: self.cpu_info.global_usage,
+            memory: self.memory_info.current_memory_percent(),
+            swap: self.memory_info.current_swap_percent(),
+        });
+        for message in &fired {
+            tracing::warn!("{}", message);
+        }
+        let Some(message) = fired.into_iter().next() else {
+            return;
+        };
+        self.status_message = Some(message);
+        self.ring_bell |= self.bell;
+        if self.flash {
+            self.flash_until = Some(Instant::now() + ALERT_FLASH_DURATION);
+        }
+    }
 
-    // Chart widget
-    let chart = {
-        let y_min = 0.0;
-        let y_max = 50.0;
-        let datasets = graph_data
-            .iter()
-            .map(|(name, data, color)| {
-                Dataset::default()
-                    .name(name.as_str())
-                    .data(data)
-                    .graph_type(GraphType::Line)
-                    .style(Style::default().fg(*color))
-                    .marker(Marker::Braille)
-            })
-            .collect();
+    pub fn sample_disks(&mut self) {
+        self.disk_info.update();
+        self.dirty = true;
+    }
 
-        Chart::new(datasets)
-            .block(Block::default().title("CPU Usage History (0-50%)"))
-            .x_axis(
-                Axis::default()
-                    .bounds([0.0, 59.0])
-                    .labels::<Vec<Span>>(vec![Span::raw("0"), Span::raw("30"), Span::raw("60")]),
-            )
-            .y_axis(
-                Axis::default()
-                    .bounds([y_min, y_max])
-                    .labels::<Vec<Span>>(vec![
-                        Span::raw("0"),
-                        Span::raw(format!("{:.0}", y_max / 2.0)),
-                        Span::raw(format!("{:.0}", y_max)),
-                    ]),
-            )
-    };
+    pub fn sample_network(&mut self) {
+        self.network.update();
+        self.dirty = true;
+    }
 
-    // Center the chart vertically
-    let vertical_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Min(1),     // Top padding
-            Constraint::Length(12), // Increased chart height from 10 to 12
-            Constraint::Min(1),     // Bottom padding
-        ])
-        .split(area);
+    pub fn sample_kernel(&mut self) {
+        self.kernel.update();
+        self.dirty = true;
+    }
 
-    // Center the chart horizontally
-    let horizontal_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Min(1),         // Left padding
-            Constraint::Percentage(90), // Chart width
-            Constraint::Min(1),         // Right padding
-        ])
-        .split(vertical_layout[1]);
+    pub fn sample_battery(&mut self) {
+        self.battery.update();
+        self.dirty = true;
+    }
 
-    frame.render_widget(chart, horizontal_layout[1]);
-}
+    /// Only runs while a wait trace is open in the detail popup
+    pub fn sample_wait_trace(&mut self) {
+        if let Some(trace) = &mut self.wait_trace {
+            trace.sample();
+            self.dirty = true;
+        }
+    }
 
-fn render_process_section(
-    frame: &mut Frame,
-    processes: &[Process],
-    selected_process: usize,
-    scroll_offset: usize,
-    area: Rect,
-) {
-    let block = Block::default()
-        .title(" Process Information ")
-        .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::LightMagenta));
+    pub fn sample_sessions(&mut self) {
+        self.session_info.update();
+        self.dirty = true;
+    }
 
-    let inner_area = block.inner(area);
-    let max_items = inner_area.height as usize - 2; // Account for header and border
-    let scroll_offset = scroll_offset.min(processes.len().saturating_sub(max_items));
-    let mut adjusted_scroll = scroll_offset;
+    /// Spawning systemctl isn't free, only do it while the Services tab is open
+    pub fn sample_services(&mut self) {
+        if self.active_tab != Tab::Services {
+            return;
+        }
+        self.service_info.update();
+        let len = self.service_info.services.len();
+        self.selected_service = self.selected_service.min(len.saturating_sub(1));
+        self.dirty = true;
+    }
 
-    if selected_process < adjusted_scroll {
-        adjusted_scroll = selected_process;
-    } else if selected_process >= adjusted_scroll + max_items {
-        adjusted_scroll = selected_process - max_items + 1;
+    /// Records a row when `--history-db` is set
+    pub fn sample_history_db(&mut self) {
+        let Some(db) = &self.history_db else {
+            return;
+        };
+        // The usage is 0 until the CPU sampler ran once
+        if self.cpu_info.history.is_empty() {
+            return;
+        }
+        if let Err(e) = db.record(&self.cpu_info.snapshot(), &self.memory_info.snapshot()) {
+            self.history_error = Some(format!("Failed to record: {}", e));
+        }
+        if self.active_tab == Tab::History {
+            self.refresh_history_buckets();
+        }
+        self.dirty = true;
     }
 
-    // Define column constraints
-    let widths = [
-        Constraint::Length(6),  // PID
-        Constraint::Length(15), // Name
-        Constraint::Length(6),  // CPU%
-        Constraint::Length(8),  // Memory
-        Constraint::Length(8),  // Status
-        Constraint::Length(6),  // Parent
-    ];
+    /// Hands the current totals to the `--influx` pusher
+    pub fn sample_influx(&mut self) {
+        if self.influx.is_none() || self.cpu_info.history.is_empty() {
+            return;
+        }
+        let snapshot = self.snapshot();
+        if let Some(exporter) = &mut self.influx {
+            exporter.send(&snapshot);
+        }
+    }
 
-    // Create header row
-    let header = Row::new(vec![
-        Cell::from(Span::styled(
-            "PID",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "NAME",
-            Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "CPU%",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "MEMORY",
+    fn refresh_history_buckets(&mut self) {
+        let Some(db) = &self.history_db else {
+            return;
+        };
+        match db.summarize(self.history_range) {
+            Ok(buckets) => {
+                self.history_buckets = buckets;
+                self.history_error = None;
+            }
+            Err(e) => self.history_error = Some(format!("Failed to read: {}", e)),
+        }
+    }
+
+    #[cfg(feature = "smart")]
+    pub fn sample_smart(&mut self) {
+        self.smart = smart::read_all(self.disk_info.disks.iter().map(|d| d.device.as_str()));
+        self.dirty = true;
+    }
+
+    pub fn sample_neighbors(&mut self) {
+        if self.active_tab != Tab::Network {
+            return;
+        }
+        self.neighbors = network::read_neighbors();
+        self.dirty = true;
+    }
+
+    pub fn sample_probes(&mut self) {
+        if self.probes.collect() {
+            self.dirty = true;
+        }
+    }
+
+    /// Node totals feed the overview panels, but reading numa_maps of the heavy
+    /// processes isn't free, so that only happens with the NUMA tab open
+    pub fn sample_numa(&mut self) {
+        if self.active_tab == Tab::Numa {
+            self.update_numa();
+        } else {
+            let core_usage: Vec<f32> = self.cpu_info.cores.iter().map(|core| core.usage).collect();
+            self.numa_info.update_nodes(&core_usage);
+        }
+        self.dirty = true;
+    }
+
+    pub fn sample_memory_details(&mut self) {
+        if self.memory_details.is_some() {
+            self.memory_details = Some(MemoryDetails::read());
+            self.dirty = true;
+        }
+    }
+
+    fn update_numa(&mut self) {
+        // all_processes is kept sorted by the active sort key, NUMA cares about memory
+        let mut processes = self.all_processes.clone();
+        process::sort_by_memory(&mut processes);
+        let core_usage: Vec<f32> = self.cpu_info.cores.iter().map(|core| core.usage).collect();
+        self.numa_info.update(&core_usage, &processes);
+    }
+
+    pub fn selected(&self) -> Option<&Process> {
+        self.processes.get(self.selected_process)
+    }
+
+    pub fn request_service_action(&mut self, action: ServiceAction) {
+        if let Some(service) = self.service_info.services.get(self.selected_service) {
+            self.pending_action = Some(ConfirmAction::Service {
+                unit: service.unit.clone(),
+                action,
+            });
+        }
+    }
+
+    /// Ask for confirmation before terminating the selected process' group
+    pub fn request_kill_group(&mut self) {
+        let Some(pgid) = self.selected().and_then(|p| p.pgid) else {
+            self.status_message = Some("No process group for the selected process".to_string());
+            return;
+        };
+        let members = process::group_members(&self.all_processes, pgid)
+            .into_iter()
+            .map(|p| (p.pid, p.name.clone()))
+            .collect();
+        self.request_kill(ConfirmAction::KillGroup { pgid, members });
+    }
+
+    /// Ask for confirmation, guarded by typing the name when a target is critical
+    fn request_kill(&mut self, action: ConfirmAction) {
+        let members = match &action {
+            ConfirmAction::KillGroup { members, .. }
+            | ConfirmAction::Kill { members }
+            | ConfirmAction::KillTree { members, .. } => members.as_slice(),
+            ConfirmAction::Service { .. } => &[],
+        };
+        self.name_guard = members.iter().find_map(|(pid, _)| {
+            let process = self.all_processes.iter().find(|p| p.pid == *pid)?;
+            Some(NameGuard {
+                pid: *pid,
+                name: process.name.clone(),
+                reason: process::critical_process(process)?,
+                typed: String::new(),
+            })
+        });
+        self.pending_action = Some(action);
+    }
+
+    fn cancel_action(&mut self) {
+        self.pending_action = None;
+        self.name_guard = None;
+    }
+
+    /// `J`: only the job started with `møas run` and its children, or everything
+    pub fn toggle_job_only(&mut self) {
+        if self.job.is_none() {
+            self.status_message = Some("No job, start one with møas run -- COMMAND".to_string());
+            return;
+        }
+        self.job_only = !self.job_only;
+        self.apply_filter();
+    }
+
+    /// Tag or untag the selected process and move on to the next one, like htop
+    pub fn toggle_tag(&mut self) {
+        let Some(pid) = self.selected().map(|p| p.pid) else {
+            return;
+        };
+        if !self.tagged.remove(&pid) {
+            self.tagged.insert(pid);
+        }
+        self.touch_navigation();
+        self.selected_process =
+            (self.selected_process + 1).min(self.processes.len().saturating_sub(1));
+    }
+
+    /// Tagged processes, or the selected one when nothing is tagged
+    fn action_targets(&self) -> Vec<(Pid, String)> {
+        if self.tagged.is_empty() {
+            return self
+                .selected()
+                .map(|p| vec![(p.pid, p.name.clone())])
+                .unwrap_or_default();
+        }
+        self.all_processes
+            .iter()
+            .filter(|p| self.tagged.contains(&p.pid))
+            .map(|p| (p.pid, p.name.clone()))
+            .collect()
+    }
+
+    pub fn request_kill_tagged(&mut self) {
+        let members = self.action_targets();
+        if !members.is_empty() {
+            self.request_kill(ConfirmAction::Kill { members });
+        }
+    }
+
+    /// Change the nice value of every tagged process by `delta`
+    pub fn renice_tagged(&mut self, delta: i32) {
+        if self.read_only {
+            self.status_message = Some("Read-only mode, action disabled".to_string());
+            return;
+        }
+        let targets = self.action_targets();
+        let mut failed = Vec::new();
+        for (pid, _) in &targets {
+            let result = process::get_nice(*pid)
+                .ok_or_else(std::io::Error::last_os_error)
+                .and_then(|nice| process::set_nice(*pid, (nice + delta).clamp(-20, 19)));
+            if let Err(e) = result {
+                failed.push((*pid, e));
+            }
+        }
+        self.status_message = Some(match failed.first() {
+            None => format!(
+                "Changed nice by {:+} for {} processes",
+                delta,
+                targets.len()
+            ),
+            Some((pid, e)) => failure(format!(
+                "Failed to renice {} of {} processes (pid {}: {})",
+                failed.len(),
+                targets.len(),
+                pid,
+                capabilities::explain(e, Capability::Renice)
+            )),
+        });
+    }
+
+    /// Ask for confirmation before terminating the selected process and its descendants
+    pub fn request_kill_tree(&mut self) {
+        let Some(root) = self.selected().map(|p| p.pid) else {
+            return;
+        };
+        // Selecting our own shell must not take the monitor down halfway through
+        let own_pid = Pid::from_u32(std::process::id());
+        let members = process::kill_order(&self.all_processes, root)
+            .into_iter()
+            .filter(|p| p.pid != own_pid)
+            .map(|p| (p.pid, p.name.clone()))
+            .collect();
+        self.request_kill(ConfirmAction::KillTree { root, members });
+    }
+
+    fn confirm_action(&mut self) {
+        if self.read_only {
+            self.cancel_action();
+            return;
+        }
+        self.name_guard = None;
+        match self.pending_action.take() {
+            Some(ConfirmAction::KillGroup { pgid, members }) => {
+                self.status_message = Some(match process::kill_process_group(pgid) {
+                    Ok(()) => format!(
+                        "Sent SIGTERM to process group {} ({} processes)",
+                        pgid,
+                        members.len()
+                    ),
+                    Err(e) => failure(format!(
+                        "Failed to kill process group {}: {}",
+                        pgid,
+                        capabilities::explain(&e, Capability::SignalOthers)
+                    )),
+                });
+            }
+            Some(ConfirmAction::Kill { members }) => {
+                let pids: Vec<Pid> = members.iter().map(|(pid, _)| *pid).collect();
+                let failed = process::kill_processes(&pids);
+                self.status_message = Some(match failed.first() {
+                    None => format!("Sent SIGTERM to {} processes", members.len()),
+                    Some((pid, e)) => failure(format!(
+                        "Failed to kill {} of {} processes (pid {}: {})",
+                        failed.len(),
+                        members.len(),
+                        pid,
+                        capabilities::explain(e, Capability::SignalOthers)
+                    )),
+                });
+                self.tagged.clear();
+            }
+            Some(ConfirmAction::KillTree { root, members }) => {
+                let pids: Vec<Pid> = members.iter().map(|(pid, _)| *pid).collect();
+                let failed = process::kill_processes(&pids);
+                self.status_message = Some(match failed.first() {
+                    None => format!(
+                        "Sent SIGTERM to the tree of {} ({} processes)",
+                        root,
+                        members.len()
+                    ),
+                    Some((pid, e)) => failure(format!(
+                        "Failed to kill {} of {} processes in the tree of {} (pid {}: {})",
+                        failed.len(),
+                        members.len(),
+                        root,
+                        pid,
+                        capabilities::explain(e, Capability::SignalOthers)
+                    )),
+                });
+            }
+            Some(ConfirmAction::Service { unit, action }) => {
+                self.status_message = Some(match services::run_action(&unit, action) {
+                    Ok(()) => format!("Ran {} on {}", action, unit),
+                    Err(e) => failure(format!("Failed to {} {}: {}", action, unit, e)),
+                });
+            }
+            None => {}
+        }
+    }
+
+    pub fn open_governor_menu(&mut self) {
+        let policies = &self.cpu_info.policies;
+        let governors = cpufreq::available_governors(policies);
+        if governors.is_empty() {
+            self.status_message = Some("CPU frequency scaling is not available".to_string());
+            return;
+        }
+        let selected = cpufreq::common_governor(policies)
+            .and_then(|current| governors.iter().position(|g| g == current))
+            .unwrap_or(0);
+        self.governor_menu = Some(GovernorMenu {
+            governors,
+            selected,
+            policy: None,
+            can_switch: cpufreq::can_switch(policies),
+        });
+    }
+
+    pub fn open_detail(&mut self) {
+        self.detail_pid = self.selected().map(|p| p.pid);
+        self.followed_tree = self
+            .detail_pid
+            .map(|pid| FollowedTree::new(&self.all_processes, pid));
+        self.affinity_input = None;
+        self.io_priority_input = None;
+        self.oom_adj_input = None;
+        self.wait_trace = None;
+    }
+
+    fn apply_affinity(&mut self) {
+        let (Some(pid), Some(input)) = (self.detail_pid, self.affinity_input.take()) else {
+            return;
+        };
+        if self.read_only {
+            return;
+        }
+        let cpus = numa::parse_cpu_list(input.trim());
+        self.status_message = Some(match process::set_affinity(pid, &cpus) {
+            Ok(()) => format!(
+                "PID {} now runs on CPUs {}",
+                pid,
+                numa::format_cpu_list(&cpus)
+            ),
+            Err(e) => failure(format!("Failed to set affinity of PID {}: {}", pid, e)),
+        });
+    }
+
+    fn apply_oom_adj(&mut self) {
+        let (Some(pid), Some(input)) = (self.detail_pid, self.oom_adj_input.take()) else {
+            return;
+        };
+        if self.read_only {
+            return;
+        }
+        let result = input
+            .trim()
+            .parse::<i32>()
+            .ok()
+            .filter(|adj| process::OOM_SCORE_ADJ_RANGE.contains(adj))
+            .ok_or_else(|| format!("OOM adjustment must be -1000 to 1000, got '{}'", input))
+            .and_then(|adj| {
+                process::set_oom_score_adj(pid, adj)
+                    .map(|()| adj)
+                    .map_err(|e| capabilities::explain(&e, Capability::OomProtect))
+            });
+        self.status_message = Some(match result {
+            Ok(adj) => format!("PID {} now has OOM adjustment {}", pid, adj),
+            Err(e) => failure(format!(
+                "Failed to set OOM adjustment of PID {}: {}",
+                pid, e
+            )),
+        });
+    }
+
+    fn apply_io_priority(&mut self) {
+        let (Some(pid), Some(input)) = (self.detail_pid, self.io_priority_input.take()) else {
+            return;
+        };
+        if self.read_only {
+            return;
+        }
+        let result = IoPriority::parse(&input).and_then(|priority| {
+            process::set_io_priority(pid, priority)
+                .map(|()| priority)
+                .map_err(|e| capabilities::explain(&e, Capability::Renice))
+        });
+        self.status_message = Some(match result {
+            Ok(priority) => format!("PID {} now has I/O priority {}", pid, priority),
+            Err(e) => failure(format!("Failed to set I/O priority of PID {}: {}", pid, e)),
+        });
+    }
+
+    pub fn mark_baseline(&mut self) {
+        self.baseline = Some(Baseline::mark(&self.all_processes));
+        self.selected_delta = 0;
+        self.status_message = Some(format!(
+            "Baseline marked with {} processes",
+            self.all_processes.len()
+        ));
+    }
+
+    /// The view settings worth keeping for the next session
+    pub fn ui_state(&self) -> UiState {
+        UiState {
+            tab: self.active_tab,
+            sort_key: self.sort_key,
+            then_sort_key: self.then_sort_key,
+            status_filter: self.status_filter,
+            name_filter: self.name_filter.clone(),
+            fuzzy_filter: self.fuzzy_filter,
+            regex_filter: self.regex_filter.is_some(),
+            filter_preset: self.filter_preset_name().map(str::to_string),
+            group_columns: self.show_group_columns,
+            fd_column: self.show_fd_column,
+            event_columns: self.show_event_columns,
+            pod_column: self.show_pod_column,
+            path_columns: self.show_path_columns,
+            delta_columns: self.show_delta_columns,
+            cpu_heatmap: self.cpu_heatmap,
+            core_colors: self.core_colors,
+            smooth_charts: self.smooth_charts,
+            all_mounts: self.disk_info.filter.show_all,
+            theme: self.theme,
+        }
+    }
+
+    /// Everything but the theme, which lives in `ACCESSIBLE`, `LIGHT` and `MONOCHROME`
+    pub fn restore_ui_state(&mut self, saved: &UiState) {
+        self.active_tab = saved.tab;
+        self.sort_key = saved.sort_key;
+        self.then_sort_key = saved.then_sort_key;
+        self.status_filter = saved.status_filter;
+        self.name_filter = saved.name_filter.clone();
+        self.fuzzy_filter = saved.fuzzy_filter;
+        self.regex_filter = saved
+            .name_filter
+            .as_deref()
+            .filter(|_| saved.regex_filter)
+            .and_then(|pattern| commands::compile_filter(pattern).ok());
+        if saved.regex_filter && self.regex_filter.is_none() {
+            self.name_filter = None;
+        }
+        // By name, the config may have changed since
+        self.filter_preset = saved
+            .filter_preset
+            .as_ref()
+            .and_then(|name| self.filter_presets.iter().position(|p| &p.name == name));
+        self.show_group_columns = saved.group_columns;
+        self.show_fd_column = saved.fd_column;
+        self.show_event_columns = saved.event_columns;
+        self.show_pod_column = saved.pod_column;
+        self.show_path_columns = saved.path_columns;
+        self.show_delta_columns = saved.delta_columns;
+        self.cpu_heatmap = saved.cpu_heatmap;
+        self.core_colors = saved.core_colors;
+        self.smooth_charts = saved.smooth_charts;
+        // --all-mounts wins over a saved filtered view
+        self.disk_info.filter.show_all |= saved.all_mounts;
+        self.sort_and_filter();
+    }
+
+    pub fn process_interval(&self) -> Duration {
+        self.process_interval
+    }
+
+    /// Run a command palette line, returns true when it asks to quit
+    fn run_command(&mut self, line: &str) -> bool {
+        let command = match commands::parse(line) {
+            Ok(command) => command,
+            Err(e) => {
+                self.status_message = Some(e);
+                return false;
+            }
+        };
+        match command {
+            Command::Kill(_) if self.read_only => {
+                self.status_message = Some("Read-only mode, action disabled".to_string());
+            }
+            Command::Kill(pid) => {
+                // Critical processes get the same typed confirmation as in the table
+                if let Some(process) = self.all_processes.iter().find(|p| p.pid == pid) {
+                    if process::critical_process(process).is_some() {
+                        let members = vec![(pid, process.name.clone())];
+                        self.request_kill(ConfirmAction::Kill { members });
+                        return false;
+                    }
+                }
+                self.status_message = Some(match process::kill_processes(&[pid]).first() {
+                    None => format!("Sent SIGTERM to {}", pid),
+                    Some((_, e)) => failure(format!(
+                        "Failed to kill {}: {}",
+                        pid,
+                        capabilities::explain(e, Capability::SignalOthers)
+                    )),
+                });
+            }
+            Command::Sort(key, then) => {
+                self.sort_key = key;
+                self.then_sort_key = then;
+                self.sort_and_filter();
+            }
+            Command::Filter(text) => self.set_name_filter(text, false, None),
+            Command::Fuzzy(text) => self.set_name_filter(text, true, None),
+            Command::Regex(pattern) => {
+                // Already validated by the parser
+                let regex = commands::compile_filter(&pattern).ok();
+                self.set_name_filter(Some(pattern), false, regex);
+            }
+            Command::Preset(None) => self.set_filter_preset(None),
+            Command::Preset(Some(name)) => {
+                match self.filter_presets.iter().position(|p| p.name == name) {
+                    Some(index) => self.set_filter_preset(Some(index)),
+                    None if self.filter_presets.is_empty() => {
+                        self.status_message = Some(format!(
+                            "No filter presets, add a [filters] table to {}",
+                            config_path_label()
+                        ));
+                    }
+                    None => {
+                        let names: Vec<&str> = self
+                            .filter_presets
+                            .iter()
+                            .map(|p| p.name.as_str())
+                            .collect();
+                        self.status_message = Some(format!(
+                            "unknown preset '{}' (known: {})",
+                            name,
+                            names.join(", ")
+                        ));
+                    }
+                }
+            }
+            Command::Theme(theme) => {
+                ACCESSIBLE.store(theme == Theme::Contrast, Ordering::Relaxed);
+                MONOCHROME.store(theme == Theme::Mono, Ordering::Relaxed);
+                LIGHT.store(theme::is_light(theme), Ordering::Relaxed);
+                self.theme = theme;
+            }
+            Command::Interval(interval) => {
+                self.process_interval = interval;
+                self.status_message = Some(format!(
+                    "Refreshing processes every {}",
+                    history::format_interval(interval)
+                ));
+            }
+            Command::WhoOwns(target) => self.select_owner(target),
+            Command::Quit => return true,
+        }
+        false
+    }
+
+    /// `:who-owns`: select the process that has a port bound or a file open
+    fn select_owner(&mut self, target: Owned) {
+        if self.remote {
+            self.status_message = Some("Not available for a remote host's processes".to_string());
+            return;
+        }
+        let target = match target {
+            // /proc has the links resolved, relative to where m-as was started
+            Owned::Path(path) => Owned::Path(std::fs::canonicalize(&path).unwrap_or(path)),
+            port => port,
+        };
+        let (owners, unreadable) = match process::who_owns(&self.all_processes, &target) {
+            Ok(found) => found,
+            Err(e) => {
+                self.status_message = Some(failure(format!("Can't look up {}: {}", target, e)));
+                return;
+            }
+        };
+        let Some(first) = owners.first() else {
+            self.status_message = Some(if unreadable > 0 {
+                format!(
+                    "No readable process has {} open, {} others not checked: {}",
+                    target,
+                    unreadable,
+                    Capability::OpenFiles.hint()
+                )
+            } else {
+                format!("No process has {} open", target)
+            });
+            return;
+        };
+
+        let mut listed: Vec<String> = owners
+            .iter()
+            .take(3)
+            .map(|owner| format!("{} ({}, {})", owner.name, owner.pid, owner.via))
+            .collect();
+        if owners.len() > 3 {
+            listed.push(format!("{} more", owners.len() - 3));
+        }
+        let hidden = match self.processes.iter().position(|p| p.pid == first.pid) {
+            Some(index) => {
+                self.selected_process = index;
+                // Keep the row where it is for a moment, it just got selected
+                self.touch_navigation();
+                ""
+            }
+            None => " (hidden by the filters)",
+        };
+        self.status_message = Some(format!("{}: {}{}", target, listed.join(", "), hidden));
+    }
+
+    fn set_name_filter(&mut self, text: Option<String>, fuzzy: bool, regex: Option<Regex>) {
+        self.name_filter = text;
+        self.fuzzy_filter = fuzzy;
+        self.regex_filter = regex;
+        self.selected_process = 0;
+        self.scroll_offset = 0;
+        self.apply_filter();
+    }
+
+    /// `"text"`, `~"text"` when fuzzy or `/pattern/` for a regex
+    pub fn name_filter_label(&self) -> Option<String> {
+        let text = self.name_filter.as_ref()?;
+        Some(if self.regex_filter.is_some() {
+            format!("/{}/", text)
+        } else if self.fuzzy_filter {
+            format!("~\"{}\"", text)
+        } else {
+            format!("\"{}\"", text)
+        })
+    }
+
+    pub fn filter_preset_name(&self) -> Option<&str> {
+        self.filter_preset
+            .map(|index| self.filter_presets[index].name.as_str())
+    }
+
+    fn set_filter_preset(&mut self, preset: Option<usize>) {
+        self.filter_preset = preset;
+        self.selected_process = 0;
+        self.scroll_offset = 0;
+        self.apply_filter();
+    }
+
+    /// Next preset, after the last one back to no preset
+    pub fn cycle_filter_preset(&mut self) {
+        if self.filter_presets.is_empty() {
+            self.status_message = Some(format!(
+                "No filter presets, add a [filters] table to {}",
+                config_path_label()
+            ));
+            return;
+        }
+        let next = match self.filter_preset {
+            None => Some(0),
+            Some(index) if index + 1 < self.filter_presets.len() => Some(index + 1),
+            Some(_) => None,
+        };
+        self.set_filter_preset(next);
+    }
+
+    /// Pressing the key of the active filter again goes back to showing everything
+    pub fn toggle_status_filter(&mut self, filter: StatusFilter) {
+        self.status_filter = if self.status_filter == filter {
+            StatusFilter::All
+        } else {
+            filter
+        };
+        self.selected_process = 0;
+        self.scroll_offset = 0;
+        self.apply_filter();
+    }
+
+    /// Write the process table as shown plus a few system totals to the working directory
+    pub fn export_view(&mut self, format: export::Format) {
+        if self.read_only {
+            self.status_message = Some("Read-only mode, export disabled".to_string());
+            return;
+        }
+        let mut filters = vec![self.status_filter.label().to_string()];
+        filters.extend(self.name_filter_label());
+        if let Some(name) = self.filter_preset_name() {
+            filters.push(format!("preset {}", name));
+        }
+        let summary = [
+            ("sort", self.sort_label()),
+            ("filter", filters.join(", ")),
+            (
+                "processes",
+                format!(
+                    "{} shown of {}",
+                    self.processes.len(),
+                    self.all_processes.len()
+                ),
+            ),
+            ("cpu", format!("{:.1}%", self.cpu_info.global_usage)),
+            (
+                "memory",
+                format!(
+                    "{:.1}% ({} of {})",
+                    self.memory_info.current_memory_percent(),
+                    units::format_size(self.memory_info.used_memory),
+                    units::format_size(self.memory_info.total_memory)
+                ),
+            ),
+            (
+                "swap",
+                format!(
+                    "{} of {}",
+                    units::format_size(self.memory_info.used_swap),
+                    units::format_size(self.memory_info.total_swap)
+                ),
+            ),
+        ];
+        let columns = visible_columns(self);
+        self.status_message = Some(
+            match export::write(format, &summary, &columns, &self.processes) {
+                Ok(path) => format!("Exported the process table to {}", path.display()),
+                Err(e) => failure(format!("Failed to export the process table: {}", e)),
+            },
+        );
+    }
+
+    /// Keep selection and scroll inside the list after the viewport changed
+    pub fn clamp_selection(&mut self, visible_height: usize) {
+        self.selected_process = self
+            .selected_process
+            .min(self.processes.len().saturating_sub(1));
+        self.scroll_offset = self
+            .scroll_offset
+            .min(self.processes.len().saturating_sub(visible_height));
+        if self.selected_process < self.scroll_offset {
+            self.scroll_offset = self.selected_process;
+        } else if visible_height > 0 && self.selected_process >= self.scroll_offset + visible_height
+        {
+            self.scroll_offset = self.selected_process + 1 - visible_height;
+        }
+    }
+}
+
+pub fn main(args: Args, job: Option<Job>) -> Result<()> {
+    // An error rather than ratatui's panic, so `møas run` can stop its command
+    let terminal = ratatui::try_init()
+        .inspect_err(|_| ratatui::restore())
+        .wrap_err("failed to initialize terminal")?;
+    crash::install_panic_hook();
+    let result = run(terminal, args, job);
+    ratatui::restore();
+    if let Err(e) = &result {
+        crash::report_error(e);
+    }
+    result
+}
+
+pub fn run(mut terminal: DefaultTerminal, args: Args, mut job: Option<Job>) -> Result<()> {
+    // Before anything else, so startup problems end up in the Log tab too
+    let log = logging::init();
+    if let Some(job) = &mut job {
+        job.forward_output();
+    }
+    // In raw mode and before the input loop, which would swallow the answer
+    theme::detect();
+    // Shared state between threads
+    let state = Arc::new(Mutex::new(AppState::new(&args)));
+    state.lock().unwrap().log = log;
+    state.lock().unwrap().job = job;
+    for capability in state.lock().unwrap().capabilities.denied() {
+        tracing::info!("no access to {}", capability);
+    }
+    let state_thread = Arc::clone(&state);
+
+    let socket_path = args
+        .socket
+        .clone()
+        .unwrap_or_else(mirror::default_socket_path);
+    if let Some(path) = &args.process_log {
+        state
+            .lock()
+            .unwrap()
+            .lifecycle
+            .set_log_file(path)
+            .wrap_err_with(|| format!("failed to open process log {}", path.display()))?;
+    }
+
+    if let Some(endpoint) = &args.influx {
+        state.lock().unwrap().influx =
+            Some(Exporter::start(endpoint.clone(), args.influx_interval));
+    }
+    if let Some(path) = &args.history_db {
+        let db = HistoryDb::open(path)
+            .wrap_err_with(|| format!("failed to open history database {}", path.display()))?;
+        state.lock().unwrap().history_db = Some(db);
+    }
+
+    if let Some(path) = config::default_path() {
+        let (presets, intervals) = config::load(&path)
+            .and_then(|config| Ok((config.filter_presets()?, config.sampler_intervals()?)))
+            .map_err(|e| eyre!("failed to read {}: {}", path.display(), e))?;
+        let mut state = state.lock().unwrap();
+        state.filter_presets = presets;
+        state.sampler_intervals = intervals.into_iter().collect();
+    }
+
+    let ui_state_path = ui_state::default_path();
+    let saved = ui_state_path
+        .as_deref()
+        .map(ui_state::load)
+        .unwrap_or_default();
+    state.lock().unwrap().restore_ui_state(&saved);
+
+    ACCESSIBLE.store(
+        args.accessible || saved.theme == Theme::Contrast,
+        Ordering::Relaxed,
+    );
+    // NO_COLOR is honored when set to anything but the empty string
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    MONOCHROME.store(
+        args.monochrome || no_color || saved.theme == Theme::Mono,
+        Ordering::Relaxed,
+    );
+    LIGHT.store(theme::is_light(saved.theme), Ordering::Relaxed);
+    state.lock().unwrap().theme = saved.theme;
+
+    // Paint before the samplers take the lock, panels show "collecting…" until
+    // their first reading
+    terminal.draw(|frame| render(frame, &state.lock().unwrap()))?;
+
+    let mut serving = false;
+    if args.mirror {
+        // Someone else collects, we only render
+        mirror::attach(state_thread, &socket_path)?;
+    } else if let Some(address) = &args.connect {
+        remote::connect(state_thread, address)?;
+    } else if let Some(destination) = &args.ssh {
+        remote::connect_ssh(state_thread, destination, &args.ssh_command, args.interval)?;
+    } else {
+        serving = mirror::serve(Arc::clone(&state_thread), &socket_path);
+        if !serving && cfg!(unix) {
+            tracing::info!(
+                "another instance owns {}, not serving mirrors",
+                socket_path.display()
+            );
+        }
+        state.lock().unwrap().probes.start();
+
+        // Spawn background samplers for data updates
+        #[cfg(feature = "tokio")]
+        crate::async_sampler::spawn(state_thread);
+        #[cfg(not(feature = "tokio"))]
+        spawn_sampler_thread(state_thread);
+    }
+    let result = run_ui(&mut terminal, &state);
+    if serving {
+        mirror::cleanup(&socket_path);
+    }
+    // A read-only session doesn't leave anything behind on the host
+    if let Some(path) = ui_state_path.as_ref().filter(|_| !args.read_only) {
+        // Not worth failing the exit over, the next launch just starts with defaults
+        if let Err(e) = ui_state::save(path, &state.lock().unwrap().ui_state()) {
+            tracing::warn!("failed to save UI state to {}: {}", path.display(), e);
+        }
+    }
+    result
+}
+
+fn run_ui(terminal: &mut DefaultTerminal, state: &Arc<Mutex<AppState>>) -> Result<()> {
+    // Main thread handles only UI and input
+    let mut visible_height = (terminal.size()?.height as usize).saturating_sub(4);
+    let mut needs_redraw = true;
+    let mut last_draw = Instant::now();
+    loop {
+        // Only redraw on input, resize or new data. While idle, block on input
+        // for a while instead of spinning so the monitor itself stays cheap.
+        let mut timeout = if needs_redraw {
+            Duration::ZERO
+        } else {
+            IDLE_POLL_INTERVAL
+        };
+        while event::poll(timeout)? {
+            timeout = Duration::ZERO;
+            needs_redraw = true;
+            let event = event::read()?;
+            let (
+                popup_open,
+                confirm_open,
+                detail_open,
+                governor_open,
+                command_open,
+                memory_open,
+                diagnostics_open,
+                hardware_open,
+            ) = {
+                let mut state = state.lock().unwrap();
+                if matches!(event, Event::Key(_)) {
+                    state.status_message = None;
+                }
+                (
+                    state.chart_options.is_some(),
+                    state.pending_action.is_some(),
+                    state.detail_pid.is_some(),
+                    state.governor_menu.is_some(),
+                    state.command_line.is_some(),
+                    state.memory_details.is_some(),
+                    state.diagnostics_open,
+                    state.hardware_open,
+                )
+            };
+            match event {
+                Event::Key(key) if confirm_open => {
+                    let mut state = state.lock().unwrap();
+                    if state.name_guard.is_some() {
+                        handle_name_guard_key(&mut state, key.code);
+                    } else {
+                        match key.code {
+                            KeyCode::Char('y') | KeyCode::Enter => state.confirm_action(),
+                            _ => state.cancel_action(),
+                        }
+                    }
+                }
+                Event::Key(key) if command_open => {
+                    let quit = handle_command_key(&mut state.lock().unwrap(), key.code);
+                    if quit {
+                        return Ok(());
+                    }
+                }
+                Event::Key(key) if popup_open => {
+                    handle_chart_options_key(&mut state.lock().unwrap(), key.code);
+                }
+                Event::Key(key) if governor_open => {
+                    handle_governor_key(&mut state.lock().unwrap(), key.code);
+                }
+                Event::Key(key) if detail_open => {
+                    handle_detail_key(&mut state.lock().unwrap(), key.code);
+                }
+                Event::Key(key) if memory_open => {
+                    if matches!(
+                        key.code,
+                        KeyCode::Esc | KeyCode::Char('M') | KeyCode::Char('q')
+                    ) {
+                        state.lock().unwrap().memory_details = None;
+                    }
+                }
+                Event::Key(key) if diagnostics_open => {
+                    if matches!(
+                        key.code,
+                        KeyCode::Esc | KeyCode::Char('P') | KeyCode::Char('q')
+                    ) {
+                        state.lock().unwrap().diagnostics_open = false;
+                    }
+                }
+                Event::Key(key) if hardware_open => {
+                    if matches!(
+                        key.code,
+                        KeyCode::Esc | KeyCode::Char('I') | KeyCode::Char('q')
+                    ) {
+                        state.lock().unwrap().hardware_open = false;
+                    }
+                }
+                Event::Key(key) if is_destructive_key(&state.lock().unwrap(), key.code) => {
+                    state.lock().unwrap().status_message =
+                        Some("Read-only mode, action disabled".to_string());
+                }
+                Event::Key(key) if handle_tab_key(&mut state.lock().unwrap(), key.code) => {}
+                Event::Key(key) if panels::handle_key(&mut state.lock().unwrap(), key.code) => {}
+                Event::Key(key) => match key.code {
+                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char(':') => state.lock().unwrap().command_line = Some(String::new()),
+                    KeyCode::Char('P') => state.lock().unwrap().diagnostics_open = true,
+                    KeyCode::Char('I') => state.lock().unwrap().hardware_open = true,
+                    KeyCode::Char('A') => {
+                        ACCESSIBLE.store(!accessible(), Ordering::Relaxed);
+                    }
+                    KeyCode::Char('O') => {
+                        let mut state = state.lock().unwrap();
+                        state.show_overhead = !state.show_overhead;
+                    }
+                    KeyCode::Char('S') => {
+                        let mut state = state.lock().unwrap();
+                        state.smooth_charts = !state.smooth_charts;
+                    }
+                    KeyCode::Char('w') => state.lock().unwrap().export_view(export::Format::Text),
+                    KeyCode::Char('W') => state.lock().unwrap().export_view(export::Format::Csv),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        let mut state = state.lock().unwrap();
+                        state.touch_navigation();
+                        if state.selected_process < state.processes.len().saturating_sub(1) {
+                            state.selected_process += 1;
+                        }
+                        if state.selected_process >= state.scroll_offset + visible_height {
+                            state.scroll_offset = state.selected_process - visible_height + 1;
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        let mut state = state.lock().unwrap();
+                        state.touch_navigation();
+                        if state.selected_process > 0 {
+                            state.selected_process -= 1;
+                        }
+                        if state.selected_process < state.scroll_offset {
+                            state.scroll_offset = state.selected_process;
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Resize(_, height) => {
+                    visible_height = (height as usize).saturating_sub(4);
+                    state.lock().unwrap().clamp_selection(visible_height);
+                    // Redraw right away instead of waiting for the next frame
+                    terminal.autoresize()?;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        {
+            // New data alone redraws at most every few seconds in accessible mode,
+            // the flag stays set so nothing is lost
+            let mut state = state.lock().unwrap();
+            if state.dirty
+                && (!accessible() || last_draw.elapsed() >= REDUCED_MOTION_FRAME_INTERVAL)
+            {
+                state.dirty = false;
+                needs_redraw = true;
+            }
+            let age_interval = if accessible() {
+                REDUCED_MOTION_FRAME_INTERVAL
+            } else {
+                DATA_AGE_REDRAW_INTERVAL
+            };
+            if last_draw.elapsed() >= age_interval {
+                needs_redraw = true;
+            }
+            if state
+                .flash_until
+                .is_some_and(|until| Instant::now() >= until)
+            {
+                state.flash_until = None;
+                needs_redraw = true;
+            }
+            if state.job.as_ref().is_some_and(Job::finished) {
+                return Ok(());
+            }
+            if std::mem::take(&mut state.ring_bell) {
+                let mut stdout = std::io::stdout();
+                stdout.write_all(b"\x07")?;
+                stdout.flush()?;
+            }
+        }
+        if !needs_redraw {
+            continue;
+        }
+
+        terminal.draw(|f| {
+            visible_height = (f.area().height as usize).saturating_sub(4);
+            let state = state.lock().unwrap();
+            render(f, &state);
+            if monochrome() {
+                theme::strip_colors(f.buffer_mut());
+            } else if LIGHT.load(Ordering::Relaxed) {
+                theme::adapt_to_light(f.buffer_mut());
+            }
+        })?;
+        needs_redraw = false;
+        last_draw = Instant::now();
+    }
+}
+
+/// Single OS thread driving every sampler from the shared tick scheduler
+#[cfg(not(feature = "tokio"))]
+fn spawn_sampler_thread(state: Arc<Mutex<AppState>>) {
+    use std::thread;
+
+    use crate::scheduler::Scheduler;
+
+    thread::spawn(move || {
+        let mut scheduler = Scheduler::new();
+        loop {
+            let due = {
+                let mut state = state.lock().unwrap();
+                let due = scheduler.due(&state);
+                if !due.is_empty() {
+                    state.sampled_at = scheduler.taken_at();
+                }
+                due
+            };
+            // The lock is let go between samplers so the UI can draw in between
+            for (name, base, sample) in due {
+                state.lock().unwrap().run_collector(name, base, sample);
+            }
+            thread::sleep(scheduler.advance());
+        }
+    });
+}
+
+/// Keys that change the system, none of them do anything in read-only mode
+fn is_destructive_key(state: &AppState, code: KeyCode) -> bool {
+    if !state.read_only {
+        return false;
+    }
+    // The process actions act on the selected process whatever tab is showing
+    let process_action = matches!(
+        code,
+        KeyCode::Char('G')
+            | KeyCode::Char('T')
+            | KeyCode::Char('K')
+            | KeyCode::Char('+')
+            | KeyCode::Char('-')
+    );
+    process_action
+        || match state.active_tab {
+            Tab::Services => matches!(
+                code,
+                KeyCode::Char('s') | KeyCode::Char('x') | KeyCode::Char('r')
+            ),
+            Tab::Overview
+            | Tab::Diff
+            | Tab::Numa
+            | Tab::Users
+            | Tab::Network
+            | Tab::Log
+            | Tab::Power
+            | Tab::History => false,
+        }
+}
+
+/// Tab switching and keys specific to the active tab, returns true when the key was used
+fn handle_tab_key(state: &mut AppState, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Char('1') => state.active_tab = Tab::Overview,
+        KeyCode::Char('2') => {
+            if state.active_tab != Tab::Services {
+                // Show something right away instead of waiting for the sampler tick
+                state.service_info.update();
+            }
+            state.active_tab = Tab::Services;
+        }
+        KeyCode::Char('3') => state.active_tab = Tab::Diff,
+        KeyCode::Char('4') => {
+            if state.active_tab != Tab::Numa {
+                state.update_numa();
+            }
+            state.active_tab = Tab::Numa;
+        }
+        KeyCode::Char('5') => state.active_tab = Tab::Users,
+        KeyCode::Char('6') => {
+            if state.active_tab != Tab::Network {
+                state.neighbors = network::read_neighbors();
+            }
+            state.active_tab = Tab::Network;
+        }
+        KeyCode::Char('7') => state.active_tab = Tab::Log,
+        KeyCode::Char('8') => state.active_tab = Tab::Power,
+        KeyCode::Char('9') => {
+            if state.active_tab != Tab::History {
+                state.refresh_history_buckets();
+            }
+            state.active_tab = Tab::History;
+        }
+        KeyCode::Char('b') => state.mark_baseline(),
+        KeyCode::Tab => state.cycle_focus(true),
+        KeyCode::BackTab => state.cycle_focus(false),
+        KeyCode::Down | KeyCode::Char('j') | KeyCode::Up | KeyCode::Char('k')
+            if state
+                .focused_panel()
+                .is_some_and(|panel| panel != Focus::Processes) =>
+        {
+            let rows = if matches!(code, KeyCode::Down | KeyCode::Char('j')) {
+                1
+            } else {
+                -1
+            };
+            state.scroll_focused(rows);
+        }
+        _ if state.active_tab == Tab::Services => match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = state.service_info.services.len().saturating_sub(1);
+                state.selected_service = (state.selected_service + 1).min(last);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected_service = state.selected_service.saturating_sub(1);
+            }
+            KeyCode::Char('s') => state.request_service_action(ServiceAction::Start),
+            KeyCode::Char('x') => state.request_service_action(ServiceAction::Stop),
+            KeyCode::Char('r') => state.request_service_action(ServiceAction::Restart),
+            _ => return false,
+        },
+        _ if state.active_tab == Tab::History => match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = state.history_buckets.len().saturating_sub(1);
+                state.selected_bucket = (state.selected_bucket + 1).min(last);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected_bucket = state.selected_bucket.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Left => {
+                state.history_range = state.history_range.cycle(code == KeyCode::Right);
+                state.selected_bucket = 0;
+                state.refresh_history_buckets();
+            }
+            _ => return false,
+        },
+        _ if state.active_tab == Tab::Diff => match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = state
+                    .baseline
+                    .as_ref()
+                    .map_or(0, |baseline| baseline.diff(&state.all_processes).len())
+                    .saturating_sub(1);
+                state.selected_delta = (state.selected_delta + 1).min(last);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected_delta = state.selected_delta.saturating_sub(1);
+            }
+            _ => return false,
+        },
+        _ => return false,
+    }
+    true
+}
+
+fn handle_governor_key(state: &mut AppState, code: KeyCode) {
+    let policy_count = state.cpu_info.policies.len();
+    let Some(menu) = &mut state.governor_menu else {
+        return;
+    };
+    match code {
+        KeyCode::Esc | KeyCode::Char('f') | KeyCode::Char('q') => state.governor_menu = None,
+        KeyCode::Down | KeyCode::Char('j') => {
+            menu.selected = (menu.selected + 1).min(menu.governors.len() - 1)
+        }
+        KeyCode::Up | KeyCode::Char('k') => menu.selected = menu.selected.saturating_sub(1),
+        // All policies, then each one on its own
+        KeyCode::Right | KeyCode::Char('l') if policy_count > 1 => {
+            menu.policy = match menu.policy {
+                None => Some(0),
+                Some(i) if i + 1 < policy_count => Some(i + 1),
+                Some(_) => None,
+            }
+        }
+        KeyCode::Left | KeyCode::Char('h') if policy_count > 1 => {
+            menu.policy = match menu.policy {
+                None => Some(policy_count - 1),
+                Some(0) => None,
+                Some(i) => Some(i - 1),
+            }
+        }
+        KeyCode::Enter => {
+            let governor = menu.governors[menu.selected].clone();
+            let policy = menu.policy;
+            let can_switch = menu.can_switch;
+            state.governor_menu = None;
+            if state.read_only {
+                state.status_message = Some("Read-only mode, action disabled".to_string());
+                return;
+            }
+            if !can_switch {
+                state.status_message = Some(format!(
+                    "Can't switch governors, {}",
+                    Capability::CpuFreq.hint()
+                ));
+                return;
+            }
+            let scope = policy
+                .and_then(|i| state.cpu_info.policies.get(i))
+                .map_or("all CPUs".to_string(), |p| p.cpu_label());
+            state.status_message = Some(match state.cpu_info.set_governor(policy, &governor) {
+                Ok(()) => format!("Switched {} to the {} governor", scope, governor),
+                Err(e) => failure(format!(
+                    "Failed to switch {} to {}: {}",
+                    scope,
+                    governor,
+                    capabilities::explain(&e, Capability::CpuFreq)
+                )),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn handle_detail_key(state: &mut AppState, code: KeyCode) {
+    if state.env_view.is_some() {
+        handle_env_key(state, code);
+        return;
+    }
+    if let Some(input) = &mut state.affinity_input {
+        match code {
+            KeyCode::Esc => state.affinity_input = None,
+            KeyCode::Enter => state.apply_affinity(),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == ',' || c == '-' => input.push(c),
+            _ => {}
+        }
+        return;
+    }
+    if let Some(input) = &mut state.io_priority_input {
+        match code {
+            KeyCode::Esc => state.io_priority_input = None,
+            KeyCode::Enter => state.apply_io_priority(),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() || c == '/' || c == '-' => input.push(c),
+            _ => {}
+        }
+        return;
+    }
+    if let Some(input) = &mut state.oom_adj_input {
+        match code {
+            KeyCode::Esc => state.oom_adj_input = None,
+            KeyCode::Enter => state.apply_oom_adj(),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => input.push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    match code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+            state.detail_pid = None;
+            state.followed_tree = None;
+            state.env_view = None;
+            state.wait_trace = None;
+        }
+        KeyCode::Char('e' | 'w') if state.remote => {
+            state.status_message = Some("Not available for a remote host's process".to_string());
+        }
+        KeyCode::Char('w') => {
+            state.wait_trace = match state.wait_trace {
+                Some(_) => None,
+                None => state.detail_pid.map(WaitTrace::new),
+            };
+        }
+        KeyCode::Char('e') => {
+            if let Some(pid) = state.detail_pid {
+                state.env_view = Some(EnvView {
+                    vars: process::environment(pid),
+                    scroll: 0,
+                    search: String::new(),
+                    searching: false,
+                    reveal: false,
+                });
+            }
+        }
+        KeyCode::Char('a' | 'i' | 'o') if state.read_only => {
+            state.status_message = Some("Read-only mode, action disabled".to_string());
+        }
+        KeyCode::Char('a') => {
+            // Start from the current mask so small edits are easy
+            let current = state
+                .detail_pid
+                .and_then(|pid| process::get_affinity(pid).ok())
+                .map(|cpus| numa::format_cpu_list(&cpus))
+                .unwrap_or_default();
+            state.affinity_input = Some(current);
+        }
+        KeyCode::Char('i') => {
+            let current = state
+                .detail_pid
+                .and_then(|pid| process::get_io_priority(pid).ok())
+                .map(|priority| priority.short())
+                .unwrap_or_default();
+            state.io_priority_input = Some(current);
+        }
+        KeyCode::Char('o') => {
+            let current = state
+                .detail_pid
+                .and_then(|pid| process::oom_score(pid).ok())
+                .map(|oom| oom.adj.to_string())
+                .unwrap_or_default();
+            state.oom_adj_input = Some(current);
+        }
+        _ => {}
+    }
+}
+
+fn handle_env_key(state: &mut AppState, code: KeyCode) {
+    let read_only = state.read_only;
+    let Some(view) = &mut state.env_view else {
+        return;
+    };
+
+    if view.searching {
+        match code {
+            KeyCode::Esc | KeyCode::Enter => view.searching = false,
+            KeyCode::Backspace => {
+                view.search.pop();
+            }
+            KeyCode::Char(c) => view.search.push(c),
+            _ => {}
+        }
+        view.scroll = 0;
+        return;
+    }
+
+    match code {
+        KeyCode::Esc | KeyCode::Char('e') | KeyCode::Char('q') => state.env_view = None,
+        KeyCode::Char('/') => view.searching = true,
+        // Shared read-only sessions must not expose secrets
+        KeyCode::Char('v') if read_only => {
+            state.status_message = Some("Read-only mode, values stay masked".to_string());
+        }
+        KeyCode::Char('v') => view.reveal = !view.reveal,
+        KeyCode::Down | KeyCode::Char('j') => {
+            let last = view.filtered().len().saturating_sub(1);
+            view.scroll = (view.scroll + 1).min(last);
+        }
+        KeyCode::Up | KeyCode::Char('k') => view.scroll = view.scroll.saturating_sub(1),
+        _ => {}
+    }
+}
+
+/// Edit and run the command palette line, returns true when the command quits
+fn handle_command_key(state: &mut AppState, code: KeyCode) -> bool {
+    let Some(line) = &mut state.command_line else {
+        return false;
+    };
+    match code {
+        KeyCode::Esc => state.command_line = None,
+        // Like vim, backspacing over the ':' closes the palette
+        KeyCode::Backspace if line.is_empty() => state.command_line = None,
+        KeyCode::Backspace => {
+            line.pop();
+        }
+        KeyCode::Char(c) => line.push(c),
+        KeyCode::Enter => {
+            let line = state.command_line.take().unwrap_or_default();
+            return state.run_command(&line);
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Type the critical process' name, nothing is killed unless it matches exactly
+fn handle_name_guard_key(state: &mut AppState, code: KeyCode) {
+    let Some(guard) = &mut state.name_guard else {
+        return;
+    };
+    match code {
+        KeyCode::Esc => state.cancel_action(),
+        KeyCode::Backspace => {
+            guard.typed.pop();
+        }
+        KeyCode::Char(c) => guard.typed.push(c),
+        KeyCode::Enter if guard.typed == guard.name => state.confirm_action(),
+        KeyCode::Enter => {
+            state.status_message = Some(format!(
+                "Name didn't match {}, nothing was killed",
+                guard.name
+            ));
+            state.cancel_action();
+        }
+        _ => {}
+    }
+}
+
+fn handle_chart_options_key(state: &mut AppState, code: KeyCode) {
+    let Some(selected) = state.chart_options else {
+        return;
+    };
+    match code {
+        KeyCode::Esc | KeyCode::Char('o') | KeyCode::Char('q') => state.chart_options = None,
+        KeyCode::Down | KeyCode::Char('j') => {
+            state.chart_options = Some((selected + 1).min(CHART_PRESETS.len() - 1));
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.chart_options = Some(selected.saturating_sub(1));
+        }
+        KeyCode::Enter => {
+            state.cpu_info.set_chart_config(CHART_PRESETS[selected]);
+            state.chart_options = None;
+        }
+        _ => {}
+    }
+}
+
+fn render(frame: &mut Frame, state: &AppState) {
+    let stuck = state
+        .d_state_tracker
+        .stuck(process::D_STATE_ALERT_THRESHOLD);
+
+    // Reserve a line at the bottom for alerts only while there is something to report
+    let alert_height = if stuck.is_empty() { 0 } else { 1 };
+    let status_height = if state.show_overhead { 1 } else { 0 };
+    let screen_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Tab bar
+            Constraint::Min(1),    // Active tab
+            Constraint::Length(alert_height),
+            Constraint::Length(status_height),
+        ])
+        .split(frame.area());
+
+    render_tab_bar(frame, state.active_tab, screen_layout[0]);
+
+    if state.show_overhead {
+        render_overhead_status(
+            frame,
+            &state.self_monitor,
+            &state.collector_budget,
+            screen_layout[3],
+        );
+    }
+
+    if !stuck.is_empty() {
+        render_d_state_alert(frame, &stuck, screen_layout[2]);
+    }
+
+    match state.active_tab {
+        Tab::Overview => render_overview(frame, state, screen_layout[1]),
+        Tab::Services => render_services_tab(frame, state, screen_layout[1]),
+        Tab::Diff => render_diff_tab(frame, state, screen_layout[1]),
+        Tab::Numa => render_numa_tab(frame, state, screen_layout[1]),
+        Tab::Users => render_users_tab(frame, state, screen_layout[1]),
+        Tab::Network => render_network_tab(frame, state, screen_layout[1]),
+        Tab::Log => render_log_tab(
+            frame,
+            &state.log,
+            state.panel_scroll(Focus::Log),
+            screen_layout[1],
+        ),
+        Tab::Power => render_power_tab(frame, state, screen_layout[1]),
+        Tab::History => render_history_tab(frame, state, screen_layout[1]),
+    }
+
+    if let Some(selected) = state.chart_options {
+        render_chart_options_popup(frame, selected, frame.area());
+    }
+    if let Some(menu) = &state.governor_menu {
+        render_governor_popup(frame, state, menu, frame.area());
+    }
+    if let Some(details) = &state.memory_details {
+        render_memory_details_popup(frame, details, frame.area());
+    }
+    if state.diagnostics_open {
+        render_diagnostics_popup(frame, &state.collector_budget, frame.area());
+    }
+    if state.hardware_open {
+        render_hardware_popup(frame, &state.hardware, frame.area());
+    }
+    if let (Some(pid), Some(view)) = (state.detail_pid, &state.env_view) {
+        render_env_popup(frame, state, pid, view, frame.area());
+    } else if let Some(pid) = state.detail_pid {
+        render_detail_popup(frame, state, pid, frame.area());
+    }
+    if let Some(action) = &state.pending_action {
+        render_confirm_popup(frame, action, state.name_guard.as_ref(), frame.area());
+    }
+    if let Some(line) = &state.command_line {
+        render_command_palette(frame, line, frame.area());
+    }
+    // No flashing in accessible (reduced motion) mode, the status message still says what fired
+    if state.flash_until.is_some() && !accessible() {
+        frame.render_widget(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(Color::Red)),
+            frame.area(),
+        );
+    }
+}
+
+fn render_command_palette(frame: &mut Frame, line: &str, area: Rect) {
+    let error = commands::live_error(line);
+    let hint = match &error {
+        // Marked in text too, not only by color
+        Some(error) => Span::styled(format!("✗ {}", error), Style::default().fg(Color::LightRed)),
+        None => {
+            let hints: Vec<String> = commands::completions(line)
+                .iter()
+                .map(|(name, args)| format!("{} {}", name, args).trim_end().to_string())
+                .collect();
+            Span::styled(hints.join(" | "), Style::default().fg(Color::Gray))
+        }
+    };
+    let color = if error.is_some() {
+        Color::LightRed
+    } else {
+        Color::LightCyan
+    };
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(":", Style::default().fg(color)),
+            Span::raw(line.to_string()),
+            Span::styled("█", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(hint),
+    ];
+
+    let height = 4.min(area.height);
+    let popup_area = Rect::new(area.x, area.y + area.height - height, area.width, height);
+    let block = focus_border(
+        Block::default()
+            .title(" Command (Enter: run | Esc: cancel) ")
+            .borders(Borders::ALL),
+        color,
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+fn render_overhead_status(
+    frame: &mut Frame,
+    self_monitor: &SelfMonitor,
+    budget: &CollectorBudget,
+    area: Rect,
+) {
+    let mut spans = vec![
+        Span::styled(" m-as overhead: ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("CPU {:.1}%", self_monitor.cpu_usage),
+            Style::default().fg(Color::Red),
+        ),
+        Span::styled(" | ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("RSS {}", units::format_mb(self_monitor.memory_mb)),
+            Style::default().fg(Color::Blue),
+        ),
+    ];
+    for (name, cost) in budget.throttled() {
+        spans.push(Span::styled(" | ", Style::default().fg(Color::Gray)));
+        spans.push(Span::styled(
+            format!(
+                "{} {}ms/run, {}x slower",
+                name,
+                cost.last.as_millis(),
+                cost.slowdown
+            ),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let status = Line::from(spans);
+
+    frame.render_widget(Paragraph::new(status), area);
+}
+
+fn render_tab_bar(frame: &mut Frame, active_tab: Tab, area: Rect) {
+    let titles = Tab::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| format!("{} {}", i + 1, tab.title()));
+    let selected = Tab::ALL.iter().position(|tab| *tab == active_tab);
+
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().fg(Color::Gray))
+        .highlight_style(
             Style::default()
-                .fg(Color::Blue)
+                .fg(Color::LightCyan)
                 .add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "STATUS",
+        );
+
+    frame.render_widget(tabs, area);
+}
+
+fn render_overview(frame: &mut Frame, state: &AppState, area: Rect) {
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(40), // Top 40% for CPU
+            Constraint::Percentage(60), // Bottom 60% for processes, etc.
+        ])
+        .split(area);
+
+    let top_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(75), // CPU cores and history
+            Constraint::Percentage(25), // Top CPU/memory hogs
+        ])
+        .split(main_layout[0]);
+
+    panels::draw(frame, &CpuPanel, state, top_layout[0]);
+    let top_right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(6)])
+        .split(top_layout[1]);
+    render_top_processes(frame, state, top_right[0]);
+    render_kernel_section(
+        frame,
+        &state.kernel,
+        freshness_title(state, "kernel"),
+        top_right[1],
+    );
+
+    let bottom_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(50), // Left 50% for processes
+            Constraint::Percentage(50), // Right 50% for other info
+        ])
+        .split(main_layout[1]);
+
+    panels::draw(frame, &ProcessPanel, state, bottom_layout[0]);
+
+    let right_side_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(25), // Memory
+            Constraint::Percentage(25), // Disks
+            Constraint::Percentage(15), // Network
+            Constraint::Percentage(15), // SSH sessions
+            Constraint::Percentage(20), // Process events
+        ])
+        .split(bottom_layout[1]);
+
+    panels::draw(frame, &MemoryPanel, state, right_side_layout[0]);
+    panels::draw(frame, &DiskPanel, state, right_side_layout[1]);
+    panels::draw(frame, &NetworkPanel, state, right_side_layout[2]);
+    render_sessions_section(
+        frame,
+        &state.session_info,
+        &state.capabilities,
+        state.panel_scroll(Focus::Sessions),
+        state.focused_panel() == Some(Focus::Sessions),
+        freshness_title(state, "sessions"),
+        right_side_layout[3],
+    );
+    let events_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(right_side_layout[4]);
+    render_lifecycle_section(
+        frame,
+        &state.lifecycle,
+        state.panel_scroll(Focus::Events),
+        state.focused_panel() == Some(Focus::Events),
+        events_layout[0],
+    );
+    render_leak_section(frame, &state.leaks, events_layout[1]);
+}
+
+/// Stands in for a panel's contents until its collector's first reading
+pub fn render_collecting(frame: &mut Frame, area: Rect) {
+    render_placeholder(frame, area, "collecting…");
+}
+
+/// One dim line in the middle of `area`, for a panel that has nothing to show
+pub fn render_placeholder(frame: &mut Frame, area: Rect, text: &str) {
+    let [_, middle, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center),
+        middle,
+    );
+}
+
+/// Right aligned "(every 1.0s, 0.3s ago)" title for a panel fed by `collector`
+pub fn freshness_title(state: &AppState, collector: &str) -> Line<'static> {
+    let label = state.freshness.label(collector);
+    if label.is_empty() {
+        return Line::default();
+    }
+    Line::from(format!(" {} ", label)).right_aligned()
+}
+
+fn render_services_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let service_info = &state.service_info;
+
+    let footer = match &state.status_message {
+        Some(message) => format!(" {} ", message),
+        None if state.read_only => " read-only ".to_string(),
+        None => " s: start | x: stop | r: restart ".to_string(),
+    };
+
+    let block = Block::default()
+        .title(format!(" Services ({}) ", service_info.services.len()))
+        .title(freshness_title(state, "services"))
+        .title_bottom(footer)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightGreen));
+
+    if !service_info.available {
+        let message = Paragraph::new("systemd is not available on this system")
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(message, area);
+        return;
+    }
+
+    let widths = [
+        Constraint::Length(40), // Unit
+        Constraint::Length(9),  // Load
+        Constraint::Length(10), // Active
+        Constraint::Length(10), // Sub
+        Constraint::Length(7),  // CPU%
+        Constraint::Length(10), // Memory
+        Constraint::Length(8),  // CPU pressure
+        Constraint::Length(8),  // Memory pressure
+        Constraint::Length(8),  // IO pressure
+        Constraint::Min(10),    // Description
+    ];
+
+    let header = Row::new(
+        [
+            ("UNIT", Color::Green),
+            ("LOAD", Color::Gray),
+            ("ACTIVE", Color::Cyan),
+            ("SUB", Color::Cyan),
+            ("CPU%", Color::Red),
+            ("MEMORY", Color::Blue),
+            ("PSI CPU", Color::Red),
+            ("PSI MEM", Color::Blue),
+            ("PSI IO", Color::Magenta),
+            ("DESCRIPTION", Color::Gray),
+        ]
+        .map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }),
+    )
+    .height(1)
+    .bottom_margin(1);
+
+    let rows = service_info.services.iter().map(|service| {
+        let active_color = match service.active.as_str() {
+            "active" => Color::Green,
+            "failed" => Color::Red,
+            _ => Color::Gray,
+        };
+        Row::new(vec![
+            Cell::from(Span::styled(
+                service.unit.clone(),
+                Style::default().fg(Color::Green),
+            )),
+            Cell::from(Span::styled(
+                service.load.clone(),
+                Style::default().fg(Color::Gray),
+            )),
+            Cell::from(Span::styled(
+                service.active.clone(),
+                Style::default().fg(active_color),
+            )),
+            Cell::from(Span::styled(
+                service.sub.clone(),
+                Style::default().fg(Color::Cyan),
+            )),
+            Cell::from(Span::styled(
+                service
+                    .cpu_usage
+                    .map_or("-".to_string(), |cpu| format!("{:.1}%", cpu)),
+                Style::default().fg(Color::Red),
+            )),
+            Cell::from(Span::styled(
+                service
+                    .memory_bytes
+                    .map_or("-".to_string(), units::format_size),
+                Style::default().fg(Color::Blue),
+            )),
+            pressure_cell(service.pressure.cpu),
+            pressure_cell(service.pressure.memory),
+            pressure_cell(service.pressure.io),
+            Cell::from(Span::styled(
+                service.description.clone(),
+                Style::default().fg(Color::Gray),
+            )),
+        ])
+    });
+
+    let table = Table::new(rows.collect::<Vec<_>>(), widths)
+        .header(header)
+        .block(block)
+        .column_spacing(1)
+        .row_highlight_style(make_highlight_style())
+        .highlight_symbol(">> ");
+
+    // Slices and containers get their own pressure table below when PSI is available
+    let (services_area, cgroups_area) = if service_info.cgroups.is_empty() {
+        (area, None)
+    } else {
+        let height = (service_info.cgroups.len() as u16 + 4).min(area.height / 3);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(height)])
+            .split(area);
+        (layout[0], Some(layout[1]))
+    };
+
+    frame.render_stateful_widget(
+        table,
+        services_area,
+        &mut TableState::default().with_selected(Some(state.selected_service)),
+    );
+    if let Some(cgroups_area) = cgroups_area {
+        render_cgroup_pressure(frame, &service_info.cgroups, cgroups_area);
+    }
+}
+
+fn render_cgroup_pressure(frame: &mut Frame, cgroups: &[CgroupPressure], area: Rect) {
+    let block = Block::default()
+        .title(" Pressure by slice / container (some avg10) ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightGreen));
+
+    let header = Row::new(
+        [
+            ("CGROUP", Color::Green),
+            ("CPU", Color::Red),
+            ("MEMORY", Color::Blue),
+            ("IO", Color::Magenta),
+        ]
+        .map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }),
+    )
+    .height(1)
+    .bottom_margin(1);
+
+    let rows = cgroups.iter().map(|cgroup| {
+        Row::new(vec![
+            Cell::from(Span::styled(
+                cgroup.name.clone(),
+                Style::default().fg(Color::Green),
+            )),
+            pressure_cell(cgroup.pressure.cpu),
+            pressure_cell(cgroup.pressure.memory),
+            pressure_cell(cgroup.pressure.io),
+        ])
+    });
+
+    let table = Table::new(
+        rows.collect::<Vec<_>>(),
+        [
+            Constraint::Min(30),   // Cgroup
+            Constraint::Length(8), // CPU
+            Constraint::Length(8), // Memory
+            Constraint::Length(8), // IO
+        ],
+    )
+    .header(header)
+    .block(block)
+    .column_spacing(1);
+    frame.render_widget(table, area);
+}
+
+/// Stall percentage, highlighted once tasks wait noticeably
+fn pressure_cell(pressure: Option<f32>) -> Cell<'static> {
+    let Some(pressure) = pressure else {
+        return Cell::from(Span::styled("-", Style::default().fg(Color::Gray)));
+    };
+    let color = if pressure >= 20.0 {
+        Color::Red
+    } else if pressure >= 5.0 {
+        Color::Yellow
+    } else {
+        Color::Gray
+    };
+    Cell::from(Span::styled(
+        format!("{:.1}%", pressure),
+        Style::default().fg(color),
+    ))
+}
+
+fn render_diff_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightMagenta));
+
+    let Some(baseline) = &state.baseline else {
+        let message =
+            Paragraph::new("Press b to mark a baseline, then come back to see what changed")
+                .alignment(Alignment::Center)
+                .block(block.title(" Diff "));
+        frame.render_widget(message, area);
+        return;
+    };
+
+    let deltas = baseline.diff(&state.all_processes);
+    let count = |change: Change| deltas.iter().filter(|d| d.change == change).count();
+    let total_growth: f64 = deltas.iter().map(|d| d.memory_delta_mb).sum();
+    let cpu_time_ms: u64 = deltas.iter().map(|d| d.cpu_time_ms).sum();
+
+    let footer = match &state.status_message {
+        Some(message) => format!(" {} ", message),
+        None => " b: mark new baseline ".to_string(),
+    };
+    let block = block
+        .title(format!(
+            " Diff since {} ago: {} new, {} exited, memory {:+.1}MB, CPU time {:.1}s ",
+            format_elapsed(baseline.age()),
+            count(Change::New),
+            count(Change::Exited),
+            total_growth,
+            cpu_time_ms as f64 / 1000.0
+        ))
+        .title_bottom(footer);
+
+    let widths = [
+        Constraint::Length(8),  // PID
+        Constraint::Length(20), // Name
+        Constraint::Length(8),  // Change
+        Constraint::Length(12), // Memory delta
+        Constraint::Min(10),    // CPU time
+    ];
+
+    let header = Row::new(
+        [
+            ("PID", Color::Yellow),
+            ("NAME", Color::Green),
+            ("CHANGE", Color::Cyan),
+            ("ΔMEMORY", Color::Blue),
+            ("CPU TIME", Color::Red),
+        ]
+        .map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }),
+    )
+    .height(1)
+    .bottom_margin(1);
+
+    let rows = deltas.iter().map(|delta| {
+        let change_color = match delta.change {
+            Change::New => Color::Green,
+            Change::Exited => Color::Red,
+            Change::Running => Color::Gray,
+        };
+        let memory_color = if delta.memory_delta_mb > 0.0 {
+            Color::LightRed
+        } else {
+            Color::Blue
+        };
+        Row::new(vec![
+            Cell::from(Span::styled(
+                delta.pid.to_string(),
+                Style::default().fg(Color::Yellow),
+            )),
+            Cell::from(Span::styled(
+                delta.name.clone(),
+                Style::default().fg(Color::Green),
+            )),
+            Cell::from(Span::styled(
+                delta.change.label(),
+                Style::default().fg(change_color),
+            )),
+            Cell::from(Span::styled(
+                format!("{:+.1}MB", delta.memory_delta_mb),
+                Style::default().fg(memory_color),
+            )),
+            Cell::from(Span::styled(
+                format!("{:.2}s", delta.cpu_time_ms as f64 / 1000.0),
+                Style::default().fg(Color::Red),
+            )),
+        ])
+    });
+
+    let table = Table::new(rows.collect::<Vec<_>>(), widths)
+        .header(header)
+        .block(block)
+        .column_spacing(1)
+        .row_highlight_style(make_highlight_style())
+        .highlight_symbol(">> ");
+
+    let selected = state.selected_delta.min(deltas.len().saturating_sub(1));
+    frame.render_stateful_widget(
+        table,
+        area,
+        &mut TableState::default().with_selected(Some(selected)),
+    );
+}
+
+/// Average CPU and memory per bucket on top, min/avg/max of every bucket below
+fn render_history_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let range = state.history_range;
+    let footer = match &state.history_error {
+        Some(error) => format!(" {} ", error),
+        None => " ←/→: range | ↑/↓: select ".to_string(),
+    };
+    let block = Block::default()
+        .title(format!(
+            " History | last {}, {} buckets ",
+            range.label(),
+            history::format_interval(range.bucket())
+        ))
+        .title_bottom(footer)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightBlue));
+
+    let buckets = &state.history_buckets;
+    let message = if state.history_db.is_none() {
+        Some("Start with --history-db PATH to record CPU and memory usage over hours and days")
+    } else if buckets.is_empty() {
+        Some("No samples in this range yet, one is recorded every 10s")
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        let message = Paragraph::new(message)
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(message, area);
+        return;
+    }
+
+    let [chart_area, table_area] =
+        Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(area);
+
+    let cpu: Vec<(f64, f64)> = buckets
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| (i as f64, bucket.cpu.avg))
+        .collect();
+    let memory: Vec<(f64, f64)> = buckets
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| (i as f64, bucket.memory.avg))
+        .collect();
+    let first = buckets
+        .first()
+        .map_or(String::new(), |b| process::format_timestamp(b.start, true));
+    let chart = Chart::new(vec![
+        Dataset::default()
+            .name("CPU avg")
+            .data(&cpu)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Red))
+            .marker(Marker::Braille),
+        Dataset::default()
+            .name("Memory avg")
+            .data(&memory)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Blue))
+            .marker(Marker::Braille),
+    ])
+    .block(block)
+    .x_axis(
+        Axis::default()
+            .bounds([0.0, (buckets.len().max(2) - 1) as f64])
+            .style(Style::default().fg(Color::Gray))
+            .labels::<Vec<Span>>(vec![Span::raw(first), Span::raw("now")]),
+    )
+    .y_axis(
+        Axis::default()
+            .bounds([0.0, 100.0])
+            .style(Style::default().fg(Color::Gray))
+            .labels::<Vec<Span>>(vec![Span::raw("0%"), Span::raw("50%"), Span::raw("100%")]),
+    );
+    frame.render_widget(chart, chart_area);
+
+    let widths = [
+        Constraint::Length(20), // Start
+        Constraint::Length(8),  // Samples
+        Constraint::Length(20), // CPU
+        Constraint::Length(20), // Memory
+        Constraint::Min(20),    // Swap
+    ];
+    let header = Row::new(
+        [
+            ("START", Color::Yellow),
+            ("SAMPLES", Color::Gray),
+            ("CPU min/avg/max", Color::Red),
+            ("MEM min/avg/max", Color::Blue),
+            ("SWAP min/avg/max", Color::LightMagenta),
+        ]
+        .map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }),
+    )
+    .height(1)
+    .bottom_margin(1);
+    let stats = |stats: &history_db::Stats| {
+        format!("{:>5.1} {:>5.1} {:>5.1}", stats.min, stats.avg, stats.max)
+    };
+    // Newest first, what's usually looked for
+    let rows: Vec<Row> = buckets
+        .iter()
+        .rev()
+        .map(|bucket| {
+            Row::new(
+                [
+                    (process::format_timestamp(bucket.start, true), Color::Yellow),
+                    (bucket.samples.to_string(), Color::Gray),
+                    (stats(&bucket.cpu), Color::Red),
+                    (stats(&bucket.memory), Color::Blue),
+                    (stats(&bucket.swap), Color::LightMagenta),
+                ]
+                .map(|(text, color)| Cell::from(Span::styled(text, Style::default().fg(color)))),
+            )
+        })
+        .collect();
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(Color::LightBlue)),
+        )
+        .column_spacing(1)
+        .row_highlight_style(make_highlight_style())
+        .highlight_symbol(">> ");
+    let selected = state.selected_bucket.min(buckets.len() - 1);
+    frame.render_stateful_widget(
+        table,
+        table_area,
+        &mut TableState::default().with_selected(Some(selected)),
+    );
+}
+
+/// Throughput chart on top, probe latencies when configured, the ARP/neighbor table below
+fn render_network_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let probe_height = if state.probes.is_empty() { 0 } else { 12 };
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(12),
+            Constraint::Length(probe_height),
+            Constraint::Min(5),
+        ])
+        .split(area);
+    panels::draw(frame, &NetworkPanel, state, layout[0]);
+    if !state.probes.is_empty() {
+        render_probes_section(frame, &state.probes, layout[1]);
+    }
+
+    let block = Block::default()
+        .title(format!(" Neighbors ({}) ", state.neighbors.len()))
+        .title(scrolled_title(state.panel_scroll(Focus::Neighbors)))
+        .title(freshness_title(state, "neighbors"))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightBlue));
+
+    let widths = [
+        Constraint::Length(40), // IP (room for IPv6)
+        Constraint::Length(18), // MAC
+        Constraint::Length(12), // Interface
+        Constraint::Min(10),    // State
+    ];
+
+    let header = Row::new(
+        [
+            ("IP", Color::Yellow),
+            ("MAC", Color::Cyan),
+            ("INTERFACE", Color::Green),
+            ("STATE", Color::Gray),
+        ]
+        .map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }),
+    )
+    .height(1)
+    .bottom_margin(1);
+
+    let rows = state
+        .neighbors
+        .iter()
+        .skip(state.panel_scroll(Focus::Neighbors))
+        .map(|neighbor| {
+            // Resolved entries are fine, ones being checked are worth a look, failed ones are a problem
+            let state_color = match neighbor.state.as_str() {
+                "REACHABLE" | "PERMANENT" | "COMPLETE" => Color::LightGreen,
+                "FAILED" | "INCOMPLETE" => Color::Red,
+                "NOARP" => Color::Gray,
+                _ => Color::Yellow,
+            };
+            Row::new(vec![
+                Cell::from(Span::styled(
+                    neighbor.ip.clone(),
+                    Style::default().fg(Color::Yellow),
+                )),
+                Cell::from(Span::styled(
+                    neighbor.mac.clone().unwrap_or_else(|| "-".to_string()),
+                    Style::default().fg(Color::Cyan),
+                )),
+                Cell::from(Span::styled(
+                    neighbor.interface.clone(),
+                    Style::default().fg(Color::Green),
+                )),
+                Cell::from(Span::styled(
+                    neighbor.state.clone(),
+                    Style::default().fg(state_color),
+                )),
+            ])
+        });
+
+    let table = Table::new(rows.collect::<Vec<_>>(), widths)
+        .header(header)
+        .block(block)
+        .column_spacing(1);
+    frame.render_widget(table, layout[2]);
+}
+
+/// One latency line per target, lost probes leave a gap
+fn render_probes_section(frame: &mut Frame, probes: &Probes, area: Rect) {
+    const COLORS: [Color; 6] = [
+        Color::LightCyan,
+        Color::LightMagenta,
+        Color::LightYellow,
+        Color::LightGreen,
+        Color::LightBlue,
+        Color::LightRed,
+    ];
+    let millis = |latency: &Duration| latency.as_secs_f64() * 1000.0;
+
+    let failing: Vec<Span> = probes
+        .histories
+        .iter()
+        .filter_map(|history| {
+            let error = history.last_error.as_ref()?;
+            Some(Span::styled(
+                format!(" {}: {} ", history.target, error),
+                Style::default().fg(Color::Red),
+            ))
+        })
+        .collect();
+    let block = Block::default()
+        .title(format!(
+            " Probes (every {}) ",
+            history::format_interval(probes::PROBE_INTERVAL)
+        ))
+        .title_bottom(Line::from(failing))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightBlue));
+
+    let points: Vec<Vec<(f64, f64)>> = probes
+        .histories
+        .iter()
+        .map(|history| {
+            history
+                .latencies
+                .iter()
+                .enumerate()
+                .filter_map(|(x, latency)| Some((x as f64, millis(latency.as_ref()?))))
+                .collect()
+        })
+        .collect();
+    let names: Vec<String> = probes
+        .histories
+        .iter()
+        .map(|history| {
+            let last = match history.last() {
+                Some(Some(latency)) => format!("{:.1}ms", millis(&latency)),
+                Some(None) => "lost".to_string(),
+                None => "…".to_string(),
+            };
+            format!("{} {} ({} lost)", history.target, last, history.lost())
+        })
+        .collect();
+    let datasets = points
+        .iter()
+        .zip(names)
+        .zip(COLORS.iter().cycle())
+        .map(|((data, name), &color)| {
+            Dataset::default()
+                .name(name)
+                .data(data)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(color))
+                .marker(Marker::Braille)
+        })
+        .collect();
+
+    let peak = points
+        .iter()
+        .flatten()
+        .map(|&(_, ms)| ms)
+        .fold(1.0, f64::max);
+    let y_max = (peak * 1.2).ceil();
+    let x_max = probes
+        .histories
+        .iter()
+        .map(|history| history.latencies.len())
+        .max()
+        .unwrap_or(0)
+        .max(2) as f64
+        - 1.0;
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(Axis::default().bounds([0.0, x_max]))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, y_max])
+                .style(Style::default().fg(Color::Gray))
+                .labels::<Vec<Span>>(vec![
+                    Span::raw("0ms"),
+                    Span::raw(format!("{:.0}ms", y_max / 2.0)),
+                    Span::raw(format!("{:.0}ms", y_max)),
+                ]),
+        )
+        .hidden_legend_constraints((Constraint::Ratio(1, 1), Constraint::Ratio(1, 1)));
+
+    frame.render_widget(chart, area);
+}
+
+fn render_users_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let users = process::usage_by_user(&state.all_processes);
+    let total_memory_mb = state.memory_info.total_memory as f64 / 1024.0 / 1024.0;
+
+    let block = Block::default()
+        .title(format!(" Users ({}) ", users.len()))
+        .title(freshness_title(state, "processes"))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightYellow));
+
+    let widths = [
+        Constraint::Length(20), // User
+        Constraint::Length(10), // Processes
+        Constraint::Length(8),  // CPU%
+        Constraint::Length(12), // Memory
+        Constraint::Min(10),    // Share of memory
+    ];
+
+    let header = Row::new(
+        [
+            ("USER", Color::Green),
+            ("PROCESSES", Color::Yellow),
+            ("CPU%", Color::Red),
+            ("MEMORY", Color::Blue),
+            ("% OF RAM", Color::Blue),
+        ]
+        .map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }),
+    )
+    .height(1)
+    .bottom_margin(1);
+
+    let rows = users.iter().map(|usage| {
+        let fraction = if total_memory_mb > 0.0 {
+            usage.memory_mb / total_memory_mb
+        } else {
+            0.0
+        };
+        Row::new(vec![
+            Cell::from(Span::styled(
+                usage.user.clone(),
+                Style::default().fg(Color::Green),
+            )),
+            Cell::from(Span::styled(
+                usage.processes.to_string(),
+                Style::default().fg(Color::Yellow),
+            )),
+            Cell::from(Span::styled(
+                format!("{:.1}%", usage.cpu_usage),
+                Style::default().fg(Color::Red),
+            )),
+            Cell::from(Span::styled(
+                units::format_mb(usage.memory_mb),
+                Style::default().fg(Color::Blue),
+            )),
+            Cell::from(Span::styled(
+                format!("{} {:.1}%", mini_bar(fraction, 20), fraction * 100.0),
+                Style::default().fg(Color::Blue),
+            )),
+        ])
+    });
+
+    let table = Table::new(rows.collect::<Vec<_>>(), widths)
+        .header(header)
+        .block(block)
+        .column_spacing(1);
+    frame.render_widget(table, area);
+}
+
+fn render_numa_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let numa_info = &state.numa_info;
+
+    let block = |title: String| {
+        Block::default()
+            .title(title)
+            .title(freshness_title(state, "numa"))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::LightBlue))
+    };
+
+    if numa_info.nodes.is_empty() {
+        let message = Paragraph::new("No NUMA topology reported by this system")
+            .alignment(Alignment::Center)
+            .block(block(" NUMA ".to_string()));
+        frame.render_widget(message, area);
+        return;
+    }
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(numa_info.nodes.len() as u16 + 4),
+            Constraint::Min(3),
+        ])
+        .split(area);
+
+    let header = |titles: Vec<(&'static str, Color)>| {
+        Row::new(titles.into_iter().map(|(title, color)| {
+            Cell::from(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+        }))
+        .height(1)
+        .bottom_margin(1)
+    };
+
+    let node_rows = numa_info.nodes.iter().map(|node| {
+        let memory_color = threshold_color(state.memory_thresholds.level(node.memory_percent()));
+        Row::new(vec![
+            Cell::from(Span::styled(
+                format!("node{}", node.id),
+                Style::default().fg(Color::Yellow),
+            )),
+            Cell::from(Span::styled(
+                numa::format_cpu_list(&node.cpus),
+                Style::default().fg(Color::Gray),
+            )),
+            Cell::from(Span::styled(
+                format!(
+                    "{} {:>5.1}%",
+                    mini_bar(node.cpu_usage as f64 / 100.0, 10),
+                    node.cpu_usage
+                ),
+                Style::default().fg(Color::Red),
+            )),
+            Cell::from(Span::styled(
+                format!(
+                    "{} {:.1}/{:.1} GB",
+                    mini_bar(node.memory_percent() as f64 / 100.0, 10),
+                    node.used_memory() as f64 / 1024.0 / 1024.0 / 1024.0,
+                    node.total_memory as f64 / 1024.0 / 1024.0 / 1024.0
+                ),
+                Style::default().fg(memory_color),
+            )),
+        ])
+    });
+    let node_table = Table::new(
+        node_rows.collect::<Vec<_>>(),
+        [
+            Constraint::Length(8),  // Node
+            Constraint::Length(20), // CPUs
+            Constraint::Length(18), // CPU%
+            Constraint::Min(20),    // Memory
+        ],
+    )
+    .header(header(vec![
+        ("NODE", Color::Yellow),
+        ("CPUS", Color::Gray),
+        ("CPU%", Color::Red),
+        ("MEMORY", Color::Blue),
+    ]))
+    .block(block(format!(" NUMA nodes ({}) ", numa_info.nodes.len())))
+    .column_spacing(1);
+    frame.render_widget(node_table, layout[0]);
+
+    let split_block = block(format!(
+        " Heavy processes split across nodes ({}) ",
+        numa_info.split_processes.len()
+    ));
+    if numa_info.split_processes.is_empty() {
+        let text = if numa_info.nodes.len() < 2 {
+            "Single node system, every process is local"
+        } else {
+            "All heavy processes are local to one node"
+        };
+        let message = Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(split_block);
+        frame.render_widget(message, layout[1]);
+        return;
+    }
+
+    let split_rows = numa_info.split_processes.iter().map(|process| {
+        let placement = process
+            .memory_per_node
+            .iter()
+            .map(|(node, bytes)| format!("N{} {:.0}MB", node, *bytes as f64 / 1024.0 / 1024.0))
+            .collect::<Vec<_>>()
+            .join("  ");
+        Row::new(vec![
+            Cell::from(Span::styled(
+                process.pid.to_string(),
+                Style::default().fg(Color::Yellow),
+            )),
+            Cell::from(Span::styled(
+                process.name.clone(),
+                Style::default().fg(Color::Green),
+            )),
+            Cell::from(Span::styled(
+                format!("{:.0}%", process.locality_percent()),
+                Style::default().fg(Color::LightRed),
+            )),
+            Cell::from(Span::styled(placement, Style::default().fg(Color::Blue))),
+        ])
+    });
+    let split_table = Table::new(
+        split_rows.collect::<Vec<_>>(),
+        [
+            Constraint::Length(8),  // PID
+            Constraint::Length(20), // Name
+            Constraint::Length(9),  // Locality
+            Constraint::Min(20),    // Per node
+        ],
+    )
+    .header(header(vec![
+        ("PID", Color::Yellow),
+        ("NAME", Color::Green),
+        ("LOCAL", Color::LightRed),
+        ("MEMORY PER NODE", Color::Blue),
+    ]))
+    .block(split_block)
+    .column_spacing(1);
+    frame.render_widget(split_table, layout[1]);
+}
+
+/// Battery list and estimate next to the top processes, with the draw and runtime charts below
+fn render_power_tab(frame: &mut Frame, state: &AppState, area: Rect) {
+    let battery = &state.battery;
+    let [top, power_area, runtime_area] = Layout::vertical([
+        // Room for a few top processes even with a single battery
+        Constraint::Length((battery.batteries.len() as u16 + 3).max(10)),
+        Constraint::Percentage(50),
+        Constraint::Percentage(50),
+    ])
+    .areas(area);
+    let [status_area, top_processes_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(top);
+
+    let mut lines: Vec<Line> = battery
+        .batteries
+        .iter()
+        .map(|b| {
+            let color = match b.percent() {
+                p if p < 10.0 => Color::LightRed,
+                p if p < 25.0 => Color::Yellow,
+                _ => Color::Green,
+            };
+            Line::from(vec![
+                Span::styled(format!(" {:<6}", b.name), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format!(
+                        "{} {:>5.1}% ",
+                        mini_bar(b.percent() / 100.0, 10),
+                        b.percent()
+                    ),
+                    Style::default().fg(color),
+                ),
+                Span::styled(
+                    format!(
+                        "{:.1}/{:.1}Wh  {:<12} {}",
+                        b.energy_wh,
+                        b.full_wh,
+                        b.state.label(),
+                        b.power_w.map_or("-".to_string(), |w| format!("{:.1}W", w))
+                    ),
+                    Style::default().fg(Color::White),
+                ),
+            ])
+        })
+        .collect();
+    let estimate = match battery.estimate {
+        Some(Estimate::Empty(left)) => format!("{} left", format_elapsed(left)),
+        Some(Estimate::Full(left)) => format!("full in {}", format_elapsed(left)),
+        None => "no estimate".to_string(),
+    };
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No battery in /sys/class/power_supply",
+            Style::default().fg(Color::Gray),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!(
+                " {}, fitted over the last {}",
+                estimate,
+                history::format_interval(battery::ESTIMATE_WINDOW)
+            ),
+            Style::default().fg(Color::LightCyan),
+        )));
+    }
+    let block = Block::default()
+        .title(" Battery ")
+        .title(freshness_title(state, "battery"))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightGreen));
+    frame.render_widget(Paragraph::new(lines).block(block), status_area);
+    // What to look at when the draw is high
+    render_top_processes(frame, state, top_processes_area);
+
+    let minutes = BATTERY_UPDATE_INTERVAL.as_secs_f64() / 60.0;
+    render_power_chart(
+        frame,
+        " Power draw ",
+        &battery.power_history,
+        |w| format!("{:.1}W", w),
+        Color::LightYellow,
+        minutes,
+        power_area,
+    );
+    render_power_chart(
+        frame,
+        &format!(" Estimated runtime ({}) ", estimate),
+        &battery.runtime_history,
+        |hours| format_elapsed(Duration::from_secs_f64(hours * 3600.0)),
+        Color::LightCyan,
+        minutes,
+        runtime_area,
+    );
+}
+
+/// Line chart of one battery history, the x axis labelled in minutes
+fn render_power_chart(
+    frame: &mut Frame,
+    title: &str,
+    values: &VecDeque<f64>,
+    label: fn(f64) -> String,
+    color: Color,
+    minutes_per_point: f64,
+    area: Rect,
+) {
+    let block = Block::default()
+        .title(title.to_string())
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(color));
+    let points = chart_points(values.iter().copied(), None);
+    let y_max = values.iter().copied().fold(0.0, f64::max).max(f64::EPSILON) * 1.2;
+    let x_max = values.len().max(2) as f64 - 1.0;
+    let chart = Chart::new(vec![Dataset::default()
+        .data(&points)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .marker(Marker::Braille)])
+    .block(block)
+    .x_axis(
+        Axis::default()
+            .bounds([0.0, x_max])
+            .style(Style::default().fg(Color::Gray))
+            .labels::<Vec<Span>>(vec![
+                Span::raw(format!("-{:.0}m", x_max * minutes_per_point)),
+                Span::raw("now"),
+            ]),
+    )
+    .y_axis(
+        Axis::default()
+            .bounds([0.0, y_max])
+            .style(Style::default().fg(Color::Gray))
+            .labels::<Vec<Span>>(vec![
+                Span::raw(label(0.0)),
+                Span::raw(label(y_max / 2.0)),
+                Span::raw(label(y_max)),
+            ]),
+    );
+    frame.render_widget(chart, area);
+}
+
+/// "42s", "3m 05s", "1h 02m"
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+fn render_d_state_alert(frame: &mut Frame, stuck: &[(Pid, String, Duration)], area: Rect) {
+    let processes = stuck
+        .iter()
+        .map(|(pid, name, elapsed)| format!("{} ({}) {}s", name, pid, elapsed.as_secs()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let alert = Paragraph::new(Line::from(vec![
+        Span::styled(
+            format!(" {} process(es) stuck in D state: ", stuck.len()),
             Style::default()
-                .fg(Color::Cyan)
+                .fg(Color::White)
+                .bg(Color::Red)
                 .add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "PARENT",
+        ),
+        Span::styled(
+            format!(" {} (check storage/NFS)", processes),
+            Style::default().fg(Color::Red),
+        ),
+    ]));
+
+    frame.render_widget(alert, area);
+}
+
+/// Rect of the given size centered inside `area`
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn render_confirm_popup(
+    frame: &mut Frame,
+    action: &ConfirmAction,
+    guard: Option<&NameGuard>,
+    area: Rect,
+) {
+    let (title, targets, confirm_hint) = match action {
+        ConfirmAction::KillGroup { pgid, members } => (
+            format!(" Kill process group {}? ", pgid),
+            members.as_slice(),
+            " y: send SIGTERM | any other key: cancel".to_string(),
+        ),
+        ConfirmAction::Kill { members } => (
+            format!(" Kill {} processes? ", members.len()),
+            members.as_slice(),
+            " y: send SIGTERM | any other key: cancel".to_string(),
+        ),
+        ConfirmAction::KillTree { root, members } => (
+            format!(" Kill process tree of {}? ", root),
+            members.as_slice(),
+            " y: SIGTERM, children first | other key: cancel".to_string(),
+        ),
+        ConfirmAction::Service { unit, action } => (
+            format!(" {} {}? ", action, unit),
+            [].as_slice(),
+            format!(" y: {} | any other key: cancel", action),
+        ),
+    };
+
+    let mut lines: Vec<Line> = targets
+        .iter()
+        .map(|(pid, name)| {
+            Line::from(vec![
+                Span::styled(format!(" {:>7} ", pid), Style::default().fg(Color::Yellow)),
+                Span::styled(name.clone(), Style::default().fg(Color::Green)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    let confirm_hint = match guard {
+        Some(guard) => {
+            let warning = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+            lines.push(Line::from(Span::styled(
+                format!(" {} ({})", guard.name, guard.pid),
+                warning,
+            )));
+            lines.push(Line::from(Span::styled(
+                format!(" {}", guard.reason.description()),
+                warning,
+            )));
+            lines.push(Line::from(vec![
+                Span::raw(format!(" Type {} to confirm: ", guard.name)),
+                Span::styled(
+                    format!("{}_", guard.typed),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]));
+            lines.push(Line::from(""));
+            " Enter: send SIGTERM | Esc: cancel".to_string()
+        }
+        None => confirm_hint,
+    };
+    lines.push(Line::from(Span::styled(
+        confirm_hint,
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+
+    let popup_area = centered_rect(50, lines.len() as u16 + 2, area);
+    let block = focus_border(
+        Block::default().title(title).borders(Borders::ALL),
+        Color::Red,
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+fn render_detail_popup(frame: &mut Frame, state: &AppState, pid: Pid, area: Rect) {
+    let field = |label: &str, value: String, color: Color| {
+        Line::from(vec![
+            Span::styled(format!(" {:<10}", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    };
+
+    let mut lines = match state.all_processes.iter().find(|p| p.pid == pid) {
+        Some(process) => vec![
+            field("Name", process.name.clone(), Color::Green),
+            field("Status", process.status.to_string(), Color::Cyan),
+            field(
+                "Parent",
+                process
+                    .parent_pid
+                    .map_or("-".to_string(), |p| p.to_string()),
+                Color::Yellow,
+            ),
+            field(
+                "CPU",
+                format!(
+                    "{:.1}% (total {:.1}s)",
+                    process.cpu_usage,
+                    process.cpu_time_ms as f64 / 1000.0
+                ),
+                Color::Red,
+            ),
+            field("Memory", units::format_mb(process.memory_mb), Color::Blue),
+        ],
+        None => vec![Line::from(Span::styled(
+            " Process exited",
+            Style::default().fg(Color::Red),
+        ))],
+    };
+
+    // Two rows the sparklines are drawn into once the popup area is known
+    let history = &state.selected_history;
+    let memory_min = history.memory_mb.iter().copied().fold(f64::MAX, f64::min);
+    let history_row = (history.pid == Some(pid) && !history.cpu.is_empty()).then(|| {
+        let gap = if accessible() {
+            String::new()
+        } else {
+            " ".repeat(SELECTED_HISTORY_CAPACITY + 1)
+        };
+        let cpu_peak = history.cpu.iter().copied().fold(0.0, f32::max);
+        let cpu_average = history.cpu.iter().sum::<f32>() / history.cpu.len() as f32;
+        let memory_max = history.memory_mb.iter().copied().fold(0.0, f64::max);
+        let row = lines.len();
+        lines.push(field(
+            "CPU 1m",
+            format!("{}peak {:.1}%, avg {:.1}%", gap, cpu_peak, cpu_average),
+            Color::Red,
+        ));
+        lines.push(field(
+            "Memory 1m",
+            format!(
+                "{}{} - {}",
+                gap,
+                units::format_mb(memory_min),
+                units::format_mb(memory_max)
+            ),
+            Color::Blue,
+        ));
+        row
+    });
+
+    if let Some(usage) = state
+        .followed_tree
+        .as_ref()
+        .and_then(|tree| tree.usage(&state.all_processes))
+    {
+        let spawned = if usage.spawned > 0 {
+            format!(", {} spawned since opened", usage.spawned)
+        } else {
+            String::new()
+        };
+        lines.push(field(
+            "Tree",
+            format!(
+                "{} process(es){}: CPU {:.1}% | Memory {}",
+                usage.processes,
+                spawned,
+                usage.cpu_usage,
+                units::format_mb(usage.memory_mb)
+            ),
+            Color::LightCyan,
+        ));
+    }
+    if state.remote {
+        // Everything below is read from the local /proc, the PID is another host's
+        lines.push(field(
+            "Details",
+            "n/a for a remote host's process".to_string(),
+            Color::Gray,
+        ));
+    } else {
+        let affinity = match process::get_affinity(pid) {
+            Ok(cpus) => format!(
+                "{} ({} of {} CPUs)",
+                numa::format_cpu_list(&cpus),
+                cpus.len(),
+                state.cpu_info.cores.len()
+            ),
+            Err(e) => e.to_string(),
+        };
+        lines.push(field("Affinity", affinity, Color::Magenta));
+        if let Some(input) = &state.affinity_input {
+            lines.push(field(
+                "New CPUs",
+                format!("{}_", input),
+                Color::LightMagenta,
+            ));
+        }
+        let disk_io = match process::io_counters(pid) {
+            Ok(counters) => format!(
+                "read {}, written {}",
+                units::format_size(counters.read_bytes),
+                units::format_size(counters.written_bytes)
+            ),
+            Err(e) => capabilities::explain(&e, Capability::OpenFiles),
+        };
+        lines.push(field("Disk I/O", disk_io, Color::LightGreen));
+        if let Some(process) = state.all_processes.iter().find(|p| p.pid == pid) {
+            if let Some(counters) = process.event_counters {
+                // Rates need a second refresh after the details were opened
+                let rate =
+                    |rate: Option<f64>| rate.map_or("-".to_string(), |r| format!("{:.0}/s", r));
+                let rates = process.event_rates;
+                lines.push(field(
+                    "Faults",
+                    format!(
+                        "minor {}, major {} ({} / {} since start)",
+                        rate(rates.map(|r| r.minor_faults)),
+                        rate(rates.map(|r| r.major_faults)),
+                        counters.minor_faults,
+                        counters.major_faults
+                    ),
+                    Color::LightRed,
+                ));
+                lines.push(field(
+                    "Switches",
+                    format!(
+                        "voluntary {}, involuntary {} ({} / {} since start)",
+                        rate(rates.map(|r| r.voluntary_switches)),
+                        rate(rates.map(|r| r.involuntary_switches)),
+                        counters.voluntary_switches,
+                        counters.involuntary_switches
+                    ),
+                    Color::Cyan,
+                ));
+            }
+        }
+        let io_priority = match process::get_io_priority(pid) {
+            Ok(priority) => priority.to_string(),
+            Err(e) => e.to_string(),
+        };
+        lines.push(field("I/O", io_priority, Color::LightBlue));
+        let oom = match process::oom_score(pid) {
+            Ok(oom) => {
+                let note = match oom.adj {
+                    -1000 => ", never killed",
+                    adj if adj < 0 => ", protected",
+                    adj if adj > 0 => ", sacrificed first",
+                    _ => "",
+                };
+                format!("score {}, adjustment {}{}", oom.score, oom.adj, note)
+            }
+            Err(e) => e.to_string(),
+        };
+        lines.push(field("OOM", oom, Color::LightRed));
+        let namespaces = match process::Namespaces::read(pid) {
+            Ok(namespaces) => {
+                // PID 1's namespaces are the host's, unreadable unless we're root
+                let host = process::Namespaces::read(Pid::from_u32(1)).ok();
+                [
+                    ("net", namespaces.net, host.and_then(|h| h.net)),
+                    ("pid", namespaces.pid, host.and_then(|h| h.pid)),
+                    ("mnt", namespaces.mnt, host.and_then(|h| h.mnt)),
+                ]
+                .into_iter()
+                .map(|(kind, inode, host)| match inode {
+                    Some(inode) if Some(inode) == host => format!("{} {} (host)", kind, inode),
+                    Some(inode) => format!("{} {}", kind, inode),
+                    None => format!("{} -", kind),
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+            }
+            Err(e) => capabilities::explain(&e, Capability::OpenFiles),
+        };
+        lines.push(field("Namespace", namespaces, Color::LightYellow));
+        if let Some(container) = process::container(pid) {
+            let mut text = if container.id.is_empty() {
+                container.runtime.to_string()
+            } else {
+                format!("{} {}", container.runtime, container.short_id())
+            };
+            if let Some(uid) = &container.pod_uid {
+                text.push_str(&format!(", pod {}", uid));
+            }
+            lines.push(field("Container", text, Color::LightYellow));
+        }
+        if let Some(input) = &state.io_priority_input {
+            lines.push(field(
+                "New I/O",
+                format!("{}_  (rt/0-7, be/0-7, idle)", input),
+                Color::LightBlue,
+            ));
+        }
+        if let Some(input) = &state.oom_adj_input {
+            lines.push(field(
+                "New OOM",
+                format!(
+                    "{}_  (-1000 never killed .. 1000 killed first, lowering needs privileges)",
+                    input
+                ),
+                Color::LightRed,
+            ));
+        }
+        if let Some(trace) = state.wait_trace.as_ref().filter(|trace| trace.pid == pid) {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    " Blocked on ({} samples, every {}ms)",
+                    trace.samples,
+                    WAIT_TRACE_INTERVAL.as_millis()
+                ),
+                Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            if let Some(e) = &trace.error {
+                lines.push(field(
+                    "Error",
+                    capabilities::explain(e, Capability::OpenFiles),
+                    Color::Red,
+                ));
+            }
+            for (wait, share) in trace.top(WAIT_TRACE_TOP) {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(" {:>5.1}%    ", share * 100.0),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(wait.to_string(), Style::default().fg(Color::Cyan)),
+                ]));
+            }
+            if let Some(last) = &trace.last {
+                let stack = if !last.stack.is_empty() {
+                    last.stack
+                        .iter()
+                        .take(WAIT_TRACE_FRAMES)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" < ")
+                } else if !state.capabilities.allows(Capability::KernelStacks) {
+                    Capability::KernelStacks.to_string()
+                } else {
+                    "-".to_string()
+                };
+                lines.push(field("Stack", stack, Color::Gray));
+            }
+        }
+
+        lines.push(Line::from(""));
+        match process::open_files(pid) {
+            Ok(files) => {
+                lines.push(Line::from(Span::styled(
+                    format!(" Open files ({})", files.len()),
+                    Style::default()
+                        .fg(Color::LightBlue)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                for file in files.iter().take(DETAIL_MAX_FILES) {
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            format!(" {:>5} ", file.fd),
+                            Style::default().fg(Color::Yellow),
+                        ),
+                        Span::styled(
+                            format!("{:<7}", file.kind.label()),
+                            Style::default().fg(Color::Cyan),
+                        ),
+                        Span::styled(format!("{:<3}", file.mode), Style::default().fg(Color::Red)),
+                        Span::styled(file.path.clone(), Style::default().fg(Color::Green)),
+                    ]));
+                }
+                if files.len() > DETAIL_MAX_FILES {
+                    lines.push(Line::from(Span::styled(
+                        format!(" ... and {} more", files.len() - DETAIL_MAX_FILES),
+                        Style::default().fg(Color::Gray),
+                    )));
+                }
+            }
+            Err(e) => lines.push(field("Open files", e.to_string(), Color::Gray)),
+        }
+    }
+
+    let editing = state.affinity_input.is_some()
+        || state.io_priority_input.is_some()
+        || state.oom_adj_input.is_some();
+    let footer = match (&state.status_message, editing) {
+        (Some(message), _) => format!(" {} ", message),
+        (None, true) => " Enter: apply | Esc: cancel ".to_string(),
+        (None, false) if state.remote => " Esc: close | remote, read-only ".to_string(),
+        (None, false) if state.read_only => {
+            " e: environment | w: wait trace | Esc: close | read-only ".to_string()
+        }
+        (None, false) => {
+            " a: affinity | i: I/O priority | o: OOM adj | e: environment | w: wait trace | Esc: close "
+                .to_string()
+        }
+    };
+
+    let popup_area = centered_rect(96, lines.len() as u16 + 2, area);
+    let block = focus_border(
+        Block::default()
+            .title(format!(" Process {} ", pid))
+            .title_bottom(footer)
+            .borders(Borders::ALL),
+        Color::LightMagenta,
+    );
+    let inner = block.inner(popup_area);
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+
+    // Sparklines shift every second, too much churn in accessible mode
+    let Some(row) = history_row.filter(|_| !accessible()) else {
+        return;
+    };
+    // CPU in tenths of a percent, memory above the minute's low so changes show
+    let cpu: Vec<u64> = history.cpu.iter().map(|&cpu| (cpu * 10.0) as u64).collect();
+    let memory: Vec<u64> = history
+        .memory_mb
+        .iter()
+        .map(|&mb| ((mb - memory_min) * 1024.0) as u64)
+        .collect();
+    let label_width = 11;
+    let width = (SELECTED_HISTORY_CAPACITY as u16).min(inner.width.saturating_sub(label_width));
+    for (offset, data, color) in [(0, &cpu, Color::Red), (1, &memory, Color::Blue)] {
+        let y = inner.y + (row + offset) as u16;
+        if y >= inner.bottom() {
+            break;
+        }
+        frame.render_widget(
+            Sparkline::default()
+                .data(tail(data, width))
+                .style(Style::default().fg(color)),
+            Rect::new(inner.x + label_width, y, width, 1),
+        );
+    }
+}
+
+fn render_env_popup(frame: &mut Frame, state: &AppState, pid: Pid, view: &EnvView, area: Rect) {
+    let popup_area = centered_rect(100, area.height.saturating_sub(4), area);
+    let vars = view.filtered();
+
+    let search = if view.searching {
+        format!(" /{}_ ", view.search)
+    } else if !view.search.is_empty() {
+        format!(" /{} ", view.search)
+    } else {
+        String::new()
+    };
+    let footer = match &state.status_message {
+        Some(message) => format!(" {} ", message),
+        None if view.reveal => " /: search | v: mask secrets | Esc: back ".to_string(),
+        None => " /: search | v: reveal secrets | Esc: back ".to_string(),
+    };
+    let block = focus_border(
+        Block::default()
+            .title(format!(
+                " Environment of {} ({}/{}){}",
+                pid,
+                vars.len(),
+                view.vars.len(),
+                search
+            ))
+            .title_bottom(footer)
+            .borders(Borders::ALL),
+        Color::LightMagenta,
+    );
+
+    let lines: Vec<Line> = if view.vars.is_empty() {
+        vec![Line::from(Span::styled(
+            " Environment not readable (other user's process?)",
+            Style::default().fg(Color::Gray),
+        ))]
+    } else {
+        vars.iter()
+            .skip(view.scroll)
+            .map(|(name, value)| {
+                let (value, color) = if view.shows_value(name) {
+                    (value.clone(), Color::White)
+                } else {
+                    ("********".to_string(), Color::Red)
+                };
+                Line::from(vec![
+                    Span::styled(format!(" {}", name), Style::default().fg(Color::Green)),
+                    Span::styled("=", Style::default().fg(Color::Gray)),
+                    Span::styled(value, Style::default().fg(color)),
+                ])
+            })
+            .collect()
+    };
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+fn render_governor_popup(frame: &mut Frame, state: &AppState, menu: &GovernorMenu, area: Rect) {
+    let policies = &state.cpu_info.policies;
+    let target = menu.policy.and_then(|i| policies.get(i));
+    let popup_area = centered_rect(50, (policies.len() + menu.governors.len()) as u16 + 3, area);
+
+    let footer = if state.read_only {
+        " read-only | Esc: close ".to_string()
+    } else if !menu.can_switch {
+        format!(" {} | Esc: close ", Capability::CpuFreq.hint())
+    } else if policies.len() > 1 {
+        " Enter: apply | ←/→: policy | Esc: close ".to_string()
+    } else {
+        " Enter: apply | Esc: close ".to_string()
+    };
+    let block = focus_border(
+        Block::default()
+            .title(format!(
+                " CPU Governor: {} ",
+                target.map_or("all CPUs".to_string(), |p| format!(
+                    "{} ({})",
+                    p.name,
+                    p.cpu_label()
+                ))
+            ))
+            .title_bottom(footer)
+            .borders(Borders::ALL),
+        Color::LightCyan,
+    );
+    let inner = block.inner(popup_area);
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(policies.len() as u16 + 1),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+
+    // Every policy with what it runs now, the targeted ones highlighted
+    let policy_lines: Vec<Line> = policies
+        .iter()
+        .enumerate()
+        .map(|(i, policy)| {
+            let targeted = menu.policy.is_none_or(|selected| selected == i);
+            let frequency = policy.cur_khz.map_or(String::new(), |khz| {
+                format!(" @ {:.2} GHz", khz as f64 / 1e6)
+            });
+            Line::from(Span::styled(
+                format!(
+                    " {:<9} {:<10} {}{}",
+                    policy.name,
+                    policy.cpu_label(),
+                    policy.governor,
+                    frequency
+                ),
+                Style::default().fg(if targeted {
+                    Color::White
+                } else {
+                    Color::DarkGray
+                }),
+            ))
+        })
+        .collect();
+
+    let current = match target {
+        Some(policy) => Some(policy.governor.as_str()),
+        None => cpufreq::common_governor(policies),
+    };
+    let items: Vec<ListItem> = menu
+        .governors
+        .iter()
+        .map(|governor| {
+            ListItem::new(format!(
+                " {}{}",
+                governor,
+                if current == Some(governor.as_str()) {
+                    " (current)"
+                } else {
+                    ""
+                }
+            ))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(make_highlight_style())
+        .highlight_symbol(">> ");
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+    frame.render_widget(Paragraph::new(policy_lines), sections[0]);
+    frame.render_stateful_widget(
+        list,
+        sections[1],
+        &mut ListState::default().with_selected(Some(menu.selected)),
+    );
+}
+
+fn render_memory_details_popup(frame: &mut Frame, details: &MemoryDetails, area: Rect) {
+    let field = |label: &str, value: String, color: Color| {
+        Line::from(vec![
+            Span::styled(format!(" {:<13}", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    };
+
+    let mut lines = vec![match &details.huge_pages {
+        Some(huge) => field(
+            "Huge pages",
+            format!(
+                "{} of {} free ({} pages), {} reserved, {} surplus",
+                huge.free,
+                huge.total,
+                units::format_size(huge.page_size),
+                huge.reserved,
+                huge.surplus
+            ),
+            Color::LightCyan,
+        ),
+        None => field("Huge pages", "not available".to_string(), Color::Gray),
+    }];
+    if let Some(mode) = &details.transparent_mode {
+        let anon = details.huge_pages.map_or(0, |huge| huge.anon_transparent);
+        lines.push(field(
+            "Transparent",
+            format!(
+                "{}, {} in anonymous mappings",
+                mode,
+                units::format_size(anon)
+            ),
+            Color::LightCyan,
+        ));
+    }
+
+    lines.push(Line::from(""));
+    if details.zones.is_empty() {
+        lines.push(field(
+            "Fragmentation",
+            "/proc/buddyinfo not available".to_string(),
+            Color::Gray,
+        ));
+    } else {
+        let huge_block = details.page_size << memory::HUGE_PAGE_ORDER;
+        let orders = details
+            .zones
+            .iter()
+            .map(|zone| zone.free_blocks.len())
+            .max()
+            .unwrap_or(0);
+        let mut header = format!(
+            " {:<13}{:>9} {:>7}  ",
+            "Zone",
+            "Free",
+            format!(">={}M", huge_block / 1024 / 1024)
+        );
+        // "4K" .. "4M", format_size's decimals don't fit the columns
+        for order in 0..orders {
+            let kib = (details.page_size << order) / 1024;
+            let size = if kib >= 1024 {
+                format!("{}M", kib / 1024)
+            } else {
+                format!("{}K", kib)
+            };
+            header.push_str(&format!("{:>6}", size));
+        }
+        lines.push(Line::from(Span::styled(
+            header,
             Style::default()
-                .fg(Color::Magenta)
+                .fg(Color::LightBlue)
                 .add_modifier(Modifier::BOLD),
-        )),
-    ])
-    .height(1)
-    .bottom_margin(1);
+        )));
+
+        for zone in &details.zones {
+            // Free memory that can't back a huge page is what fragmentation costs
+            let large = zone.free_at_least(memory::HUGE_PAGE_ORDER);
+            let color = match large {
+                l if l < 0.2 => Color::LightRed,
+                l if l < 0.5 => Color::Yellow,
+                _ => Color::Green,
+            };
+            let counts: String = zone
+                .free_blocks
+                .iter()
+                .map(|&count| format!("{:>6}", format_count(count as f64)))
+                .collect();
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(" {:<13}", format!("{} {}", zone.node, zone.zone)),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    format!(
+                        "{:>9}",
+                        units::format_size(zone.free_pages() * details.page_size)
+                    ),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    format!(" {:>6.0}%  ", large * 100.0),
+                    Style::default().fg(color),
+                ),
+                Span::styled(counts, Style::default().fg(Color::White)),
+            ]));
+        }
+    }
 
-    // Create table rows
-    let rows = processes
-        .iter()
-        .enumerate()
-        .skip(adjusted_scroll)
-        .take(max_items)
-        .map(|(i, process)| {
-            let is_selected = i == selected_process;
+    let popup_area = centered_rect(100, lines.len() as u16 + 2, area);
+    let block = focus_border(
+        Block::default()
+            .title(" Memory Details ")
+            .title_bottom(" free blocks per size from the buddy allocator | Esc: close ")
+            .borders(Borders::ALL),
+        Color::Yellow,
+    );
 
-            let style = if is_selected {
-                make_highlight_style()
-            } else {
-                Style::default()
-            };
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
 
-            let status_str = match process.status {
-                ProcessStatus::Run => "Running",
-                ProcessStatus::Sleep => "Sleeping",
-                ProcessStatus::Idle => "Idle",
-                ProcessStatus::Zombie => "Zombie",
-                ProcessStatus::Dead => "Dead",
-                ProcessStatus::Stop => "Stopped",
-                _ => "Unknown",
-            };
+fn render_hardware_popup(frame: &mut Frame, hardware: &HardwareInfo, area: Rect) {
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!(" {:<12}", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ])
+    };
+    let unknown = |value: &str| {
+        if value.is_empty() {
+            "unknown".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let mut lines = vec![
+        row("Model", unknown(&hardware.model)),
+        row("Vendor", unknown(&hardware.vendor)),
+        row("Frequency", format!("{} MHz", hardware.frequency_mhz)),
+    ];
+    let count = |n: usize, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+    let sockets = hardware.topology.as_ref().map_or(1, |t| t.sockets);
+    let cores = match hardware.physical_cores {
+        Some(cores) => format!(
+            "{}, {}, {}",
+            count(sockets, "socket"),
+            count(cores, "core"),
+            count(hardware.threads, "thread")
+        ),
+        None => count(hardware.threads, "thread"),
+    };
+    lines.push(row("Topology", cores));
+    match &hardware.topology {
+        Some(topology) => {
+            for cache in &topology.caches {
+                let kind = match cache.kind.as_str() {
+                    "Data" => "d",
+                    "Instruction" => "i",
+                    _ => "",
+                };
+                lines.push(row(
+                    &format!("L{}{} cache", cache.level, kind),
+                    format!(
+                        "{} x {}",
+                        cache.instances,
+                        units::format_size(cache.size_bytes)
+                    ),
+                ));
+            }
+            lines.push(row(
+                "Microcode",
+                topology
+                    .microcode
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+            ));
+        }
+        None => lines.push(Line::from(Span::styled(
+            " caches and microcode are only read on Linux",
+            Style::default().fg(Color::Gray),
+        ))),
+    }
 
-            let parent_str = process
-                .parent_pid
-                .map_or("None".to_string(), |pid| pid.to_string());
+    let popup_area = centered_rect(64, lines.len() as u16 + 2, area);
+    let block = focus_border(
+        Block::default()
+            .title(" Hardware ")
+            .title_bottom(" read at startup | Esc: close ")
+            .borders(Borders::ALL),
+        Color::Yellow,
+    );
 
-            // Truncate name if needed
-            let name = if process.name.len() > 15 {
-                format!("{}...", &process.name[..12])
-            } else {
-                process.name.clone()
-            };
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
 
-            Row::new(vec![
-                Cell::from(Span::styled(
-                    process.pid.to_string(),
-                    Style::default().fg(Color::Yellow),
-                )),
-                Cell::from(Span::styled(name, Style::default().fg(Color::Green))),
-                Cell::from(Span::styled(
-                    format!("{:.1}%", process.cpu_usage),
-                    Style::default().fg(Color::Red),
-                )),
-                Cell::from(Span::styled(
-                    format!("{:.2}MB", process.memory_mb),
-                    Style::default().fg(Color::Blue),
-                )),
-                Cell::from(Span::styled(status_str, Style::default().fg(Color::Cyan))),
-                Cell::from(Span::styled(
-                    parent_str,
-                    Style::default().fg(Color::Magenta),
-                )),
-            ])
-            .style(style)
-        });
+fn render_diagnostics_popup(frame: &mut Frame, budget: &CollectorBudget, area: Rect) {
+    let ms = |duration: Duration| format!("{:.1}", duration.as_secs_f64() * 1000.0);
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            " {:<16}{:>10}{:>10}{:>9}{:>9}{:>9}{:>8}{:>7}",
+            "Sampler", "Interval", "Measured", "Last ms", "Avg ms", "Max ms", "Runs", "Slow"
+        ),
+        Style::default()
+            .fg(Color::LightBlue)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    for (name, cost) in budget.collectors() {
+        let interval = cost.base * cost.slowdown;
+        let measured = cost
+            .period
+            .map_or("-".to_string(), history::format_interval);
+        // Share of its interval the sampler spends running
+        let share = cost.average().as_secs_f64() / interval.as_secs_f64().max(0.001);
+        let color = match share {
+            s if s >= 0.1 => Color::LightRed,
+            s if s >= 0.05 => Color::Yellow,
+            _ => Color::Green,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {:<16}", name), Style::default().fg(Color::Gray)),
+            Span::styled(
+                format!("{:>10}{:>10}", history::format_interval(interval), measured),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                format!(
+                    "{:>9}{:>9}{:>9}",
+                    ms(cost.last),
+                    ms(cost.average()),
+                    ms(cost.max)
+                ),
+                Style::default().fg(color),
+            ),
+            Span::styled(
+                format!("{:>8}{:>6}x", cost.runs, cost.slowdown),
+                Style::default().fg(if cost.slowdown > 1 {
+                    Color::Yellow
+                } else {
+                    Color::White
+                }),
+            ),
+        ]));
+    }
+    if lines.len() == 1 {
+        lines.push(Line::from(Span::styled(
+            " no sampler has run yet",
+            Style::default().fg(Color::Gray),
+        )));
+    }
 
-    let table = Table::new(rows.collect::<Vec<_>>(), widths)
-        .header(header)
+    let popup_area = centered_rect(84, lines.len() as u16 + 2, area);
+    let title = format!(
+        " first runs staggered by {}ms | Esc: close ",
+        selfmon::STAGGER_STEP.as_millis()
+    );
+    let block = focus_border(
+        Block::default()
+            .title(" Sampler Diagnostics ")
+            .title_bottom(title)
+            .borders(Borders::ALL),
+        Color::Yellow,
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+fn render_chart_options_popup(frame: &mut Frame, selected: usize, area: Rect) {
+    let popup_area = centered_rect(40, CHART_PRESETS.len() as u16 + 4, area);
+
+    let block = focus_border(
+        Block::default()
+            .title(" Chart Options ")
+            .title_bottom(" Enter: apply | Esc: close ")
+            .borders(Borders::ALL),
+        Color::LightCyan,
+    );
+
+    let items: Vec<ListItem> = CHART_PRESETS
+        .iter()
+        .map(|preset| ListItem::new(format!(" {}", preset.label())))
+        .collect();
+
+    let list = List::new(items)
         .block(block)
-        .widths(widths)
-        .column_spacing(2)
-        .row_highlight_style(make_highlight_style()) // Use your custom style here
+        .highlight_style(make_highlight_style())
         .highlight_symbol(">> ");
 
-    let selected_position =
-        if selected_process >= adjusted_scroll && selected_process < adjusted_scroll + max_items {
-            Some(selected_process - adjusted_scroll)
-        } else {
-            None
-        };
-
+    frame.render_widget(Clear, popup_area);
     frame.render_stateful_widget(
-        table,
-        area,
-        &mut TableState::default().with_selected(selected_position),
+        list,
+        popup_area,
+        &mut ListState::default().with_selected(Some(selected)),
     );
 }
 
-fn render_memory_section(frame: &mut Frame, memory_info: &MemoryInfo, area: Rect) {
+fn render_top_processes(frame: &mut Frame, state: &AppState, area: Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let total_memory_mb = state.memory_info.total_memory as f64 / 1024.0 / 1024.0;
+
+    let top_cpu: Vec<(String, f64, String)> = process::top_by_cpu(&state.all_processes, TOP_N)
+        .into_iter()
+        .map(|p| {
+            (
+                p.name.clone(),
+                p.cpu_usage as f64 / 100.0,
+                format!("{:.1}%", p.cpu_usage),
+            )
+        })
+        .collect();
+    let top_memory: Vec<(String, f64, String)> =
+        process::top_by_memory(&state.all_processes, TOP_N)
+            .into_iter()
+            .map(|p| {
+                (
+                    p.name.clone(),
+                    p.memory_mb / total_memory_mb.max(1.0),
+                    units::format_mb(p.memory_mb),
+                )
+            })
+            .collect();
+
+    render_top_list(frame, " Top CPU ", &top_cpu, Color::Red, layout[0]);
+    render_top_list(frame, " Top Memory ", &top_memory, Color::Blue, layout[1]);
+    if !state.freshness.collected("processes") {
+        for area in layout.iter() {
+            render_collecting(frame, *area);
+        }
+    }
+}
+
+/// Compact list of (name, fraction 0-1, value label) rows with a text bar each
+fn render_top_list(
+    frame: &mut Frame,
+    title: &str,
+    entries: &[(String, f64, String)],
+    color: Color,
+    area: Rect,
+) {
     let block = Block::default()
-        .title(" Memory Usage ")
+        .title(title)
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(color));
 
-    // Create layout with left padding
-    let inner_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Length(4), // Left padding
-            Constraint::Min(1),    // Content area
-        ])
-        .split(block.inner(area));
+    let inner_width = block.inner(area).width as usize;
+    // name | bar | value, the bar gets whatever is left
+    let name_width = 12;
+    let value_width = 7;
+    let bar_width = inner_width.saturating_sub(name_width + value_width + 2);
+
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|(name, fraction, value)| {
+            let name: String = name.chars().take(name_width).collect();
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<width$} ", name, width = name_width),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(mini_bar(*fraction, bar_width), Style::default().fg(color)),
+                Span::styled(
+                    format!(" {:>width$}", value, width = value_width),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
 
-    // Render the text content with proper padding
-    render_memory_usage(frame, memory_info, inner_layout[1]);
+pub fn mini_bar(fraction: f64, width: usize) -> String {
+    let filled = ((fraction.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
 
-    // Render the outer block
-    frame.render_widget(block, area);
+/// (x, y) points for a line chart, smoothed with the EMA weight when given
+pub fn chart_points(values: impl Iterator<Item = f64>, smoothing: Option<f64>) -> Vec<(f64, f64)> {
+    let values: Vec<f64> = match smoothing {
+        Some(alpha) => history::smooth(values, alpha),
+        None => values.collect(),
+    };
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(x, y)| (x as f64, y))
+        .collect()
 }
 
-fn render_memory_usage(frame: &mut Frame, memory_info: &MemoryInfo, area: Rect) {
-    let memory_percent = memory_info.current_memory_percent();
-    let swap_percent = memory_info.current_swap_percent();
+/// Best fuzzy score over the name and the command line
+fn fuzzy_score(query: &str, process: &Process) -> Option<i64> {
+    let name = fuzzy::find(query, &process.name).map(|m| m.score);
+    let cmdline = fuzzy::find(query, &process.cmdline).map(|m| m.score);
+    name.max(cmdline)
+}
 
-    // Text with empty line between Memory and Swap
-    let text = vec![
-        Line::from(vec![
-            Span::styled("Memory: ", Style::default().fg(Color::LightBlue)),
+/// Process table columns for the current toggles, also what gets exported
+pub fn visible_columns(state: &AppState) -> Vec<Column> {
+    let mut columns = vec![
+        Column::Pid,
+        Column::Name,
+        Column::Cpu,
+        Column::Time,
+        Column::Rss,
+    ];
+    if state.show_delta_columns {
+        columns.extend([Column::MemDelta, Column::CpuDelta]);
+    }
+    // Only worth the room on systems that have swap at all
+    if state.memory_info.total_swap > 0 {
+        columns.push(Column::Swap);
+    }
+    if state.processes.iter().any(|p| p.power_mw.is_some()) {
+        columns.push(Column::Energy);
+    }
+    columns.extend([Column::Status, Column::Parent]);
+    if state.show_group_columns {
+        columns.extend([Column::Pgid, Column::Sid]);
+    }
+    if state.show_fd_column {
+        columns.push(Column::Fds);
+    }
+    if state.show_event_columns {
+        columns.extend(Column::EVENTS);
+    }
+    if state.show_pod_column {
+        columns.push(Column::Pod);
+    }
+    if state.show_path_columns {
+        columns.extend([Column::Cwd, Column::Exe]);
+    }
+    columns.push(Column::Origin);
+    columns
+}
+
+/// Where presets are read from, for hints
+fn config_path_label() -> String {
+    config::default_path().map_or("config.toml".to_string(), |path| path.display().to_string())
+}
+
+pub fn threshold_color(level: UsageLevel) -> Color {
+    // Fewer, stronger colors instead of the green to red ramp
+    if accessible() {
+        return match level {
+            UsageLevel::Normal => Color::White,
+            UsageLevel::Warning => Color::LightYellow,
+            UsageLevel::Critical => Color::LightRed,
+        };
+    }
+    match level {
+        UsageLevel::Normal => Color::Green,
+        UsageLevel::Warning => Color::Yellow,
+        UsageLevel::Critical => Color::Red,
+    }
+}
+
+/// Last `width` points, so sparklines show the most recent history
+pub fn tail(data: &[u64], width: u16) -> &[u64] {
+    &data[data.len().saturating_sub(width as usize)..]
+}
+
+fn render_kernel_section(
+    frame: &mut Frame,
+    kernel: &KernelStats,
+    freshness: Line<'static>,
+    area: Rect,
+) {
+    let block = Block::default()
+        .title(" Kernel ")
+        .title(freshness)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightYellow));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if !kernel.available {
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                " /proc/stat not available",
+                Style::default().fg(Color::Gray),
+            )),
+            inner,
+        );
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1); 4])
+        .split(inner);
+    let columns = |row: Rect| {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(17), Constraint::Min(0)])
+            .split(row)
+    };
+
+    // Spikes are what this panel is for, make them stand out
+    let switch_color = if kernel.context_switch_spike() {
+        Color::LightRed
+    } else {
+        Color::LightYellow
+    };
+    for (row, label, rate, history, color) in [
+        (
+            rows[0],
+            "ctxt/s",
+            kernel.context_switches,
+            &kernel.context_switch_history,
+            switch_color,
+        ),
+        (
+            rows[1],
+            "intr/s",
+            kernel.interrupts,
+            &kernel.interrupt_history,
+            Color::LightCyan,
+        ),
+    ] {
+        let parts = columns(row);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(format!(" {:<7}", label), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format!("{:>8}", format_count(rate)),
+                    Style::default().fg(color),
+                ),
+            ])),
+            parts[0],
+        );
+        if !accessible() {
+            let data: Vec<u64> = history.iter().copied().collect();
+            frame.render_widget(
+                Sparkline::default()
+                    .data(tail(&data, parts[1].width))
+                    .style(Style::default().fg(color)),
+                parts[1],
+            );
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(" forks/s ", Style::default().fg(Color::Gray)),
             Span::styled(
-                format!("{:.1}%", memory_percent),
-                Style::default()
-                    .fg(Color::LightBlue)
-                    .add_modifier(Modifier::BOLD),
+                format!("{:<6.1}", kernel.forks),
+                Style::default().fg(Color::White),
             ),
-        ]),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("Swap: ", Style::default().fg(Color::LightRed)),
+            Span::styled(" running ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                kernel.running.to_string(),
+                Style::default().fg(Color::LightGreen),
+            ),
+            Span::styled(" blocked ", Style::default().fg(Color::Gray)),
             Span::styled(
-                if memory_info.total_swap > 0 {
-                    format!("{:.1}%", swap_percent)
+                kernel.blocked.to_string(),
+                Style::default().fg(if kernel.blocked > 0 {
+                    Color::LightRed
                 } else {
-                    "N/A".to_string()
-                },
-                Style::default()
-                    .fg(Color::LightRed)
-                    .add_modifier(Modifier::BOLD),
+                    Color::White
+                }),
             ),
-        ]),
-    ];
+        ])),
+        rows[2],
+    );
 
-    // Center vertically
-    let vertical_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(30), // Top padding
-            Constraint::Length(3),      // Text content (3 lines)
-            Constraint::Percentage(30), // Bottom padding
-        ])
-        .split(area);
+    let mut limits = Vec::new();
+    for (name, limit) in kernel.limits.all() {
+        let color = match limit.ratio() {
+            r if r >= kernel::LIMIT_CRITICAL => Color::LightRed,
+            r if r >= kernel::LIMIT_WARNING => Color::Yellow,
+            _ => Color::White,
+        };
+        limits.push(Span::styled(
+            format!(" {} ", name),
+            Style::default().fg(Color::Gray),
+        ));
+        limits.push(Span::styled(
+            format!(
+                "{}/{}",
+                format_count(limit.used as f64),
+                format_count(limit.max as f64)
+            ),
+            Style::default().fg(color),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(limits)), rows[3]);
+}
+
+/// 12345.6 -> "12.3k", counts rather than bytes so always powers of 1000
+fn format_count(value: f64) -> String {
+    match value {
+        v if v >= 1e6 => format!("{:.1}M", v / 1e6),
+        v if v >= 1e3 => format!("{:.1}k", v / 1e3),
+        v => format!("{:.0}", v),
+    }
+}
+
+fn render_lifecycle_section(
+    frame: &mut Frame,
+    lifecycle: &LifecycleTracker,
+    scroll: usize,
+    focused: bool,
+    area: Rect,
+) {
+    let block = panel_border(
+        Block::default()
+            .title(" Process events ")
+            .title(scrolled_title(scroll))
+            .borders(Borders::ALL),
+        Color::LightYellow,
+        focused,
+    );
 
-    let paragraph = Paragraph::new(text).alignment(Alignment::Left);
+    // Newest on top, older events scroll out at the bottom
+    let items: Vec<ListItem> = lifecycle
+        .events
+        .iter()
+        .rev()
+        .skip(scroll)
+        .take(area.height.saturating_sub(2) as usize)
+        .map(|event| {
+            let color = match event.kind {
+                LifecycleKind::Started => Color::Green,
+                LifecycleKind::Exited => Color::Red,
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{} ", process::format_timestamp(event.time, false)),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(event.to_string(), Style::default().fg(color)),
+            ]))
+        })
+        .collect();
 
-    frame.render_widget(paragraph, vertical_layout[1]);
+    frame.render_widget(List::new(items).block(block), area);
 }
 
-fn render_network_section(frame: &mut Frame, area: Rect) {
+/// Processes whose memory only went up lately, fastest growing first
+fn render_leak_section(frame: &mut Frame, leaks: &LeakDetector, area: Rect) {
     let block = Block::default()
-        .title("Network Usage ")
+        .title(format!(" Leak suspects ({}) ", leaks.suspects.len()))
+        .title_bottom(format!(
+            " ≥{}MB/min over {} ",
+            leaks.min_slope_mb_per_min,
+            history::format_interval(leaks.window)
+        ))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::LightBlue));
+        .border_style(Style::default().fg(Color::LightRed));
 
-    frame.render_widget(block, area);
+    if leaks.suspects.is_empty() {
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                "no steadily growing processes",
+                Style::default().fg(Color::Gray),
+            ))
+            .block(block),
+            area,
+        );
+        return;
+    }
+    let items: Vec<ListItem> = leaks
+        .suspects
+        .iter()
+        .map(|suspect| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<12} ", suspect.name.chars().take(12).collect::<String>()),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(
+                    format!("{:>7} ", suspect.pid),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(
+                    format!("+{:.1}MB/min ", suspect.slope_mb_per_min),
+                    Style::default().fg(Color::LightRed),
+                ),
+                Span::styled(
+                    format!(
+                        "+{} in {}s, now {}",
+                        units::format_mb(suspect.growth_mb),
+                        suspect.span.as_secs(),
+                        units::format_mb(suspect.memory_mb)
+                    ),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+    frame.render_widget(List::new(items).block(block), area);
+}
+
+/// Newest on top, like the process events
+fn render_log_tab(frame: &mut Frame, log: &LogBuffer, scroll: usize, area: Rect) {
+    let log = log.lock().unwrap();
+    let path = logging::default_path()
+        .map(|path| format!(" | {}", path.display()))
+        .unwrap_or_default();
+    let block = Block::default()
+        .title(format!(" Log ({}){} ", log.len(), path))
+        .title(scrolled_title(scroll))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightYellow));
+
+    let items: Vec<ListItem> = log
+        .iter()
+        .rev()
+        .skip(scroll)
+        .take(area.height.saturating_sub(2) as usize)
+        .map(|entry| {
+            let color = match entry.level {
+                tracing::Level::ERROR => Color::Red,
+                tracing::Level::WARN => Color::Yellow,
+                tracing::Level::INFO => Color::LightGreen,
+                _ => Color::Gray,
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{} ", process::format_timestamp(entry.time, false)),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(format!("{:<5} ", entry.level), Style::default().fg(color)),
+                Span::styled(
+                    format!("{:<12} ", entry.target),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(entry.message.clone()),
+            ]))
+        })
+        .collect();
+
+    frame.render_widget(List::new(items).block(block), area);
+}
+
+fn render_sessions_section(
+    frame: &mut Frame,
+    session_info: &SessionInfo,
+    capabilities: &Capabilities,
+    scroll: usize,
+    focused: bool,
+    freshness: Line<'static>,
+    area: Rect,
+) {
+    let failed_str = match session_info.failed_logins {
+        Some(count) => count.to_string(),
+        None if !capabilities.allows(Capability::AuthLog) => {
+            format!("permission denied, {}", Capability::AuthLog.hint())
+        }
+        None => "N/A".to_string(),
+    };
+
+    let block = panel_border(
+        Block::default()
+            .title(format!(
+                " SSH Sessions ({}) | Failed logins since start: {} ",
+                session_info.sessions.len(),
+                failed_str
+            ))
+            .title(freshness)
+            .borders(Borders::ALL),
+        Color::LightGreen,
+        focused,
+    );
+
+    let widths = [
+        Constraint::Length(12), // User
+        Constraint::Length(8),  // TTY
+        Constraint::Min(15),    // Source
+        Constraint::Length(8),  // Duration
+    ];
+
+    let header = Row::new(vec![
+        Cell::from(Span::styled(
+            "USER",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Cell::from(Span::styled(
+            "TTY",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Cell::from(Span::styled(
+            "SOURCE",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Cell::from(Span::styled(
+            "TIME",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        )),
+    ]);
+
+    let rows = session_info.sessions.iter().skip(scroll).map(|session| {
+        Row::new(vec![
+            Cell::from(Span::styled(
+                session.user.clone(),
+                Style::default().fg(Color::Green),
+            )),
+            Cell::from(Span::styled(
+                session.tty.clone(),
+                Style::default().fg(Color::Cyan),
+            )),
+            Cell::from(Span::styled(
+                session.source.clone(),
+                Style::default().fg(Color::Yellow),
+            )),
+            Cell::from(Span::styled(
+                sessions::format_duration(session.duration()),
+                Style::default().fg(Color::Magenta),
+            )),
+        ])
+    });
+
+    let table = Table::new(rows.collect::<Vec<_>>(), widths)
+        .header(header)
+        .block(block)
+        .column_spacing(1);
+
+    frame.render_widget(table, area);
 }
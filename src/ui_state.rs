@@ -0,0 +1,90 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::Theme;
+use crate::process::{SortKey, StatusFilter};
+use crate::tui::Tab;
+
+/// How the view was set up when the last session ended, restored on the next launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub tab: Tab,
+    pub sort_key: SortKey,
+    pub then_sort_key: Option<SortKey>,
+    pub status_filter: StatusFilter,
+    pub name_filter: Option<String>,
+    pub fuzzy_filter: bool,
+    /// `name_filter` is a regex
+    pub regex_filter: bool,
+    /// Name of the config file preset
+    pub filter_preset: Option<String>,
+    pub group_columns: bool,
+    pub fd_column: bool,
+    pub event_columns: bool,
+    pub pod_column: bool,
+    pub path_columns: bool,
+    pub delta_columns: bool,
+    pub cpu_heatmap: bool,
+    /// Fixed color per core instead of coloring by load
+    pub core_colors: bool,
+    pub smooth_charts: bool,
+    pub all_mounts: bool,
+    pub theme: Theme,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            tab: Tab::Overview,
+            sort_key: SortKey::Memory,
+            then_sort_key: None,
+            status_filter: StatusFilter::All,
+            name_filter: None,
+            fuzzy_filter: false,
+            regex_filter: false,
+            filter_preset: None,
+            group_columns: false,
+            fd_column: false,
+            event_columns: false,
+            pod_column: false,
+            path_columns: false,
+            delta_columns: false,
+            cpu_heatmap: false,
+            core_colors: false,
+            smooth_charts: false,
+            all_mounts: false,
+            theme: Theme::Auto,
+        }
+    }
+}
+
+/// $XDG_STATE_HOME/m-as/ui.json, falling back to ~/.local/state
+pub fn default_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(dir.join("m-as").join("ui.json"))
+}
+
+/// The saved state, defaults when there is none yet or it can't be read
+pub fn load(path: &Path) -> UiState {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, state: &UiState) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write then rename, so a crash mid-write never leaves a truncated file behind
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(state)?)?;
+    fs::rename(&temp, path)
+}
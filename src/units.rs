@@ -0,0 +1,135 @@
+use std::sync::OnceLock;
+
+/// Whether throughput is shown in bytes or bits per second
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateUnit {
+    #[default]
+    Bytes,
+    Bits,
+}
+
+/// Powers of 1024 (KiB, MiB) or of 1000 (kB, MB)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Prefix {
+    #[default]
+    Binary,
+    Si,
+}
+
+/// How sizes and rates are formatted in every panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Units {
+    pub rate_unit: RateUnit,
+    pub prefix: Prefix,
+}
+
+static CURRENT: OnceLock<Units> = OnceLock::new();
+
+const BINARY_PREFIXES: [&str; 5] = ["", "Ki", "Mi", "Gi", "Ti"];
+const SI_PREFIXES: [&str; 5] = ["", "k", "M", "G", "T"];
+
+impl Units {
+    /// The configured units, the defaults when nothing was configured
+    pub fn current() -> Units {
+        CURRENT.get().copied().unwrap_or_default()
+    }
+
+    /// Configure the units once at startup, later calls are ignored
+    pub fn set_current(units: Units) {
+        let _ = CURRENT.set(units);
+    }
+
+    fn base(&self) -> f64 {
+        match self.prefix {
+            Prefix::Binary => 1024.0,
+            Prefix::Si => 1000.0,
+        }
+    }
+
+    /// 1536 -> (1.5, 1) with binary prefixes, the index into the prefix table
+    fn scale(&self, mut value: f64) -> (f64, usize) {
+        let mut exponent = 0;
+        while value >= self.base() && exponent < BINARY_PREFIXES.len() - 1 {
+            value /= self.base();
+            exponent += 1;
+        }
+        (value, exponent)
+    }
+
+    fn prefix(&self, exponent: usize) -> &'static str {
+        match self.prefix {
+            Prefix::Binary => BINARY_PREFIXES[exponent],
+            Prefix::Si => SI_PREFIXES[exponent],
+        }
+    }
+
+    fn rate_factor(&self) -> f64 {
+        match self.rate_unit {
+            RateUnit::Bytes => 1.0,
+            RateUnit::Bits => 8.0,
+        }
+    }
+
+    fn rate_suffix(&self) -> &'static str {
+        match self.rate_unit {
+            RateUnit::Bytes => "B/s",
+            RateUnit::Bits => "bit/s",
+        }
+    }
+
+    /// Storage and memory sizes, always in bytes: "512B", "1.5GiB", "1.6GB"
+    pub fn size(&self, bytes: u64) -> String {
+        let (value, exponent) = self.scale(bytes as f64);
+        format!(
+            "{}{}B",
+            format_value(value, exponent),
+            self.prefix(exponent)
+        )
+    }
+
+    /// Throughput: "1.5MiB/s", or "12.6Mbit/s" with bits and SI prefixes
+    pub fn rate(&self, bytes_per_sec: u64) -> String {
+        let (value, exponent) = self.scale(bytes_per_sec as f64 * self.rate_factor());
+        format!(
+            "{}{}{}",
+            format_value(value, exponent),
+            self.prefix(exponent),
+            self.rate_suffix()
+        )
+    }
+
+    /// Upper bound for a throughput chart: `max` rounded up to 1, 2 or 5 times a
+    /// power of the prefix base in display units, so the axis labels stay round
+    pub fn rate_axis_max(&self, max_bytes_per_sec: u64) -> u64 {
+        let (value, exponent) = self.scale(max_bytes_per_sec as f64 * self.rate_factor());
+        let nice = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0]
+            .into_iter()
+            .find(|step| *step >= value)
+            .unwrap_or(value);
+        (nice * self.base().powi(exponent as i32) / self.rate_factor()).ceil() as u64
+    }
+}
+
+/// No decimals for whole units or values that are already three digits wide
+fn format_value(value: f64, exponent: usize) -> String {
+    if exponent == 0 || value >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// `bytes` in the configured units
+pub fn format_size(bytes: u64) -> String {
+    Units::current().size(bytes)
+}
+
+/// `bytes_per_sec` in the configured units
+pub fn format_rate(bytes_per_sec: u64) -> String {
+    Units::current().rate(bytes_per_sec)
+}
+
+/// Sizes the samplers track in megabytes
+pub fn format_mb(megabytes: f64) -> String {
+    format_size((megabytes * 1024.0 * 1024.0) as u64)
+}
@@ -0,0 +1,156 @@
+//! `møas watch <pid|name>`: one line per interval about a single process and
+//! its children. Exits with status 1 once the process is gone, so wrapper
+//! scripts around long jobs notice.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::eyre::bail;
+use color_eyre::Result;
+use sysinfo::{Pid, ProcessStatus};
+
+use crate::platform::IoCounters;
+use crate::process::{self, Process};
+use crate::units;
+
+pub fn run(target: &str, interval: Duration) -> Result<()> {
+    let mut processes = own_processes();
+    let watched = find(&processes, target)?;
+    let (pid, name) = (watched.pid, watched.name.clone());
+    let mut stdout = io::stdout().lock();
+    writeln!(
+        stdout,
+        "Watching {} ({}): {}",
+        name,
+        pid,
+        if watched.cmdline.is_empty() {
+            &name
+        } else {
+            &watched.cmdline
+        }
+    )?;
+
+    let mut last_times: HashMap<Pid, u64> =
+        processes.iter().map(|p| (p.pid, p.cpu_time_ms)).collect();
+    let mut last_io = process::io_counters(pid).ok();
+    let mut last_at = Instant::now();
+    loop {
+        thread::sleep(interval);
+        processes = own_processes();
+        process::fill_cpu_usage(&mut processes, &last_times, last_at.elapsed());
+        let elapsed = last_at.elapsed();
+        last_at = Instant::now();
+        last_times = processes.iter().map(|p| (p.pid, p.cpu_time_ms)).collect();
+
+        // A reused PID with a different name is a different process
+        let Some(watched) = processes
+            .iter()
+            .find(|p| p.pid == pid && p.name == name && p.status != ProcessStatus::Zombie)
+        else {
+            let _ = writeln!(
+                stdout,
+                "{}. {} ({}) exited.",
+                process::format_timestamp(SystemTime::now(), false),
+                name,
+                pid
+            );
+            let _ = stdout.flush();
+            std::process::exit(1);
+        };
+        let io = process::io_counters(pid).ok();
+        let line = summary(watched, &processes, last_io.zip(io), elapsed);
+        last_io = io;
+
+        // Reader went away (e.g. `| head`), that's a normal way to stop
+        match writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+    }
+}
+
+/// Processes without threads and without this one, which would match its own name
+fn own_processes() -> Vec<Process> {
+    let own = Pid::from_u32(std::process::id());
+    process::get_all_processes()
+        .into_iter()
+        .filter(|p| p.thread_kind.is_none() && p.pid != own)
+        .collect()
+}
+
+/// A PID, or a name that only one process has
+fn find<'a>(processes: &'a [Process], target: &str) -> Result<&'a Process> {
+    if let Ok(pid) = target.parse::<u32>() {
+        return match processes.iter().find(|p| p.pid == Pid::from_u32(pid)) {
+            Some(process) => Ok(process),
+            None => bail!("no process with PID {}", pid),
+        };
+    }
+    let matches: Vec<&Process> = processes.iter().filter(|p| p.name == target).collect();
+    match matches.as_slice() {
+        [] => bail!("no process named '{}'", target),
+        [process] => Ok(process),
+        several => bail!(
+            "{} processes are named '{}' ({}), pass a PID instead",
+            several.len(),
+            target,
+            several
+                .iter()
+                .map(|p| p.pid.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// "14:02:11. CPU 12.5%, memory 300MiB, read 1.2MiB/s, write 0B/s. 2 children, CPU 5.0%, memory 120MiB."
+fn summary(
+    watched: &Process,
+    processes: &[Process],
+    io: Option<(IoCounters, IoCounters)>,
+    elapsed: Duration,
+) -> String {
+    let mut line = format!(
+        "{}. CPU {:.1}%, memory {}",
+        process::format_timestamp(SystemTime::now(), false),
+        watched.cpu_usage,
+        units::format_mb(watched.memory_mb)
+    );
+    match io {
+        Some((before, now)) => {
+            let rate = |before: u64, now: u64| {
+                (now.saturating_sub(before) as f64 / elapsed.as_secs_f64().max(0.001)) as u64
+            };
+            let _ = write!(
+                line,
+                ", read {}, write {}",
+                units::format_rate(rate(before.read_bytes, now.read_bytes)),
+                units::format_rate(rate(before.written_bytes, now.written_bytes))
+            );
+        }
+        // /proc/<pid>/io of other users' processes needs root
+        None => line.push_str(", I/O not readable"),
+    }
+
+    let children = process::descendants(processes, watched.pid);
+    if children.is_empty() {
+        line.push_str(". No children.");
+    } else {
+        let _ = write!(
+            line,
+            ". {} {}, CPU {:.1}%, memory {}.",
+            children.len(),
+            if children.len() == 1 {
+                "child"
+            } else {
+                "children"
+            },
+            children.iter().map(|p| p.cpu_usage).sum::<f32>(),
+            units::format_mb(children.iter().map(|p| p.memory_mb).sum())
+        );
+    }
+    line
+}